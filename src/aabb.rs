@@ -0,0 +1,203 @@
+use crate::{ray::Ray, vector::Vector3D};
+
+fn component_min(a: &Vector3D, b: &Vector3D) -> Vector3D {
+    Vector3D::new(a.x().min(b.x()), a.y().min(b.y()), a.z().min(b.z()))
+}
+
+fn component_max(a: &Vector3D, b: &Vector3D) -> Vector3D {
+    Vector3D::new(a.x().max(b.x()), a.y().max(b.y()), a.z().max(b.z()))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Aabb {
+    min: Vector3D,
+    max: Vector3D,
+}
+
+impl Aabb {
+    pub fn new(a: &Vector3D, b: &Vector3D) -> Self {
+        Aabb {
+            min: component_min(a, b),
+            max: component_max(a, b),
+        }
+    }
+
+    // Bounds for shapes that extend forever, like planes
+    pub fn infinite() -> Self {
+        Aabb {
+            min: Vector3D::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            max: Vector3D::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+        }
+    }
+
+    pub fn from_points<'a>(points: impl IntoIterator<Item = &'a Vector3D>) -> Option<Self> {
+        points
+            .into_iter()
+            .fold(None, |bounds: Option<Aabb>, point| {
+                Some(match bounds {
+                    Some(bounds) => Aabb::new(
+                        &component_min(&bounds.min, point),
+                        &component_max(&bounds.max, point),
+                    ),
+                    None => Aabb::new(point, point),
+                })
+            })
+    }
+
+    pub fn min(&self) -> &Vector3D {
+        &self.min
+    }
+
+    pub fn max(&self) -> &Vector3D {
+        &self.max
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: component_min(&self.min, &other.min),
+            max: component_max(&self.max, &other.max),
+        }
+    }
+
+    // Shapes like planes have no finite bounds to sort them by
+    pub fn is_finite(&self) -> bool {
+        [self.min.x(), self.min.y(), self.min.z()]
+            .into_iter()
+            .chain([self.max.x(), self.max.y(), self.max.z()])
+            .all(f64::is_finite)
+    }
+
+    pub fn centroid(&self) -> Vector3D {
+        (&self.min + &self.max) * 0.5
+    }
+
+    pub fn hit(&self, ray: &Ray) -> bool {
+        self.entry_distance(ray).is_some()
+    }
+
+    // Distance along the ray to where it enters the box, zero when it starts inside.
+    // Slab method, see https://tavianator.com/2011/ray_box.html
+    pub fn entry_distance(&self, ray: &Ray) -> Option<f64> {
+        let axes = [
+            (ray.start.x(), ray.direction.x(), self.min.x(), self.max.x()),
+            (ray.start.y(), ray.direction.y(), self.min.y(), self.max.y()),
+            (ray.start.z(), ray.direction.z(), self.min.z(), self.max.z()),
+        ];
+
+        let mut near = f64::NEG_INFINITY;
+        let mut far = f64::INFINITY;
+
+        for (start, direction, min, max) in axes {
+            // A ray parallel to a slab never crosses it, so it only matters whether the ray
+            // starts between its planes. Skipping the reciprocal avoids 0 * infinity = NaN.
+            if direction == 0. {
+                if start < min || start > max {
+                    return None;
+                }
+
+                continue;
+            }
+
+            let reciprocal = 1. / direction;
+            let t1 = (min - start) * reciprocal;
+            let t2 = (max - start) * reciprocal;
+
+            near = near.max(t1.min(t2));
+            far = far.min(t1.max(t2));
+        }
+
+        (far >= near.max(0.)).then_some(near.max(0.))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case((0.0, 0.0, -5.0), (0.0, 0.0, 1.0), true   ; "ray aimed at box hits")]
+    #[test_case((0.0, 0.0, -5.0), (0.0, 0.0, -1.0), false ; "ray aimed away from box misses")]
+    #[test_case((0.0, 5.0, -5.0), (0.0, 0.0, 1.0), false  ; "axis aligned ray beside box misses")]
+    #[test_case((-5.0, -5.0, -5.0), (1.0, 1.0, 1.0), true ; "diagonal ray hits box")]
+    #[test_case((0.0, 0.0, 0.0), (1.0, 0.0, 0.0), true    ; "ray starting inside box hits")]
+    #[test_case((1.0, 0.0, -5.0), (0.0, 0.0, 1.0), true   ; "ray grazing box face hits")]
+    fn test_aabb_hit(start: (f64, f64, f64), direction: (f64, f64, f64), expected: bool) {
+        let aabb = Aabb::new(
+            &Vector3D::new(-1.0, -1.0, -1.0),
+            &Vector3D::new(1.0, 1.0, 1.0),
+        );
+        let ray = Ray::new(
+            &Vector3D::new(start.0, start.1, start.2),
+            &Vector3D::new(direction.0, direction.1, direction.2),
+        );
+
+        assert_eq!(aabb.hit(&ray), expected);
+    }
+
+    #[test_case((0.0, 0.0, -5.0), (0.0, 0.0, 1.0) ; "infinite box is always hit")]
+    fn test_aabb_infinite_hit(start: (f64, f64, f64), direction: (f64, f64, f64)) {
+        let ray = Ray::new(
+            &Vector3D::new(start.0, start.1, start.2),
+            &Vector3D::new(direction.0, direction.1, direction.2),
+        );
+
+        assert!(Aabb::infinite().hit(&ray));
+    }
+
+    #[test_case((1.0, 1.0, 1.0), (-1.0, -1.0, -1.0) ; "corners are sorted into min and max")]
+    fn test_aabb_new(a: (f64, f64, f64), b: (f64, f64, f64)) {
+        let aabb = Aabb::new(&Vector3D::new(a.0, a.1, a.2), &Vector3D::new(b.0, b.1, b.2));
+
+        assert_eq!(aabb.min(), &Vector3D::new(-1.0, -1.0, -1.0));
+        assert_eq!(aabb.max(), &Vector3D::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_aabb_union() {
+        let a = Aabb::new(
+            &Vector3D::new(-1.0, 0.0, 0.0),
+            &Vector3D::new(0.0, 1.0, 1.0),
+        );
+        let b = Aabb::new(
+            &Vector3D::new(2.0, -3.0, 0.5),
+            &Vector3D::new(3.0, 0.0, 4.0),
+        );
+
+        let union = a.union(&b);
+
+        assert_eq!(union.min(), &Vector3D::new(-1.0, -3.0, 0.0));
+        assert_eq!(union.max(), &Vector3D::new(3.0, 1.0, 4.0));
+    }
+
+    #[test_case((0.0, 0.0, -5.0), (0.0, 0.0, 1.0), Some(4.0) ; "entry is at the near face")]
+    #[test_case((0.0, 0.0, 0.0), (0.0, 0.0, 1.0), Some(0.0)  ; "starting inside enters immediately")]
+    #[test_case((0.0, 5.0, -5.0), (0.0, 0.0, 1.0), None      ; "miss has no entry")]
+    fn test_aabb_entry_distance(
+        start: (f64, f64, f64),
+        direction: (f64, f64, f64),
+        expected: Option<f64>,
+    ) {
+        let aabb = Aabb::new(
+            &Vector3D::new(-1.0, -1.0, -1.0),
+            &Vector3D::new(1.0, 1.0, 1.0),
+        );
+        let ray = Ray::new(
+            &Vector3D::new(start.0, start.1, start.2),
+            &Vector3D::new(direction.0, direction.1, direction.2),
+        );
+
+        assert_eq!(aabb.entry_distance(&ray), expected);
+    }
+
+    #[test]
+    fn test_aabb_is_finite() {
+        let aabb = Aabb::new(
+            &Vector3D::new(-1.0, 0.0, 0.0),
+            &Vector3D::new(3.0, 2.0, 1.0),
+        );
+
+        assert!(aabb.is_finite());
+        assert!(!Aabb::infinite().is_finite());
+        assert_eq!(aabb.centroid(), Vector3D::new(1.0, 1.0, 0.5));
+    }
+}
@@ -0,0 +1,148 @@
+use crate::{body::Renderable, bvh::Bvh, grid::Grid, ray::Ray};
+
+// How the scene finds which bodies a ray hits
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Acceleration {
+    // Every body is tested against every ray, fine for a handful of bodies
+    Linear,
+    #[default]
+    Bvh,
+    // Better than the BVH when the bodies are many, evenly spread and about the same size
+    Grid,
+}
+
+// The structure built for one of the above, kept in step with the scene's bodies
+#[derive(Debug)]
+pub enum Accelerator {
+    Linear,
+    Bvh(Bvh),
+    Grid(Grid),
+}
+
+impl Accelerator {
+    pub fn new(acceleration: Acceleration, bodies: &[Box<dyn Renderable>]) -> Self {
+        match acceleration {
+            Acceleration::Linear => Accelerator::Linear,
+            Acceleration::Bvh => Accelerator::Bvh(Bvh::new(bodies)),
+            Acceleration::Grid => Accelerator::Grid(Grid::new(bodies)),
+        }
+    }
+
+    pub fn acceleration(&self) -> Acceleration {
+        match self {
+            Accelerator::Linear => Acceleration::Linear,
+            Accelerator::Bvh(_) => Acceleration::Bvh,
+            Accelerator::Grid(_) => Acceleration::Grid,
+        }
+    }
+
+    pub fn hit(&self, bodies: &[Box<dyn Renderable>], ray: &Ray) -> Option<(f64, usize)> {
+        self.hit_counting(bodies, ray).0
+    }
+
+    // Nearest hit as its distance and the body's index, along with how many bodies were
+    // intersected to find it
+    pub fn hit_counting(
+        &self,
+        bodies: &[Box<dyn Renderable>],
+        ray: &Ray,
+    ) -> (Option<(f64, usize)>, u64) {
+        match self {
+            Accelerator::Linear => {
+                let mut search = Search::default();
+
+                for index in 0..bodies.len() {
+                    search.test_body(bodies, index, ray);
+                }
+
+                (search.nearest, search.tests)
+            }
+            Accelerator::Bvh(bvh) => bvh.hit_counting(bodies, ray),
+            Accelerator::Grid(grid) => grid.hit_counting(bodies, ray),
+        }
+    }
+
+    pub fn any_hit(&self, bodies: &[Box<dyn Renderable>], ray: &Ray, max_t: f64) -> (bool, u64) {
+        match self {
+            Accelerator::Linear => {
+                let mut tests = 0;
+                let hit = (0..bodies.len())
+                    .any(|index| is_hit_within(bodies, index, ray, max_t, &mut tests));
+
+                (hit, tests)
+            }
+            Accelerator::Bvh(bvh) => bvh.any_hit(bodies, ray, max_t),
+            Accelerator::Grid(grid) => grid.any_hit(bodies, ray, max_t),
+        }
+    }
+}
+
+// Cheap check against the body's bounding sphere before running its full intersection.
+// Disabled bodies are missed by everything.
+fn is_missed(body: &dyn Renderable, ray: &Ray) -> bool {
+    if !body.is_enabled() {
+        return true;
+    }
+
+    let (center, radius) = body.bounding_sphere();
+
+    radius.is_finite() && ray.misses_sphere(&center, radius)
+}
+
+pub(crate) fn is_hit_within(
+    bodies: &[Box<dyn Renderable>],
+    index: usize,
+    ray: &Ray,
+    max_t: f64,
+    tests: &mut u64,
+) -> bool {
+    if is_missed(bodies[index].as_ref(), ray) {
+        return false;
+    }
+    *tests += 1;
+
+    bodies[index]
+        .closest_ray_distance(ray)
+        .is_some_and(|distance| distance < max_t)
+}
+
+// Where a search for the nearest hit has got to
+#[derive(Default)]
+pub(crate) struct Search {
+    pub nearest: Option<(f64, usize)>,
+    pub tests: u64,
+}
+
+impl Search {
+    pub fn test_body(&mut self, bodies: &[Box<dyn Renderable>], index: usize, ray: &Ray) {
+        if is_missed(bodies[index].as_ref(), ray) {
+            return;
+        }
+        self.tests += 1;
+
+        if let Some(distance) = bodies[index].closest_ray_distance(ray) {
+            // Ties go to the earlier body, like they would in a linear scan
+            let is_nearer = match self.nearest {
+                Some((nearest_distance, nearest_index)) => {
+                    distance < nearest_distance
+                        || (distance == nearest_distance && index < nearest_index)
+                }
+                None => true,
+            };
+
+            if is_nearer {
+                self.nearest = Some((distance, index));
+            }
+        }
+    }
+}
+
+// The nearest hit found by testing every body, for checking accelerators against
+#[cfg(test)]
+pub(crate) fn linear_hit(bodies: &[Box<dyn Renderable>], ray: &Ray) -> Option<(f64, usize)> {
+    bodies
+        .iter()
+        .enumerate()
+        .filter_map(|(index, body)| body.closest_ray_distance(ray).map(|d| (d, index)))
+        .min_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Greater))
+}
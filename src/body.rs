@@ -1,67 +1,255 @@
 pub const THRESHOLD: f64 = f64::EPSILON * 3.;
 
-use std::cmp::Ordering;
+use std::{cmp::Ordering, f64::consts::PI};
 
-use crate::{color::Color, ray::Ray, vector::Vector3D};
+use color_eyre::eyre::Result;
+
+use crate::{
+    aabb::Aabb,
+    color::Color,
+    json::{FromJson, Json, ToJson},
+    material::Material,
+    ray::Ray,
+    roots::Roots,
+    texture::Texture,
+    vector::{Vector2D, Vector3D},
+};
 
 #[derive(Debug)]
 pub struct Body {
-    color: Color,
+    material: Material,
+    enabled: bool,
 }
 
 impl Body {
-    pub fn new(color: Color) -> Self {
-        Body { color }
+    pub fn new(material: impl Into<Material>) -> Self {
+        Body {
+            material: material.into(),
+            enabled: true,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
     }
 }
 
 pub trait Colored {
-    fn color(&self) -> Color;
+    fn material(&self) -> &Material;
+
+    fn color(&self) -> Color {
+        self.material().albedo
+    }
+
+    // Texture hook, surfaces with a UV mapping sample their color through this
+    fn color_at_uv(&self, _uv: Vector2D) -> Color {
+        self.color()
+    }
+
+    // Bodies made of parts with their own materials pick the one at the point
+    fn material_at(&self, _point: &Vector3D, _time: f64) -> &Material {
+        self.material()
+    }
+}
+
+pub trait UvMapped {
+    // Surface coordinates of a point, both in the range 0 to 1
+    fn uv_at(&self, point: &Vector3D, time: f64) -> Vector2D;
+}
+
+pub trait Moving {
+    // Where the body is at a time within the frame, see the camera's shutter
+    fn position_at(&self, time: f64) -> Vector3D;
 }
 
 impl Colored for Body {
-    fn color(&self) -> Color {
-        self.color
+    fn material(&self) -> &Material {
+        &self.material
     }
 }
 
+// Where a ray meets a body
+#[derive(Debug, Clone)]
+pub struct HitRecord {
+    pub t: f64,
+    pub point: Vector3D,
+    // Outward facing and of unit length, whichever side the ray came from
+    pub normal: Vector3D,
+    // Whether the ray hit the outside of the surface, rays leaving a body hit it from behind
+    pub front_face: bool,
+}
+
 pub trait Volume {
-    fn closest_ray_distance(&self, ray: &Ray) -> Option<f64>;
-    fn closest_ray_point(&self, ray: &Ray) -> Option<Vector3D>;
-    fn intersect(&self, ray: &Ray) -> Vec<f64>;
-    fn get_normal_at(&self, point: &Vector3D) -> Vector3D;
-    fn get_color_at(&self, point: &Vector3D) -> Color;
+    fn intersect(&self, ray: &Ray) -> Roots;
+    // Methods taking a point and a time find moving bodies where they are at that time, see the
+    // camera's shutter
+    fn get_normal_at(&self, point: &Vector3D, time: f64) -> Vector3D;
+    fn get_color_at(&self, point: &Vector3D, time: f64) -> Color;
+    fn bounding_box(&self) -> Aabb;
+
+    // Center and radius of a sphere around the body, cheap to test rays against before running
+    // the full intersection. Unbounded bodies get an infinite one.
+    fn bounding_sphere(&self) -> (Vector3D, f64) {
+        let bounds = self.bounding_box();
+
+        if !bounds.is_finite() {
+            return (Vector3D::new(0., 0., 0.), f64::INFINITY);
+        }
+
+        (
+            bounds.centroid(),
+            (bounds.max() - bounds.min()).length() / 2.,
+        )
+    }
+
+    // Surface coordinates of the point and the direction u increases in there, for normal maps.
+    // Bodies without a UV mapping don't have one.
+    fn uv_frame(&self, _point: &Vector3D, _time: f64) -> Option<(Vector2D, Vector3D)> {
+        None
+    }
+
+    // Outward facing surface normal of unit length
+    fn normal_at(&self, point: &Vector3D, time: f64) -> Vector3D {
+        self.get_normal_at(point, time).unit()
+    }
+
+    fn closest_ray_distance(&self, ray: &Ray) -> Option<f64> {
+        let distances = self
+            .intersect(ray)
+            .into_iter()
+            .filter(|distance| *distance > THRESHOLD);
+
+        distances.min_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Greater))
+    }
+
+    // Stretches of the ray inside the body as sorted (entry, exit) distances. Closed bodies
+    // cross their surface in pairs, a lone tangent hit counts as an empty stretch
+    fn intervals(&self, ray: &Ray) -> Vec<(f64, f64)> {
+        let mut distances = self.intersect(ray);
+        distances.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Greater));
+
+        distances
+            .chunks(2)
+            .map(|pair| (pair[0], pair[pair.len() - 1]))
+            .collect()
+    }
+
+    fn closest_ray_point(&self, ray: &Ray) -> Option<Vector3D> {
+        self.closest_ray_distance(ray)
+            .map(|distance| ray.at(distance))
+    }
+
+    // Fills in the record for a hit the given distance along the ray
+    fn hit_at(&self, ray: &Ray, t: f64) -> HitRecord {
+        let point = ray.at(t);
+        let normal = self.normal_at(&point, ray.time);
+
+        HitRecord {
+            t,
+            front_face: ray.direction.dot(&normal) < 0.,
+            point,
+            normal,
+        }
+    }
+
+    fn hit(&self, ray: &Ray) -> Option<HitRecord> {
+        self.closest_ray_distance(ray)
+            .map(|distance| self.hit_at(ray, distance))
+    }
 }
 
-pub trait Renderable: Volume + Colored {}
+// Bodies are shared between render threads
+pub trait Renderable: Volume + Colored + Send + Sync {
+    // Disabled bodies stay in the scene but rays pass through them as if they weren't there.
+    // Bodies that can't be switched off keep these.
+    fn is_enabled(&self) -> bool {
+        true
+    }
+
+    fn set_enabled(&mut self, _enabled: bool) {}
+}
+
+// The normal to shade the point with, the geometric one bent by the material's normal map
+pub fn shading_normal(
+    body: &dyn Renderable,
+    point: &Vector3D,
+    normal: &Vector3D,
+    time: f64,
+) -> Vector3D {
+    let Some(normal_map) = body.material_at(point, time).normal_map else {
+        return normal.clone();
+    };
+
+    match body.uv_frame(point, time) {
+        Some((uv, tangent)) => normal_map.perturb(uv, normal, &tangent),
+        None => normal.clone(),
+    }
+}
+
+// How far off a surface the probe that checks a point lies on it starts
+const SURFACE_PROBE: f64 = 1e-6;
+
+// Steps back out along the body's normal and checks the surface is right there, for bodies
+// made of parts to find which one a point belongs to. Moving bodies are checked where they are
+// at the given time.
+pub fn is_on_surface(body: &dyn Renderable, point: &Vector3D, time: f64) -> bool {
+    let normal = body.normal_at(point, time);
+    let probe = Ray::new(&(point + &normal.scale(SURFACE_PROBE)), &normal.invert()).with_time(time);
+
+    body.intersect(&probe)
+        .iter()
+        .any(|distance| (distance - SURFACE_PROBE).abs() < SURFACE_PROBE / 2.)
+}
 
 #[derive(Debug)]
 pub struct Sphere {
     body: Body,
     center: Vector3D,
     radius: f64,
+    // Distance covered over a whole frame, the center is where it starts
+    velocity: Vector3D,
+    texture: Option<Box<dyn Texture>>,
 }
 
 impl Sphere {
-    pub fn new(center: Vector3D, radius: f64, color: Color) -> Self {
+    pub fn new(center: Vector3D, radius: f64, material: impl Into<Material>) -> Self {
         Sphere {
-            body: Body { color },
+            body: Body::new(material),
             radius,
             center,
+            velocity: Vector3D::new(0., 0., 0.),
+            texture: None,
         }
     }
+
+    pub fn with_velocity(mut self, velocity: Vector3D) -> Self {
+        self.velocity = velocity;
+        self
+    }
+
+    // Colors the sphere with the texture instead of the material's albedo, which stays as the
+    // sphere's color where a single one is needed
+    pub fn with_texture(mut self, texture: impl Texture + 'static) -> Self {
+        self.texture = Some(Box::new(texture));
+        self
+    }
 }
 
 impl Colored for Sphere {
-    fn color(&self) -> Color {
-        self.body.color()
+    fn material(&self) -> &Material {
+        self.body.material()
     }
 }
 
 impl Volume for Sphere {
-    fn intersect(&self, ray: &Ray) -> Vec<f64> {
+    fn intersect(&self, ray: &Ray) -> Roots {
         // For this system, the sphere's center is the origin
-        let ray_start_coordinate = Vector3D::from(&self.center).to(&ray.start);
+        let center = self.position_at(ray.time);
+        let ray_start_coordinate = Vector3D::from(&center).to(&ray.start);
 
         let b = 2. * ray_start_coordinate.dot(&ray.direction);
         let c = ray_start_coordinate.squid() - self.radius * self.radius;
@@ -69,46 +257,120 @@ impl Volume for Sphere {
         let discriminant = b * b - 4. * c;
 
         if discriminant < 0. {
-            vec![]
+            Roots::new()
         } else if discriminant == 0. {
-            vec![-b / 2.]
+            Roots::from([-b / 2.])
         } else {
             let root = discriminant.sqrt();
-            vec![(-b - root) / 2., (-b + root) / 2.]
+            Roots::from([(-b - root) / 2., (-b + root) / 2.])
         }
     }
 
-    fn closest_ray_distance(&self, ray: &Ray) -> Option<f64> {
-        let distances = self
-            .intersect(ray)
-            .into_iter()
-            .filter(|distance| *distance > THRESHOLD);
+    // The normal has to come from where the sphere was at the time
+    fn get_normal_at(&self, point: &Vector3D, time: f64) -> Vector3D {
+        point.to(&self.position_at(time))
+    }
 
-        distances.min_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Greater))
+    fn normal_at(&self, point: &Vector3D, time: f64) -> Vector3D {
+        (point - &self.position_at(time)).unit()
     }
 
-    fn closest_ray_point(&self, ray: &Ray) -> Option<Vector3D> {
-        self.closest_ray_distance(ray)
-            .map(|distance| {
-                Vector3D::from(&ray.start).for_distance_in_direction(distance, &ray.direction)
-            })
-            .and_then(|result| result.ok())
+    fn get_color_at(&self, point: &Vector3D, time: f64) -> Color {
+        let uv = self.uv_at(point, time);
+
+        match &self.texture {
+            Some(texture) => texture.sample(uv, point),
+            None => self.color_at_uv(uv),
+        }
     }
 
-    fn get_normal_at(&self, point: &Vector3D) -> Vector3D {
-        point.to(&self.center)
+    // u runs around the sphere's Y axis, there's no direction for it at the poles
+    fn uv_frame(&self, point: &Vector3D, time: f64) -> Option<(Vector2D, Vector3D)> {
+        let around = point - &self.position_at(time);
+        let tangent = Vector3D::new(around.z(), 0., -around.x());
+
+        (tangent.length() > 0.).then(|| (self.uv_at(point, time), tangent.unit()))
     }
 
-    fn get_color_at(&self, _point: &Vector3D) -> Color {
-        // let normal = self.get_normal_at(point);
-        // let shadow_color = color::BLACK;
-        // TODO: Based on lights in the scene, calculate the color at the requested point
+    // Centred halfway along the path, grown by half of it to reach both ends
+    fn bounding_sphere(&self) -> (Vector3D, f64) {
+        (
+            self.position_at(0.5),
+            self.radius + self.velocity.length() / 2.,
+        )
+    }
 
-        self.color()
+    // Covers the whole path of a moving sphere
+    fn bounding_box(&self) -> Aabb {
+        let extent = Vector3D::new(self.radius, self.radius, self.radius);
+        let (start, end) = (self.position_at(0.), self.position_at(1.));
+
+        Aabb::new(&(&start - &extent), &(&start + &extent))
+            .union(&Aabb::new(&(&end - &extent), &(&end + &extent)))
     }
 }
 
-impl Renderable for Sphere {}
+impl Moving for Sphere {
+    fn position_at(&self, time: f64) -> Vector3D {
+        &self.center + &self.velocity.scale(time)
+    }
+}
+
+impl UvMapped for Sphere {
+    // Latitude/longitude mapping with the seam on -Z and u increasing towards +X
+    fn uv_at(&self, point: &Vector3D, time: f64) -> Vector2D {
+        let direction = (point - &self.position_at(time)).unit();
+
+        let u = 0.5 + direction.x().atan2(direction.z()) / (2. * PI);
+        let v = 0.5 - direction.y().clamp(-1., 1.).asin() / PI;
+
+        Vector2D::new(u, v)
+    }
+}
+
+impl Renderable for Sphere {
+    fn is_enabled(&self) -> bool {
+        self.body.is_enabled()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.body.set_enabled(enabled);
+    }
+}
+
+// Still spheres leave out the velocity
+impl ToJson for Sphere {
+    fn to_json(&self) -> Json {
+        let mut json = Json::object([
+            ("center", self.center.to_json()),
+            ("radius", self.radius.to_json()),
+            ("material", self.material().to_json()),
+        ]);
+
+        if let Json::Object(fields) = &mut json {
+            if self.velocity.squid() > 0. {
+                fields.push(("velocity".to_string(), self.velocity.to_json()));
+            }
+        }
+
+        json
+    }
+}
+
+// A plain color can stand in for a full material
+impl FromJson for Sphere {
+    fn from_json(json: &Json) -> Result<Self> {
+        let material = match json.field("material") {
+            Some(_) => json.get("material")?,
+            None => Material::new(json.get("color")?),
+        };
+
+        Ok(
+            Sphere::new(json.get("center")?, json.get("radius")?, material)
+                .with_velocity(json.get_or("velocity", Vector3D::new(0., 0., 0.))?),
+        )
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -139,6 +401,18 @@ mod tests {
         assert_eq!(sphere.color().rgba(), col.rgba())
     }
 
+    #[test_case((7, 8, 9), 0.75 ; "sphere with reflective material reports its properties")]
+    fn test_sphere_material(albedo: (u8, u8, u8), reflectivity: f64) {
+        let material = Material {
+            reflectivity,
+            ..Material::new(Color::from(albedo))
+        };
+        let sphere = Sphere::new(Vector3D::new(0.0, 0.0, 0.0), 1.0, material);
+
+        assert_eq!(sphere.material().reflectivity, reflectivity);
+        assert_eq!(sphere.color().rgba(), Color::from(albedo).rgba());
+    }
+
     #[test_case(
         (0.0, 0.0, 5.0), (0.0, 1.0, 0.0), vec![], None, None
         ; "ray misses sphere")]
@@ -162,6 +436,8 @@ mod tests {
         let ray = Ray {
             start: Vector3D::new(start.0, start.1, start.2),
             direction: Vector3D::new(direction.0, direction.1, direction.2),
+            time: 0.0,
+            seed: 0,
         };
         let mut intersections = sphere.intersect(&ray);
         assert!(intersections.iter().all(|t| t.is_finite()));
@@ -172,4 +448,178 @@ mod tests {
         let closest = sphere.closest_ray_point(&ray);
         assert_eq!(closest, expected_closest_point);
     }
+
+    // The heap allocating solution intersect used to return, kept to check Roots against
+    fn vec_intersect(sphere: &Sphere, ray: &Ray) -> Vec<f64> {
+        let ray_start_coordinate = Vector3D::from(&sphere.center).to(&ray.start);
+
+        let b = 2. * ray_start_coordinate.dot(&ray.direction);
+        let c = ray_start_coordinate.squid() - sphere.radius * sphere.radius;
+
+        let discriminant = b * b - 4. * c;
+
+        if discriminant < 0. {
+            vec![]
+        } else if discriminant == 0. {
+            vec![-b / 2.]
+        } else {
+            let root = discriminant.sqrt();
+            vec![(-b - root) / 2., (-b + root) / 2.]
+        }
+    }
+
+    #[test_case(2.0, 0 ; "miss")]
+    #[test_case(1.0, 1 ; "tangent")]
+    #[test_case(0.5, 2 ; "two hits")]
+    fn test_sphere_roots_match_vec(height: f64, expected_count: usize) {
+        let sphere = Sphere::new(Vector3D::new(0.0, 0.0, 0.0), 1.0, Color::new(0, 0, 0));
+
+        // Sweep many rays at the same height so the stack type gets a workout like when rendering,
+        // whole number starts keep the tangent discriminant exactly zero
+        for step in 0..10_000 {
+            let x = -5.0 - step as f64;
+            let ray = Ray::new(
+                &Vector3D::new(x, height, 0.0),
+                &Vector3D::new(1.0, 0.0, 0.0),
+            );
+
+            let roots = sphere.intersect(&ray);
+            assert_eq!(roots, vec_intersect(&sphere, &ray));
+            assert_eq!(roots.len(), expected_count);
+            assert!(!roots.is_spilled());
+        }
+    }
+
+    #[test_case((1.0, 0.0, 0.0), (1.0, 0.0, 0.0)   ; "normal on +X side points along +X")]
+    #[test_case((0.0, -1.0, 0.0), (0.0, -1.0, 0.0) ; "normal on -Y side points along -Y")]
+    #[test_case((0.0, 0.0, 3.0), (0.0, 0.0, 1.0)   ; "normal is unit length off the surface")]
+    fn test_sphere_normal_at(point: (f64, f64, f64), expected: (f64, f64, f64)) {
+        let sphere = Sphere::new(Vector3D::new(0.0, 0.0, 0.0), 1.0, Color::new(0, 0, 0));
+        let normal = sphere.normal_at(&Vector3D::new(point.0, point.1, point.2), 0.);
+
+        assert!(approx_eq(normal.x(), expected.0));
+        assert!(approx_eq(normal.y(), expected.1));
+        assert!(approx_eq(normal.z(), expected.2));
+    }
+
+    #[test_case((0.0, 0.0, -5.0), (0.0, 0.0, 1.0), (0.0, 0.0, -1.0), true ; "outside ray hits the front face")]
+    #[test_case((0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (1.0, 0.0, 0.0), false  ; "inside ray hits the back face")]
+    fn test_sphere_hit_record(
+        start: (f64, f64, f64),
+        direction: (f64, f64, f64),
+        expected_normal: (f64, f64, f64),
+        front_face: bool,
+    ) {
+        let sphere = Sphere::new(Vector3D::new(0.0, 0.0, 0.0), 1.0, Color::new(0, 0, 0));
+        let ray = Ray::new(
+            &Vector3D::new(start.0, start.1, start.2),
+            &Vector3D::new(direction.0, direction.1, direction.2),
+        );
+
+        let hit = sphere.hit(&ray).unwrap();
+
+        assert_eq!(Some(hit.t), sphere.closest_ray_distance(&ray));
+        assert_eq!(hit.point, ray.at(hit.t));
+        assert_eq!(
+            hit.normal,
+            Vector3D::new(expected_normal.0, expected_normal.1, expected_normal.2)
+        );
+        assert_eq!(hit.front_face, front_face);
+    }
+
+    #[test_case((0.0, 0.0, 1.0), (0.5, 0.5)   ; "point on +Z maps to the middle")]
+    #[test_case((1.0, 0.0, 0.0), (0.75, 0.5)  ; "point on +X is a quarter turn along u")]
+    #[test_case((0.0, 1.0, 0.0), (0.5, 0.0)   ; "north pole maps to v of 0")]
+    #[test_case((0.0, -1.0, 0.0), (0.5, 1.0)  ; "south pole maps to v of 1")]
+    fn test_sphere_uv_at(point: (f64, f64, f64), expected: (f64, f64)) {
+        let sphere = Sphere::new(Vector3D::new(1.0, 2.0, 3.0), 2.0, Color::new(0, 0, 0));
+        let point = Vector3D::new(
+            1.0 + 2.0 * point.0,
+            2.0 + 2.0 * point.1,
+            3.0 + 2.0 * point.2,
+        );
+        let uv = sphere.uv_at(&point, 0.);
+
+        assert!((uv.x() - expected.0).abs() < 1e-9);
+        assert!((uv.y() - expected.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_sphere_texture() {
+        let plain = Sphere::new(Vector3D::new(0.0, 0.0, 0.0), 1.0, Color::new(9, 9, 9));
+        let textured =
+            Sphere::new(Vector3D::new(0.0, 0.0, 0.0), 1.0, Color::new(9, 9, 9)).with_texture(
+                crate::texture::Gradient::new(Color::new(0, 0, 0), Color::new(255, 255, 255)),
+            );
+        // u is a quarter turn further round on +X than on -X
+        let (left, right) = (Vector3D::new(-1.0, 0.0, 0.0), Vector3D::new(1.0, 0.0, 0.0));
+
+        assert_eq!(plain.get_color_at(&right, 0.).rgba(), [9, 9, 9, 255]);
+        assert_eq!(textured.color().rgba(), [9, 9, 9, 255]);
+        assert!(
+            textured.get_color_at(&right, 0.).rgba()[0]
+                > textured.get_color_at(&left, 0.).rgba()[0]
+        );
+    }
+
+    #[test_case((1.0, 0.0, 0.0)  ; "point facing +X")]
+    #[test_case((0.0, 0.6, 0.8)  ; "point above the equator")]
+    #[test_case((-0.8, 0.0, 0.6) ; "point on the -X side")]
+    fn test_moving_sphere_samples_where_it_moved(direction: (f64, f64, f64)) {
+        let gradient =
+            || crate::texture::Gradient::new(Color::new(0, 0, 0), Color::new(255, 255, 255));
+        let moving = Sphere::new(Vector3D::new(0.0, 0.0, 0.0), 1.0, Color::new(9, 9, 9))
+            .with_velocity(Vector3D::new(2.0, 0.0, 0.0))
+            .with_texture(gradient());
+        let still = Sphere::new(Vector3D::new(2.0, 0.0, 0.0), 1.0, Color::new(9, 9, 9))
+            .with_texture(gradient());
+        let point = Vector3D::new(2.0 + direction.0, direction.1, direction.2);
+
+        assert_eq!(moving.uv_at(&point, 1.), still.uv_at(&point, 0.));
+        assert_eq!(
+            moving.get_color_at(&point, 1.).rgba(),
+            still.get_color_at(&point, 0.).rgba()
+        );
+        assert_eq!(moving.normal_at(&point, 1.), still.normal_at(&point, 0.));
+        assert_eq!(moving.uv_frame(&point, 1.), still.uv_frame(&point, 0.));
+    }
+
+    #[test_case((0.0, 0.0, 0.0), false ; "still sphere leaves velocity out")]
+    #[test_case((0.0, 1.5, -2.0), true ; "moving sphere keeps its velocity")]
+    fn test_sphere_json_velocity(velocity: (f64, f64, f64), written: bool) {
+        let velocity = Vector3D::new(velocity.0, velocity.1, velocity.2);
+        let sphere = Sphere::new(Vector3D::new(1.0, 2.0, 3.0), 0.5, Color::new(9, 9, 9))
+            .with_velocity(velocity.clone());
+
+        let json = sphere.to_json();
+        let loaded = Sphere::from_json(&Json::parse(&json.to_string()).unwrap()).unwrap();
+
+        assert_eq!(json.field("velocity").is_some(), written);
+        assert_eq!(loaded.velocity, velocity);
+        assert_eq!(loaded.to_json(), json);
+    }
+
+    #[test_case((1.0, 2.0, 3.0), 2.0, (-1.0, 0.0, 1.0), (3.0, 4.0, 5.0) ; "sphere bounds are center plus minus radius")]
+    fn test_sphere_bounding_box(
+        center: (f64, f64, f64),
+        radius: f64,
+        expected_min: (f64, f64, f64),
+        expected_max: (f64, f64, f64),
+    ) {
+        let sphere = Sphere::new(
+            Vector3D::new(center.0, center.1, center.2),
+            radius,
+            Color::new(0, 0, 0),
+        );
+        let bounds = sphere.bounding_box();
+
+        assert_eq!(
+            bounds.min(),
+            &Vector3D::new(expected_min.0, expected_min.1, expected_min.2)
+        );
+        assert_eq!(
+            bounds.max(),
+            &Vector3D::new(expected_max.0, expected_max.1, expected_max.2)
+        );
+    }
 }
@@ -0,0 +1,368 @@
+use std::cmp::Ordering;
+
+use crate::{
+    aabb::Aabb,
+    accelerator::{is_hit_within, Search},
+    body::Renderable,
+    ray::Ray,
+};
+
+// Bodies per leaf, past this a node is split in two
+const LEAF_SIZE: usize = 2;
+
+#[derive(Debug)]
+enum Node {
+    Leaf {
+        bounds: Aabb,
+        bodies: Vec<usize>,
+    },
+    Branch {
+        bounds: Aabb,
+        left: Box<Node>,
+        right: Box<Node>,
+    },
+}
+
+// Bounding volume hierarchy over a list of bodies, which it refers to by index
+#[derive(Debug, Default)]
+pub struct Bvh {
+    root: Option<Node>,
+    // Bodies without finite bounds can't be sorted into the tree, so they're always tested
+    unbounded: Vec<usize>,
+}
+
+impl Bvh {
+    pub fn new(bodies: &[Box<dyn Renderable>]) -> Self {
+        let (bounded, unbounded): (Vec<_>, Vec<_>) = bodies
+            .iter()
+            .map(|body| body.bounding_box())
+            .enumerate()
+            .partition(|(_, bounds)| bounds.is_finite());
+
+        Bvh {
+            root: build(bounded),
+            unbounded: unbounded.into_iter().map(|(index, _)| index).collect(),
+        }
+    }
+
+    // Nearest hit along the ray as its distance and the index of the body, the same one a
+    // linear scan over the bodies would find
+    pub fn hit(&self, bodies: &[Box<dyn Renderable>], ray: &Ray) -> Option<(f64, usize)> {
+        self.hit_counting(bodies, ray).0
+    }
+
+    // Same as hit, along with how many bodies had to be intersected to find it
+    pub fn hit_counting(
+        &self,
+        bodies: &[Box<dyn Renderable>],
+        ray: &Ray,
+    ) -> (Option<(f64, usize)>, u64) {
+        let mut search = Search::default();
+
+        for index in &self.unbounded {
+            search.test_body(bodies, *index, ray);
+        }
+
+        if let Some(root) = &self.root {
+            root.hit(bodies, ray, &mut search);
+        }
+
+        (search.nearest, search.tests)
+    }
+
+    // Whether any body is hit closer than max_t, stopping at the first one found. Also returns
+    // how many bodies were intersected on the way.
+    pub fn any_hit(&self, bodies: &[Box<dyn Renderable>], ray: &Ray, max_t: f64) -> (bool, u64) {
+        let mut tests = 0;
+
+        let hit = self
+            .unbounded
+            .iter()
+            .any(|index| is_hit_within(bodies, *index, ray, max_t, &mut tests))
+            || self
+                .root
+                .as_ref()
+                .is_some_and(|root| root.any_hit(bodies, ray, max_t, &mut tests));
+
+        (hit, tests)
+    }
+}
+
+fn build(mut items: Vec<(usize, Aabb)>) -> Option<Node> {
+    let bounds = items
+        .iter()
+        .map(|(_, bounds)| bounds.clone())
+        .reduce(|total, bounds| total.union(&bounds))?;
+
+    if items.len() <= LEAF_SIZE {
+        return Some(Node::Leaf {
+            bounds,
+            bodies: items.into_iter().map(|(index, _)| index).collect(),
+        });
+    }
+
+    // Split at the median along the axis where the centers are spread out the most
+    let centroids: Vec<_> = items.iter().map(|(_, bounds)| bounds.centroid()).collect();
+    let spread = Aabb::from_points(&centroids)?;
+    let extent = spread.max() - spread.min();
+
+    let axis = if extent.x() >= extent.y() && extent.x() >= extent.z() {
+        0
+    } else if extent.y() >= extent.z() {
+        1
+    } else {
+        2
+    };
+    let along_axis = |bounds: &Aabb| {
+        let centroid = bounds.centroid();
+        [centroid.x(), centroid.y(), centroid.z()][axis]
+    };
+
+    items.sort_by(|(_, a), (_, b)| {
+        along_axis(a)
+            .partial_cmp(&along_axis(b))
+            .unwrap_or(Ordering::Equal)
+    });
+    let right = items.split_off(items.len() / 2);
+
+    Some(Node::Branch {
+        bounds,
+        left: Box::new(build(items)?),
+        right: Box::new(build(right)?),
+    })
+}
+
+impl Node {
+    fn bounds(&self) -> &Aabb {
+        match self {
+            Node::Leaf { bounds, .. } | Node::Branch { bounds, .. } => bounds,
+        }
+    }
+
+    fn hit(&self, bodies: &[Box<dyn Renderable>], ray: &Ray, search: &mut Search) {
+        match self.bounds().entry_distance(ray) {
+            None => return,
+            // Nothing in here can beat a hit in front of the box
+            Some(entry) if search.nearest.is_some_and(|(distance, _)| distance < entry) => return,
+            Some(_) => {}
+        }
+
+        match self {
+            Node::Leaf {
+                bodies: indices, ..
+            } => {
+                for index in indices {
+                    search.test_body(bodies, *index, ray);
+                }
+            }
+            Node::Branch { left, right, .. } => {
+                left.hit(bodies, ray, search);
+                right.hit(bodies, ray, search);
+            }
+        }
+    }
+
+    fn any_hit(
+        &self,
+        bodies: &[Box<dyn Renderable>],
+        ray: &Ray,
+        max_t: f64,
+        tests: &mut u64,
+    ) -> bool {
+        if !self
+            .bounds()
+            .entry_distance(ray)
+            .is_some_and(|entry| entry < max_t)
+        {
+            return false;
+        }
+
+        match self {
+            Node::Leaf {
+                bodies: indices, ..
+            } => indices
+                .iter()
+                .any(|index| is_hit_within(bodies, *index, ray, max_t, tests)),
+            Node::Branch { left, right, .. } => {
+                left.any_hit(bodies, ray, max_t, tests) || right.any_hit(bodies, ray, max_t, tests)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering as AtomicOrdering},
+        Arc,
+    };
+
+    use super::*;
+    use crate::{
+        accelerator::linear_hit,
+        body::{Colored, Sphere, Volume},
+        color::{self, Color},
+        material::Material,
+        plane::Plane,
+        roots::Roots,
+        vector::Vector3D,
+    };
+    use test_case::test_case;
+
+    // Sphere that counts how often it's tested against a ray
+    struct CountingSphere {
+        sphere: Sphere,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl Colored for CountingSphere {
+        fn material(&self) -> &Material {
+            self.sphere.material()
+        }
+    }
+
+    impl Volume for CountingSphere {
+        fn intersect(&self, ray: &Ray) -> Roots {
+            self.calls.fetch_add(1, AtomicOrdering::Relaxed);
+            self.sphere.intersect(ray)
+        }
+
+        fn get_normal_at(&self, point: &Vector3D, time: f64) -> Vector3D {
+            self.sphere.get_normal_at(point, time)
+        }
+
+        fn get_color_at(&self, point: &Vector3D, time: f64) -> Color {
+            self.sphere.get_color_at(point, time)
+        }
+
+        fn bounding_box(&self) -> Aabb {
+            self.sphere.bounding_box()
+        }
+
+        fn bounding_sphere(&self) -> (Vector3D, f64) {
+            self.sphere.bounding_sphere()
+        }
+    }
+
+    impl Renderable for CountingSphere {}
+
+    fn grid_of_spheres(side: i32, calls: &Arc<AtomicUsize>) -> Vec<Box<dyn Renderable>> {
+        (0..side)
+            .flat_map(|x| (0..side).flat_map(move |y| (0..side).map(move |z| (x, y, z))))
+            .map(|(x, y, z)| {
+                Box::new(CountingSphere {
+                    sphere: Sphere::new(
+                        Vector3D::new(x as f64 * 3.0, y as f64 * 3.0, z as f64 * 3.0),
+                        1.0,
+                        color::RED,
+                    ),
+                    calls: calls.clone(),
+                }) as Box<dyn Renderable>
+            })
+            .collect()
+    }
+
+    #[test_case(6 ; "six by six by six grid")]
+    fn test_bvh_matches_linear_scan_with_fewer_tests(side: i32) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let bodies = grid_of_spheres(side, &calls);
+        let bvh = Bvh::new(&bodies);
+
+        let rays: Vec<Ray> = (0..20)
+            .flat_map(|i| (0..20).map(move |j| (i, j)))
+            .map(|(i, j)| {
+                Ray::new(
+                    &Vector3D::new(i as f64 - 2.0, j as f64 - 2.0, -10.0),
+                    &Vector3D::new(0.1 * (i % 3) as f64, 0.05 * (j % 4) as f64, 1.0),
+                )
+            })
+            .collect();
+
+        calls.store(0, AtomicOrdering::Relaxed);
+        let linear: Vec<_> = rays.iter().map(|ray| linear_hit(&bodies, ray)).collect();
+        let linear_calls = calls.swap(0, AtomicOrdering::Relaxed);
+
+        let tree: Vec<_> = rays.iter().map(|ray| bvh.hit(&bodies, ray)).collect();
+        let tree_calls = calls.load(AtomicOrdering::Relaxed);
+
+        assert_eq!(tree, linear);
+        assert!(linear.iter().any(Option::is_some));
+        assert!(linear.iter().any(Option::is_none));
+        assert!(tree_calls * 4 < linear_calls);
+    }
+
+    #[test]
+    fn test_bvh_always_tests_unbounded_bodies() {
+        let bodies: Vec<Box<dyn Renderable>> = vec![
+            Box::new(Sphere::new(Vector3D::new(0.0, 0.0, 0.0), 1.0, color::RED)),
+            Box::new(Plane::new(
+                Vector3D::new(0.0, -5.0, 0.0),
+                Vector3D::new(0.0, 1.0, 0.0),
+                color::GREEN,
+            )),
+        ];
+        let bvh = Bvh::new(&bodies);
+
+        let down = Ray::new(
+            &Vector3D::new(10.0, 0.0, 0.0),
+            &Vector3D::new(0.0, -1.0, 0.0),
+        );
+        let ahead = Ray::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 1.0),
+        );
+
+        assert_eq!(bvh.hit(&bodies, &down), Some((5.0, 1)));
+        assert_eq!(bvh.hit(&bodies, &ahead), Some((4.0, 0)));
+        assert_eq!(Bvh::new(&[]).hit(&[], &ahead), None);
+    }
+
+    #[test_case(6 ; "six by six by six grid")]
+    fn test_any_hit_agrees_with_nearest_hit(side: i32) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let bodies = grid_of_spheres(side, &calls);
+        let bvh = Bvh::new(&bodies);
+
+        for i in 0..20 {
+            let ray = Ray::new(
+                &Vector3D::new(i as f64 - 2.0, 1.0, -10.0),
+                &Vector3D::new(0.0, 0.1 * (i % 3) as f64, 1.0),
+            );
+
+            for max_t in [1.0, 9.5, 12.0, f64::INFINITY] {
+                let nearest = bvh.hit(&bodies, &ray);
+
+                assert_eq!(
+                    bvh.any_hit(&bodies, &ray, max_t).0,
+                    nearest.is_some_and(|(distance, _)| distance < max_t)
+                );
+            }
+        }
+    }
+
+    #[test_case((0.9, 0.9, -5.0), (0.0, 0.0, 1.0), false ; "ray through the box corner")]
+    #[test_case((0.5, 0.5, -5.0), (0.0, 0.0, 1.0), true  ; "ray through the sphere")]
+    #[test_case((0.0, 0.0, 5.0), (0.0, 0.0, 1.0), false  ; "sphere behind the ray")]
+    fn test_bounding_sphere_skips_clear_misses(
+        start: (f64, f64, f64),
+        direction: (f64, f64, f64),
+        tested: bool,
+    ) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let bodies = grid_of_spheres(1, &calls);
+        let bvh = Bvh::new(&bodies);
+        let ray = Ray::new(
+            &Vector3D::new(start.0, start.1, start.2),
+            &Vector3D::new(direction.0, direction.1, direction.2),
+        );
+
+        let linear = linear_hit(&bodies, &ray);
+        calls.store(0, AtomicOrdering::Relaxed);
+        let (nearest, tests) = bvh.hit_counting(&bodies, &ray);
+
+        assert_eq!(nearest, linear);
+        assert_eq!(nearest.is_some(), tested);
+        assert_eq!(calls.load(AtomicOrdering::Relaxed) > 0, tested);
+        assert_eq!(tests > 0, tested);
+    }
+}
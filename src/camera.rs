@@ -1,14 +1,24 @@
-use color_eyre::eyre::Result;
+use std::{f64::consts::PI, sync::OnceLock};
 
 use crate::{
+    aabb::Aabb,
     color::Color,
+    error::Result,
     ray::Ray,
+    rng::{self, splitmix, Rng, XorShift},
     scene::Scene,
     {vector, vector::Vector3D},
 };
 
 const ONE_HALF: f64 = 1. / 2.;
 
+// Looking straight up or down leaves no way to tell right from left
+const MAX_PITCH: f64 = 89.;
+
+// Vertical field of view in degrees
+pub const DEFAULT_FOV: u8 = 60;
+pub const DEFAULT_RESOLUTION: Resolution = (600, 600);
+
 fn calculate_ndc_x(x: i32, width: u16) -> f64 {
     (x as f64 + ONE_HALF) / width as f64 * 2.0 - 1.0
 }
@@ -17,9 +27,38 @@ fn calculate_ndc_y(y: i32, height: u16) -> f64 {
     1.0 - (y as f64 + 0.5) / height as f64 * 2.0
 }
 
+// Seed for the random parts of a sample, from the render's seed, the pixel and the sample's
+// offset within it so renders stay reproducible
+fn sample_seed(x: i32, y: i32, offset: (f64, f64), seed: u64) -> u64 {
+    let pixel = ((x as u32 as u64) << 32) | y as u32 as u64;
+    let sample = splitmix(offset.0.to_bits() ^ offset.1.to_bits().rotate_left(32));
+
+    seed ^ splitmix(pixel ^ sample)
+}
+
 pub type Resolution = (u16, u16);
 
-#[derive(Debug)]
+// Pinhole rays through every pixel's center, built on first use and dropped whenever the camera
+// changes. It's derived from the other fields, so it doesn't take part in comparisons.
+#[derive(Debug, Default)]
+struct RayCache(OnceLock<Vec<Ray>>);
+
+impl PartialEq for RayCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    Perspective,
+    // Parallel rays, scale is half the height of the view in world units
+    Orthographic { scale: f64 },
+    // Full 360° by 180° view, columns map to longitude and rows to latitude like a world map
+    Equirectangular,
+}
+
+#[derive(Debug, PartialEq)]
 pub struct Camera {
     position: Vector3D,
     target: Vector3D,
@@ -30,9 +69,80 @@ pub struct Camera {
     right: Vector3D,
     aspect_ratio: f64,
     fov: u8,
+    projection: Projection,
+    // Lens diameter, zero keeps the pinhole where everything is in focus
+    aperture: f64,
+    focus_distance: f64,
+    // Fraction of a frame the shutter stays open, 0 to 1. Rays are cast at random times within
+    // it so moving bodies blur, at zero everything is frozen at the start of the frame
+    shutter: f64,
+    rays: RayCache,
+}
+
+// Sets up a camera one setting at a time, anything left alone keeps its default. Without a
+// position or target it sits at the origin looking along +Z.
+#[derive(Debug, Clone)]
+pub struct CameraBuilder {
+    position: Vector3D,
+    look_at: Vector3D,
+    resolution: Resolution,
+    fov: u8,
+    projection: Projection,
+}
+
+impl Default for CameraBuilder {
+    fn default() -> Self {
+        CameraBuilder {
+            position: Vector3D::new(0., 0., 0.),
+            look_at: Vector3D::new(0., 0., 1.),
+            resolution: DEFAULT_RESOLUTION,
+            fov: DEFAULT_FOV,
+            projection: Projection::Perspective,
+        }
+    }
+}
+
+impl CameraBuilder {
+    pub fn position(mut self, position: Vector3D) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn look_at(mut self, look_at: Vector3D) -> Self {
+        self.look_at = look_at;
+        self
+    }
+
+    pub fn resolution(mut self, width: u16, height: u16) -> Self {
+        self.resolution = (width, height);
+        self
+    }
+
+    pub fn fov(mut self, degrees: u8) -> Self {
+        self.fov = degrees;
+        self
+    }
+
+    pub fn projection(mut self, projection: Projection) -> Self {
+        self.projection = projection;
+        self
+    }
+
+    pub fn build(self) -> Camera {
+        let (width, height) = self.resolution;
+        let mut camera = Camera::new(&self.position, &self.look_at, width, height);
+        camera.set_fov(self.fov);
+        camera.set_projection(self.projection);
+
+        camera
+    }
 }
 
 impl Camera {
+    pub fn builder() -> CameraBuilder {
+        CameraBuilder::default()
+    }
+
     pub fn new(position: &Vector3D, look_at: &Vector3D, width: u16, height: u16) -> Self {
         let position = if position.x() == look_at.x() && position.z() == look_at.z() {
             position.append(&Vector3D::new(0., 0., -0.0000001))
@@ -49,6 +159,8 @@ impl Camera {
 
         let target = Vector3D::new(look_at.x(), look_at.y(), look_at.z());
 
+        let focus_distance = Vector3D::from(&position).to(&target).length();
+
         Camera {
             aspect_ratio,
             position,
@@ -58,30 +170,300 @@ impl Camera {
             height,
             right,
             up,
-            fov: 60,
+            fov: DEFAULT_FOV,
+            projection: Projection::Perspective,
+            aperture: 0.,
+            focus_distance,
+            shutter: 0.,
+            rays: RayCache::default(),
         }
     }
 
-    // TODO: Revisit for arbitrary FOV and aspect ratio
-    pub fn trace(&self, scene: &Scene, x: i32, y: i32) -> Result<Color> {
-        let ndc_x = calculate_ndc_x(x, self.width);
-        let ndc_y = calculate_ndc_y(y, self.height);
+    // Straight out of the middle of the lens along the view direction
+    pub fn center_ray(&self) -> Ray {
+        Ray::new(&self.position, &self.direction)
+    }
 
-        let vx = self.right.scale(ndc_x);
+    pub fn ray_for_pixel(&self, x: i32, y: i32) -> Ray {
+        self.ray_for_sample(x, y, (ONE_HALF, ONE_HALF), 0)
+    }
 
-        let vy = self.up.scale(ndc_y);
+    // Row-major rays from the pinhole through every pixel's center, before the lens and shutter
+    // have a say. Worked out once and reused until the camera is changed, every sample of a
+    // pixel starts from its ray so passes over a still camera skip redoing the basis math.
+    pub fn precompute_rays(&self) -> &[Ray] {
+        self.rays.0.get_or_init(|| {
+            (0..self.height as i32)
+                .flat_map(|y| (0..self.width as i32).map(move |x| (x, y)))
+                .map(|(x, y)| self.pinhole_ray(x, y, (ONE_HALF, ONE_HALF)))
+                .collect()
+        })
+    }
 
-        let direction = self.direction.append(&vx).append(&vy);
+    // The cached ray through the pixel's center, None when it's outside the image
+    pub fn cached_ray(&self, x: i32, y: i32) -> Option<&Ray> {
+        if !(0..self.width as i32).contains(&x) || !(0..self.height as i32).contains(&y) {
+            return None;
+        }
+
+        self.precompute_rays()
+            .get(y as usize * self.width as usize + x as usize)
+    }
+
+    fn invalidate_rays(&mut self) {
+        self.rays.0.take();
+    }
+
+    // Ray through a point inside the pixel, the offset runs from 0 to 1 along x and y with the
+    // pixel's center at one half. The seed picks the point on the lens and the moment during
+    // the shutter, and goes along with the ray for shading.
+    pub fn ray_for_sample(&self, x: i32, y: i32, offset: (f64, f64), seed: u64) -> Ray {
+        let seed = sample_seed(x, y, offset, seed);
+        let ray = self.lens_ray(x, y, offset, seed).with_seed(seed);
+
+        if self.shutter <= 0. {
+            return ray;
+        }
+
+        // Every sample of a pixel catches a different moment. Mixed once more so the time
+        // doesn't follow the lens position drawn from the same seed.
+        let time = XorShift::with_seed(splitmix(seed)).next_f64();
+
+        ray.with_time(time * self.shutter)
+    }
+
+    // The fov and scale are vertical, so this widens horizontally to keep pixels square
+    fn offset_on_screen(&self, screen_x: f64, screen_y: f64, half_extent: f64) -> Vector3D {
+        self.right
+            .scale(screen_x * half_extent * self.aspect_ratio)
+            .append(&self.up.scale(screen_y * half_extent))
+    }
+
+    // Half the view fits into tan(fov/2) at unit distance from a perspective camera
+    fn half_extent(&self) -> f64 {
+        (self.fov as f64 * ONE_HALF).to_radians().tan()
+    }
+
+    // How far across the screen the point within the pixel is from the pixel's center
+    fn offset_from_center(&self, offset: (f64, f64)) -> (f64, f64) {
+        (
+            (offset.0 - ONE_HALF) * 2. / self.width as f64,
+            -(offset.1 - ONE_HALF) * 2. / self.height as f64,
+        )
+    }
+
+    // Pinhole ray through a point inside the pixel, shifted over from the cached one through its
+    // center. Panoramas bend the view too much for a shift and work it out afresh.
+    fn sample_pinhole_ray(&self, x: i32, y: i32, offset: (f64, f64)) -> Ray {
+        let Some(center) = self.cached_ray(x, y) else {
+            return self.pinhole_ray(x, y, offset);
+        };
+        let (shift_x, shift_y) = self.offset_from_center(offset);
+
+        match self.projection {
+            _ if offset == (ONE_HALF, ONE_HALF) => center.clone(),
+            Projection::Perspective => {
+                // The cached direction is a unit vector, this stretches it back out to the
+                // screen at unit distance where offsets are measured
+                let on_screen = center
+                    .direction
+                    .scale(1. / center.direction.dot(&self.direction));
+                let direction =
+                    on_screen.append(&self.offset_on_screen(shift_x, shift_y, self.half_extent()));
 
-        let ray = Ray::new(&self.position, &direction.unit());
+                Ray::new(&self.position, &direction.unit())
+            }
+            Projection::Orthographic { scale } => Ray::new(
+                &center
+                    .start
+                    .append(&self.offset_on_screen(shift_x, shift_y, scale)),
+                &self.direction,
+            ),
+            Projection::Equirectangular => self.pinhole_ray(x, y, offset),
+        }
+    }
+
+    // Works the pinhole ray out from scratch, see sample_pinhole_ray for the cached way
+    fn pinhole_ray(&self, x: i32, y: i32, offset: (f64, f64)) -> Ray {
+        let (shift_x, shift_y) = self.offset_from_center(offset);
+        let screen_x = calculate_ndc_x(x, self.width) + shift_x;
+        let screen_y = calculate_ndc_y(y, self.height) + shift_y;
+
+        match self.projection {
+            Projection::Perspective => {
+                let direction = self.direction.append(&self.offset_on_screen(
+                    screen_x,
+                    screen_y,
+                    self.half_extent(),
+                ));
+
+                Ray::new(&self.position, &direction.unit())
+            }
+            Projection::Orthographic { scale } => {
+                let start = self
+                    .position
+                    .append(&self.offset_on_screen(screen_x, screen_y, scale));
+
+                Ray::new(&start, &self.direction)
+            }
+            Projection::Equirectangular => {
+                // The left and right edges of the image both look straight behind the camera
+                let longitude = screen_x * PI;
+                let latitude = screen_y * PI * ONE_HALF;
+
+                let around = self
+                    .direction
+                    .scale(longitude.cos())
+                    .append(&self.right.scale(longitude.sin()));
+                let direction = around
+                    .scale(latitude.cos())
+                    .append(&self.up.scale(latitude.sin()));
+
+                Ray::new(&self.position, &direction)
+            }
+        }
+    }
 
-        ray.trace(scene)
+    fn lens_ray(&self, x: i32, y: i32, offset: (f64, f64), seed: u64) -> Ray {
+        let pinhole = self.sample_pinhole_ray(x, y, offset);
+
+        if self.aperture <= 0. {
+            return pinhole;
+        }
+
+        // Thin lens, every ray through the lens for this pixel meets on the focus plane. Each
+        // sample of the pixel goes through its own point on the lens.
+        // A panorama looks every way at once, so it focuses on a sphere around the camera
+        // instead of a plane in front of it that sideways and backward rays never reach
+        let focus_along = match self.projection {
+            Projection::Equirectangular => self.focus_distance,
+            _ => self.focus_distance / pinhole.direction.dot(&self.direction),
+        };
+        let focus_point = pinhole.start.append(&pinhole.direction.scale(focus_along));
+
+        let mut rng = XorShift::with_seed(seed);
+        let (lens_x, lens_y) = rng::in_unit_disk(&mut rng);
+        let lens_radius = self.aperture * ONE_HALF;
+
+        let start = pinhole
+            .start
+            .append(&self.right.scale(lens_x * lens_radius))
+            .append(&self.up.scale(lens_y * lens_radius));
+
+        Ray::new(&start, &Vector3D::from(&start).to(&focus_point))
+    }
+
+    pub fn trace(&self, scene: &Scene, x: i32, y: i32) -> Result<Color> {
+        self.ray_for_pixel(x, y).trace(scene)
     }
 
     pub fn resolution(&self) -> Resolution {
         (self.width, self.height)
     }
 
+    pub fn set_resolution(&mut self, width: u16, height: u16) {
+        self.invalidate_rays();
+        self.width = width;
+        self.height = height;
+        self.aspect_ratio = width as f64 / height as f64;
+    }
+
+    pub fn aspect_ratio(&self) -> f64 {
+        self.aspect_ratio
+    }
+
+    pub fn fov(&self) -> u8 {
+        self.fov
+    }
+
+    pub fn set_fov(&mut self, degrees: u8) {
+        self.invalidate_rays();
+        self.fov = degrees;
+    }
+
+    pub fn aperture(&self) -> f64 {
+        self.aperture
+    }
+
+    pub fn focus_distance(&self) -> f64 {
+        self.focus_distance
+    }
+
+    pub fn set_depth_of_field(&mut self, aperture: f64, focus_distance: f64) {
+        self.invalidate_rays();
+        self.aperture = aperture;
+        self.focus_distance = focus_distance;
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.invalidate_rays();
+        self.projection = projection;
+    }
+
+    pub fn shutter(&self) -> f64 {
+        self.shutter
+    }
+
+    pub fn set_shutter(&mut self, shutter: f64) {
+        self.invalidate_rays();
+        self.shutter = shutter.clamp(0., 1.);
+    }
+
+    // Free look from Euler angles in degrees. Yaw turns around Y starting from +Z towards +X,
+    // pitch tilts up towards +Y. The target is moved along so move_to keeps looking the same way.
+    pub fn set_orientation(&mut self, yaw: f64, pitch: f64) {
+        let (yaw, pitch) = (
+            yaw.to_radians(),
+            pitch.clamp(-MAX_PITCH, MAX_PITCH).to_radians(),
+        );
+
+        let direction = Vector3D::new(
+            pitch.cos() * yaw.sin(),
+            pitch.sin(),
+            pitch.cos() * yaw.cos(),
+        )
+        .unit();
+
+        let right = vector::Y.cross(&direction).unit().invert();
+        let up = right.cross(&direction).unit();
+
+        self.target = self.position.append(&direction.scale(self.focus_distance));
+        self.direction = direction;
+        self.right = right;
+        self.up = up;
+        self.invalidate_rays();
+    }
+
+    // Backs the camera away along its view direction until the whole box fits in the picture,
+    // aiming at its center. Worked out for the perspective projection, unbounded boxes can't be
+    // fit and leave the camera where it was.
+    pub fn frame(&mut self, bounds: &Aabb) {
+        if !bounds.is_finite() {
+            return;
+        }
+
+        let center = bounds.centroid();
+        let radius = (bounds.max() - bounds.min()).length() * ONE_HALF;
+
+        // The narrower of the vertical and horizontal view angles has to take the bounding sphere
+        let tan_vertical = (self.fov as f64 * ONE_HALF).to_radians().tan();
+        let half_angle = tan_vertical.min(tan_vertical * self.aspect_ratio).atan();
+        let distance = radius / half_angle.sin();
+
+        let position = center.subtract(&self.direction.scale(distance));
+        self.look_at(position, center);
+    }
+
+    // Moves the camera and turns it to face the target
+    pub fn look_at(&mut self, position: Vector3D, target: Vector3D) {
+        self.target = target;
+        self.move_to(position);
+    }
+
     pub fn move_to(&mut self, new_position: Vector3D) {
         let position = if new_position.x() == self.target.x() && new_position.z() == self.target.z()
         {
@@ -99,6 +481,7 @@ impl Camera {
         self.direction = direction;
         self.right = right;
         self.up = up;
+        self.invalidate_rays();
     }
 }
 
@@ -141,6 +524,35 @@ mod tests {
         assert!(approx_eq(cam.up.z(), exp_up.z()));
     }
 
+    #[test]
+    fn test_builder_defaults_match_new() {
+        let (position, look_at) = (Vector3D::new(1.0, 2.0, -5.0), Vector3D::new(0.0, 0.5, 0.0));
+        let (width, height) = DEFAULT_RESOLUTION;
+
+        let built = Camera::builder()
+            .position(position.clone())
+            .look_at(look_at.clone())
+            .build();
+
+        assert_eq!(built, Camera::new(&position, &look_at, width, height));
+        assert_eq!(built.fov(), DEFAULT_FOV);
+    }
+
+    #[test]
+    fn test_builder_applies_every_setting() {
+        let camera = Camera::builder()
+            .position(Vector3D::new(0.0, 0.0, -5.0))
+            .look_at(Vector3D::new(0.0, 0.0, 0.0))
+            .resolution(320, 240)
+            .fov(90)
+            .projection(Projection::Orthographic { scale: 2.0 })
+            .build();
+
+        assert_eq!(camera.resolution(), (320, 240));
+        assert_eq!(camera.fov(), 90);
+        assert_eq!(camera.projection(), Projection::Orthographic { scale: 2.0 });
+    }
+
     #[test_case(
         300, 300,
         (1,0,0)
@@ -157,7 +569,13 @@ mod tests {
             600,
         );
         let sphere = Sphere::new(Vector3D::new(0.0, 0.0, 0.0), 1.0, Color::new(1, 0, 0));
-        let scene = Scene::new(&mut cam, Color::new(0, 0, 1), Box::new([Box::new(sphere)]));
+        let scene = Scene::new(
+            &mut cam,
+            Color::new(0, 0, 1),
+            Color::new(0, 0, 0),
+            Box::new([Box::new(sphere)]),
+            Box::new([]),
+        );
         let color = scene.trace(x, y).unwrap();
 
         assert_eq!(
@@ -166,6 +584,373 @@ mod tests {
         );
     }
 
+    #[test_case(30, 90 ; "widening from 30 to 90 degrees")]
+    #[test_case(60, 120 ; "widening from the default")]
+    fn test_wider_fov_spreads_edge_rays(narrow: u8, wide: u8) {
+        let mut cam = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            600,
+            600,
+        );
+
+        cam.set_fov(narrow);
+        let narrow_ray = cam.ray_for_pixel(599, 0);
+
+        cam.set_fov(wide);
+        let wide_ray = cam.ray_for_pixel(599, 0);
+
+        assert_eq!(cam.fov(), wide);
+        assert!(wide_ray.direction.x().abs() > narrow_ray.direction.x().abs());
+        assert!(wide_ray.direction.y().abs() > narrow_ray.direction.y().abs());
+        assert!(wide_ray.direction.z() < narrow_ray.direction.z());
+    }
+
+    #[test_case(90, 45.0 ; "ninety degrees reaches 45 degrees at the edge")]
+    #[test_case(60, 30.0 ; "sixty degrees reaches 30 degrees at the edge")]
+    fn test_fov_edge_angle(fov: u8, expected_degrees: f64) {
+        let mut cam = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            1,
+            2,
+        );
+        cam.set_fov(fov);
+
+        // With a two pixel tall image the pixel centers sit halfway to the edges
+        let ray = cam.ray_for_pixel(0, 0);
+        let half_extent = expected_degrees.to_radians().tan() / 2.;
+
+        assert!((ray.direction.y() / ray.direction.z() - half_extent).abs() < 1e-9);
+    }
+
+    #[test_case(800, 600 ; "landscape")]
+    #[test_case(600, 800 ; "portrait")]
+    fn test_sphere_stays_circular(width: u16, height: u16) {
+        let mut cam = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            width,
+            height,
+        );
+        let sphere = Sphere::new(Vector3D::new(0.0, 0.0, 0.0), 1.0, Color::new(1, 0, 0));
+        let scene = Scene::new(
+            &mut cam,
+            Color::new(0, 0, 1),
+            Color::new(0, 0, 0),
+            Box::new([Box::new(sphere)]),
+            Box::new([]),
+        );
+        let hit = |x: i32, y: i32| scene.trace(x, y).unwrap().rgba() == [1, 0, 0, 0xff];
+
+        let (center_x, center_y) = (width as i32 / 2, height as i32 / 2);
+        let across = (0..width as i32).filter(|x| hit(*x, center_y)).count() as i32;
+        let down = (0..height as i32).filter(|y| hit(center_x, *y)).count() as i32;
+
+        assert!(across > 0);
+        assert!((across - down).abs() <= 2);
+    }
+
+    #[test_case(Projection::Orthographic { scale: 2.0 }, true ; "orthographic rays are parallel")]
+    #[test_case(Projection::Perspective, false                ; "perspective rays diverge")]
+    fn test_projection_ray_directions(projection: Projection, parallel: bool) {
+        let mut cam = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            800,
+            600,
+        );
+        cam.set_projection(projection);
+
+        let rays: Vec<Ray> = [(0, 0), (799, 0), (400, 300), (0, 599), (799, 599)]
+            .iter()
+            .map(|(x, y)| cam.ray_for_pixel(*x, *y))
+            .collect();
+
+        let shares_direction = rays.iter().all(|ray| {
+            (ray.direction.x() - cam.direction.x()).abs() < 1e-12
+                && (ray.direction.y() - cam.direction.y()).abs() < 1e-12
+                && (ray.direction.z() - cam.direction.z()).abs() < 1e-12
+        });
+
+        assert_eq!(cam.projection(), projection);
+        assert_eq!(shares_direction, parallel);
+    }
+
+    #[test_case(Vector3D::new(0.0, 0.0, -5.0), Vector3D::new(0.0, 0.0, 0.0) ; "looking down +Z")]
+    #[test_case(Vector3D::new(3.0, 2.0, 1.0), Vector3D::new(-1.0, 0.5, 4.0) ; "looking askew")]
+    fn test_center_pixel_ray_looks_forward(position: Vector3D, look_at: Vector3D) {
+        // Odd sizes put a pixel right in the middle
+        let cam = Camera::new(&position, &look_at, 601, 401);
+        let ray = cam.ray_for_pixel(300, 200);
+
+        assert!(approx_eq(ray.direction.x(), cam.direction.x()));
+        assert!(approx_eq(ray.direction.y(), cam.direction.y()));
+        assert!(approx_eq(ray.direction.z(), cam.direction.z()));
+        assert_eq!(ray.start, cam.position);
+    }
+
+    #[test_case(720, 360 ; "two to one")]
+    #[test_case(200, 200 ; "square")]
+    fn test_equirectangular_wraps_around(width: u16, height: u16) {
+        let mut cam = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            width,
+            height,
+        );
+        cam.set_projection(Projection::Equirectangular);
+        let row = height as i32 / 2;
+
+        // Both edges look almost straight back, one pixel either side of the seam
+        let left = cam.ray_for_sample(0, row, (0.0, 0.5), 0);
+        let right = cam.ray_for_sample(width as i32 - 1, row, (1.0, 0.5), 0);
+        let behind = cam.direction.invert();
+
+        assert!(left.direction.to(&right.direction).length() < 1e-9);
+        assert!(left.direction.to(&behind).length() < 1e-2);
+        assert!((left.start.z() + 5.0).abs() < 1e-9);
+
+        // The middle of the image looks ahead, the top straight up
+        let ahead = cam.ray_for_sample(width as i32 / 2, row, (0.0, 0.0), 0);
+        let up = cam.ray_for_sample(width as i32 / 2, 0, (0.0, 0.0), 0);
+
+        assert!(ahead.direction.to(&cam.direction).length() < 1e-9);
+        assert!(up.direction.to(&cam.up).length() < 1e-9);
+    }
+
+    #[test]
+    fn test_equirectangular_depth_of_field_keeps_looking_back() {
+        let mut cam = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            200,
+            100,
+        );
+        cam.set_projection(Projection::Equirectangular);
+        let pinhole = cam.ray_for_pixel(0, 50);
+        cam.set_depth_of_field(0.5, 4.0);
+
+        let ray = cam.ray_for_pixel(0, 50);
+        let focus_point = pinhole.start.append(&pinhole.direction.scale(4.0));
+
+        assert!(ray.start != pinhole.start);
+        assert!(ray.direction.dot(&cam.direction) < -0.9);
+        // Passes through where the pinhole ray is at the focus distance
+        let to_focus = Vector3D::from(&ray.start).to(&focus_point);
+        assert!(to_focus.unit().to(&ray.direction).length() < 1e-9);
+    }
+
+    #[test_case(2.0, (799, 0), (-2.0 * 4.0 / 3.0, 2.0) ; "top right corner")]
+    #[test_case(1.0, (0, 599), (4.0 / 3.0, -1.0)       ; "bottom left corner")]
+    fn test_orthographic_ray_origins(scale: f64, pixel: (i32, i32), expected: (f64, f64)) {
+        let mut cam = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            800,
+            600,
+        );
+        cam.set_projection(Projection::Orthographic { scale });
+
+        // Pixel centers sit half a pixel in from the edges of the image plane
+        let ray = cam.ray_for_pixel(pixel.0, pixel.1);
+        let half_pixel = scale / 600.;
+
+        assert!((ray.start.x() - expected.0).abs() <= half_pixel + 1e-9);
+        assert!((ray.start.y() - expected.1).abs() <= half_pixel + 1e-9);
+        assert!((ray.start.z() + 5.0).abs() < 1e-6);
+    }
+
+    #[test_case((400, 300) ; "center pixel")]
+    #[test_case((1, 599)   ; "bottom left pixel")]
+    fn test_sample_offsets_span_the_pixel(pixel: (i32, i32)) {
+        let cam = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            800,
+            600,
+        );
+        let (x, y) = pixel;
+
+        let centered = cam.ray_for_sample(x, y, (0.5, 0.5), 0).direction;
+        let corner = cam.ray_for_sample(x, y, (1.0, 1.0), 0).direction;
+        let next_corner = cam.ray_for_sample(x + 1, y + 1, (0.0, 0.0), 0).direction;
+
+        assert_eq!(centered, cam.ray_for_pixel(x, y).direction);
+        assert!((&corner - &next_corner).length() < 1e-12);
+        assert!(corner.x() < centered.x() && corner.y() < centered.y());
+    }
+
+    #[test_case(Projection::Perspective                ; "perspective")]
+    #[test_case(Projection::Orthographic { scale: 2.0 } ; "orthographic")]
+    fn test_zero_aperture_matches_pinhole(projection: Projection) {
+        let position = Vector3D::new(1.0, 2.0, -5.0);
+        let look_at = Vector3D::new(0.0, 0.0, 0.0);
+
+        let mut pinhole = Camera::new(&position, &look_at, 800, 600);
+        pinhole.set_projection(projection);
+
+        let mut lens = Camera::new(&position, &look_at, 800, 600);
+        lens.set_projection(projection);
+        lens.set_depth_of_field(0., 2.5);
+
+        for (x, y) in [(0, 0), (799, 0), (400, 300), (123, 456)] {
+            let expected = pinhole.ray_for_pixel(x, y);
+            let ray = lens.ray_for_pixel(x, y);
+
+            assert_eq!(ray.start, expected.start);
+            assert_eq!(ray.direction, expected.direction);
+        }
+    }
+
+    #[test_case((400, 300) ; "center pixel")]
+    #[test_case((10, 590)  ; "corner pixel")]
+    fn test_aperture_rays_meet_on_focus_plane(pixel: (i32, i32)) {
+        let mut cam = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            800,
+            600,
+        );
+        let pinhole = cam.ray_for_pixel(pixel.0, pixel.1);
+        cam.set_depth_of_field(0.5, 4.0);
+
+        let ray = cam.ray_for_pixel(pixel.0, pixel.1);
+        let again = cam.ray_for_pixel(pixel.0, pixel.1);
+
+        // Same pixel, same jitter
+        assert_eq!(ray.start, again.start);
+        assert_eq!(ray.direction, again.direction);
+
+        assert!(ray.start != pinhole.start);
+        assert!((ray.start.z() + 5.0).abs() < 1e-9);
+
+        // Both rays pass through the same point on the plane z = -1
+        let at_focus = |ray: &Ray| {
+            let t = (-1.0 - ray.start.z()) / ray.direction.z();
+            (
+                ray.start.x() + t * ray.direction.x(),
+                ray.start.y() + t * ray.direction.y(),
+            )
+        };
+        let (x, y) = at_focus(&ray);
+        let (expected_x, expected_y) = at_focus(&pinhole);
+
+        assert!((x - expected_x).abs() < 1e-9);
+        assert!((y - expected_y).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_samples_of_a_pixel_spread_over_the_lens() {
+        let mut cam = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            800,
+            600,
+        );
+        cam.set_depth_of_field(0.5, 4.0);
+
+        let first = cam.ray_for_sample(400, 300, (0.25, 0.25), 0);
+        let second = cam.ray_for_sample(400, 300, (0.75, 0.75), 0);
+
+        assert!(first.start != second.start);
+        // The render's seed moves them too
+        assert!(cam.ray_for_sample(400, 300, (0.25, 0.25), 1).start != first.start);
+    }
+
+    // Columns along the middle row that the sphere shows up in
+    fn covered_columns(shutter: f64, velocity: Vector3D) -> Vec<i32> {
+        let mut cam = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            60,
+            60,
+        );
+        cam.set_shutter(shutter);
+        let sphere = Sphere::new(Vector3D::new(0.0, 0.0, 0.0), 1.0, Color::new(255, 0, 0))
+            .with_velocity(velocity);
+        let scene = Scene::new(
+            &mut cam,
+            Color::new(0, 0, 255),
+            Color::new(0, 0, 0),
+            Box::new([Box::new(sphere)]),
+            Box::new([]),
+        );
+
+        (0..60)
+            .filter(|x| scene.trace(*x, 30).unwrap().rgba() == [255, 0, 0, 255])
+            .collect()
+    }
+
+    #[test]
+    fn test_zero_shutter_freezes_motion() {
+        assert_eq!(
+            covered_columns(0.0, Vector3D::new(2.0, 0.0, 0.0)),
+            covered_columns(0.0, Vector3D::new(0.0, 0.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn test_shutter_blurs_moving_sphere() {
+        let frozen = covered_columns(0.0, Vector3D::new(2.0, 0.0, 0.0));
+        let blurred = covered_columns(1.0, Vector3D::new(2.0, 0.0, 0.0));
+
+        let span = |columns: &[i32]| columns.last().unwrap() - columns.first().unwrap();
+
+        assert!(span(&blurred) > span(&frozen), "{blurred:?} vs {frozen:?}");
+        // The sphere only moves towards +X, so it's still seen where it started
+        assert!(blurred.first() <= frozen.first());
+    }
+
+    #[test_case(0.0, 0.0, (0.0, 0.0, 1.0), (-1.0, 0.0, 0.0)    ; "no rotation looks down +Z")]
+    #[test_case(90.0, 0.0, (1.0, 0.0, 0.0), (0.0, 0.0, 1.0)    ; "quarter yaw turns right to +Z")]
+    #[test_case(180.0, 0.0, (0.0, 0.0, -1.0), (1.0, 0.0, 0.0)  ; "half yaw looks back down -Z")]
+    #[test_case(0.0, 45.0, (0.0, 0.5f64.sqrt(), 0.5f64.sqrt()), (-1.0, 0.0, 0.0) ; "pitch leaves right level")]
+    fn test_set_orientation(
+        yaw: f64,
+        pitch: f64,
+        expected_direction: (f64, f64, f64),
+        expected_right: (f64, f64, f64),
+    ) {
+        let mut cam = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(3.0, 1.0, 0.0),
+            600,
+            600,
+        );
+        cam.set_orientation(yaw, pitch);
+
+        assert!((cam.direction.x() - expected_direction.0).abs() < 1e-12);
+        assert!((cam.direction.y() - expected_direction.1).abs() < 1e-12);
+        assert!((cam.direction.z() - expected_direction.2).abs() < 1e-12);
+
+        assert!((cam.right.x() - expected_right.0).abs() < 1e-12);
+        assert!((cam.right.y() - expected_right.1).abs() < 1e-12);
+        assert!((cam.right.z() - expected_right.2).abs() < 1e-12);
+
+        assert!(cam.up.y() > 0.);
+        assert!(cam.up.dot(&cam.direction).abs() < 1e-12);
+    }
+
+    #[test_case(90.0  ; "straight up")]
+    #[test_case(-90.0 ; "straight down")]
+    #[test_case(500.0 ; "past the pole")]
+    fn test_set_orientation_clamps_pitch(pitch: f64) {
+        let mut cam = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            600,
+            600,
+        );
+        cam.set_orientation(0.0, pitch);
+
+        let limit = MAX_PITCH.to_radians().sin();
+
+        assert!((cam.direction.y().abs() - limit).abs() < 1e-12);
+        assert!(cam.right.length().is_finite());
+        assert!(cam.direction.z() > 0.);
+    }
+
     #[test_case(0, 600, -0.9983333333333333     ; "ndc_x at left edge")]
     #[test_case(300, 600, 0.0016666666666667778   ; "ndc_x at center")]
     #[test_case(599, 600, 0.9983333333333333      ; "ndc_x at right edge")]
@@ -223,4 +1008,156 @@ mod tests {
         assert!(approx_eq(cam.up.y(), exp_up.y()));
         assert!(approx_eq(cam.up.z(), exp_up.z()));
     }
+
+    fn assert_same_ray(a: &Ray, b: &Ray) {
+        assert_eq!(a.start, b.start);
+        assert_eq!(a.direction, b.direction);
+        assert_eq!(a.time, b.time);
+    }
+
+    #[test_case(Projection::Perspective, 0.0            ; "pinhole")]
+    #[test_case(Projection::Orthographic { scale: 2.0 }, 0.0 ; "orthographic")]
+    #[test_case(Projection::Perspective, 0.5            ; "thin lens")]
+    fn test_precomputed_rays_match(projection: Projection, aperture: f64) {
+        let mut camera = Camera::new(
+            &Vector3D::new(1.0, 2.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            8,
+            6,
+        );
+        camera.set_projection(projection);
+        camera.set_depth_of_field(aperture, 5.0);
+
+        let rays = camera.precompute_rays();
+
+        assert_eq!(rays.len(), 8 * 6);
+        for (x, y) in [(0, 0), (7, 0), (3, 4), (7, 5)] {
+            let fresh = camera.pinhole_ray(x, y, (0.5, 0.5));
+
+            assert_same_ray(&rays[y as usize * 8 + x as usize], &fresh);
+            assert_same_ray(camera.cached_ray(x, y).unwrap(), &fresh);
+            // The lens moves the ray off the pinhole
+            if aperture == 0.0 {
+                assert_same_ray(&camera.ray_for_pixel(x, y), &fresh);
+            }
+        }
+        assert!(camera.cached_ray(8, 0).is_none());
+        assert!(camera.cached_ray(0, -1).is_none());
+    }
+
+    #[test_case(Projection::Perspective, (0.1, 0.9)                 ; "perspective")]
+    #[test_case(Projection::Orthographic { scale: 2.0 }, (0.8, 0.3) ; "orthographic")]
+    #[test_case(Projection::Equirectangular, (0.25, 0.6)            ; "panorama")]
+    #[test_case(Projection::Perspective, (0.5, 0.5)                 ; "pixel center")]
+    fn test_samples_start_from_cached_rays(projection: Projection, offset: (f64, f64)) {
+        let mut camera = Camera::new(
+            &Vector3D::new(1.0, 2.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            8,
+            6,
+        );
+        camera.set_projection(projection);
+
+        for (x, y) in [(0, 0), (7, 0), (3, 4), (7, 5)] {
+            let (sampled, fresh) = (
+                camera.sample_pinhole_ray(x, y, offset),
+                camera.pinhole_ray(x, y, offset),
+            );
+
+            assert!(sampled.start.distance_squared_to(&fresh.start) < 1e-24);
+            assert!(sampled.direction.distance_squared_to(&fresh.direction) < 1e-24);
+            assert_same_ray(&camera.ray_for_sample(x, y, offset, 0), &sampled);
+        }
+    }
+
+    #[test]
+    fn test_set_resolution_rebuilds_rays() {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            8,
+            6,
+        );
+        assert_eq!(camera.precompute_rays().len(), 8 * 6);
+
+        camera.set_resolution(4, 4);
+
+        assert_eq!(camera.resolution(), (4, 4));
+        assert_eq!(camera.aspect_ratio(), 1.0);
+        assert_eq!(camera.precompute_rays().len(), 4 * 4);
+    }
+
+    #[test]
+    fn test_precomputed_rays_follow_the_camera() {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            4,
+            4,
+        );
+        let before = camera.cached_ray(1, 2).unwrap().direction.clone();
+
+        camera.move_to(Vector3D::new(3.0, 1.0, -4.0));
+        assert_same_ray(
+            camera.cached_ray(1, 2).unwrap(),
+            &camera.pinhole_ray(1, 2, (0.5, 0.5)),
+        );
+
+        camera.set_orientation(30.0, 10.0);
+        assert_same_ray(
+            camera.cached_ray(1, 2).unwrap(),
+            &camera.pinhole_ray(1, 2, (0.5, 0.5)),
+        );
+
+        camera.set_fov(90);
+        assert_same_ray(
+            camera.cached_ray(1, 2).unwrap(),
+            &camera.pinhole_ray(1, 2, (0.5, 0.5)),
+        );
+        assert_ne!(camera.cached_ray(1, 2).unwrap().direction, before);
+    }
+
+    fn two_spheres(camera: &mut Camera) -> Scene<'_> {
+        Scene::new(
+            camera,
+            Color::new(0, 0, 0),
+            Color::new(0, 0, 0),
+            Box::new([
+                Box::new(Sphere::new(
+                    Vector3D::new(-5.0, 0.0, 0.0),
+                    1.0,
+                    Color::new(255, 0, 0),
+                )),
+                Box::new(Sphere::new(
+                    Vector3D::new(5.0, 0.0, 0.0),
+                    1.0,
+                    Color::new(0, 255, 0),
+                )),
+            ]),
+            Box::new([]),
+        )
+    }
+
+    #[test_case(60, 60 ; "square")]
+    #[test_case(40, 80 ; "portrait is narrower across")]
+    fn test_frame_fits_the_scene(width: u16, height: u16) {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            width,
+            height,
+        );
+        let bounds = two_spheres(&mut camera).bounds().unwrap();
+
+        camera.frame(&bounds);
+
+        assert_eq!(camera.direction, Vector3D::new(0.0, 0.0, 1.0));
+        let scene = two_spheres(&mut camera);
+        let middle = height as i32 / 2;
+        let picked: Vec<Option<usize>> = (0..width as i32).map(|x| scene.pick(x, middle)).collect();
+
+        // Both spheres in view with some room to spare at the edges
+        assert!(picked.contains(&Some(0)) && picked.contains(&Some(1)));
+        assert_eq!((picked[0], picked[width as usize - 1]), (None, None));
+    }
 }
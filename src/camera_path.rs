@@ -0,0 +1,220 @@
+use std::{f64::consts::PI, time::Duration};
+
+use crate::vector::Vector3D;
+
+// How close to straight opposite two keyframes can be before slerp can't tell which way to turn
+const ANTIPODAL_EPSILON: f64 = 1e-9;
+
+// Where the camera is and what it's looking at some time into an animation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyframe {
+    pub time: Duration,
+    pub position: Vector3D,
+    pub target: Vector3D,
+}
+
+// Camera animation through keyframes. Between two of them the target moves in a straight line
+// while the camera swings around it, so keyframes at the same distance from a fixed target
+// trace an arc rather than cutting across it.
+#[derive(Debug, Clone, Default)]
+pub struct CameraPath {
+    // Sorted by time
+    keyframes: Vec<Keyframe>,
+}
+
+impl CameraPath {
+    pub fn new(mut keyframes: Vec<Keyframe>) -> Self {
+        keyframes.sort_by_key(|keyframe| keyframe.time);
+
+        CameraPath { keyframes }
+    }
+
+    pub fn with_keyframe(mut self, time: Duration, position: Vector3D, target: Vector3D) -> Self {
+        let index = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.time <= time);
+        self.keyframes.insert(
+            index,
+            Keyframe {
+                time,
+                position,
+                target,
+            },
+        );
+        self
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    // Time of the last keyframe, when the animation comes to rest
+    pub fn duration(&self) -> Duration {
+        self.keyframes
+            .last()
+            .map_or(Duration::ZERO, |keyframe| keyframe.time)
+    }
+
+    // Camera position and target at time t. Before the first keyframe and after the last the
+    // camera holds still there. None on a path without keyframes.
+    pub fn sample(&self, t: Duration) -> Option<(Vector3D, Vector3D)> {
+        let next = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.time <= t);
+
+        let (from, to) = match next {
+            0 => return self.keyframe_pose(0),
+            next if next == self.keyframes.len() => return self.keyframe_pose(next - 1),
+            next => (&self.keyframes[next - 1], &self.keyframes[next]),
+        };
+
+        if t == from.time {
+            return Some((from.position.clone(), from.target.clone()));
+        }
+
+        let s = (t - from.time).as_secs_f64() / (to.time - from.time).as_secs_f64();
+
+        let target = from.target.lerp(&to.target, s);
+        let (from_offset, to_offset) = (&from.position - &from.target, &to.position - &to.target);
+        let direction = swing(&from_offset.unit(), &to_offset.unit(), s);
+        let distance = from_offset.length() + (to_offset.length() - from_offset.length()) * s;
+
+        Some((target.append(&direction.scale(distance)), target))
+    }
+
+    fn keyframe_pose(&self, index: usize) -> Option<(Vector3D, Vector3D)> {
+        let keyframe = self.keyframes.get(index)?;
+
+        Some((keyframe.position.clone(), keyframe.target.clone()))
+    }
+}
+
+// Turns from one unit direction to the other. Any way around is as short for opposite
+// directions, those swing level around the vertical, or around X when looking straight up or
+// down.
+fn swing(from: &Vector3D, to: &Vector3D, s: f64) -> Vector3D {
+    if from.dot(to) > ANTIPODAL_EPSILON - 1. {
+        return from.slerp(to, s).unit();
+    }
+
+    let side = match from.cross(&Vector3D::new(0., 1., 0.)) {
+        side if side.length() > ANTIPODAL_EPSILON => side.unit(),
+        _ => from.cross(&Vector3D::new(1., 0., 0.)).unit(),
+    };
+
+    from.scale((PI * s).cos())
+        .append(&side.scale((PI * s).sin()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::approx_eq;
+    use test_case::test_case;
+
+    fn quarter_orbit() -> CameraPath {
+        CameraPath::new(vec![
+            Keyframe {
+                time: Duration::from_secs(2),
+                position: Vector3D::new(10.0, 0.0, 0.0),
+                target: Vector3D::new(0.0, 0.0, 0.0),
+            },
+            Keyframe {
+                time: Duration::ZERO,
+                position: Vector3D::new(0.0, 0.0, -10.0),
+                target: Vector3D::new(0.0, 0.0, 0.0),
+            },
+        ])
+    }
+
+    #[test_case(0    ; "first keyframe")]
+    #[test_case(2000 ; "last keyframe")]
+    fn test_sample_at_keyframe_is_exact(millis: u64) {
+        let path = quarter_orbit();
+        let time = Duration::from_millis(millis);
+        let keyframe = path
+            .keyframes()
+            .iter()
+            .find(|keyframe| keyframe.time == time)
+            .unwrap();
+
+        assert_eq!(
+            path.sample(time),
+            Some((keyframe.position.clone(), keyframe.target.clone()))
+        );
+    }
+
+    #[test]
+    fn test_sample_midway_swings_around_the_target() {
+        let (position, target) = quarter_orbit().sample(Duration::from_secs(1)).unwrap();
+        let half = 10.0 * std::f64::consts::FRAC_1_SQRT_2;
+
+        assert_eq!(target, Vector3D::new(0.0, 0.0, 0.0));
+        assert!(approx_eq(position.length(), 10.0));
+        assert!(approx_eq(position.x(), half));
+        assert!(approx_eq(position.z(), -half));
+    }
+
+    #[test]
+    fn test_sample_interpolates_target_and_distance() {
+        let path = CameraPath::default()
+            .with_keyframe(
+                Duration::from_secs(4),
+                Vector3D::new(2.0, 0.0, -20.0),
+                Vector3D::new(2.0, 0.0, 0.0),
+            )
+            .with_keyframe(
+                Duration::ZERO,
+                Vector3D::new(0.0, 0.0, -10.0),
+                Vector3D::new(0.0, 0.0, 0.0),
+            );
+
+        let (position, target) = path.sample(Duration::from_secs(1)).unwrap();
+
+        assert_eq!(path.duration(), Duration::from_secs(4));
+        assert!(approx_eq(target.x(), 0.5));
+        assert!(approx_eq(position.x(), 0.5));
+        assert!(approx_eq(position.z(), -12.5));
+    }
+
+    #[test_case(0     ; "before the start")]
+    #[test_case(10000 ; "after the end")]
+    fn test_sample_holds_outside_the_keyframes(millis: u64) {
+        let first = (Vector3D::new(0.0, 0.0, -10.0), Vector3D::new(0.0, 0.0, 0.0));
+        let last = (Vector3D::new(0.0, 5.0, 0.0), Vector3D::new(1.0, 0.0, 0.0));
+        let path = CameraPath::default()
+            .with_keyframe(Duration::from_secs(1), first.0.clone(), first.1.clone())
+            .with_keyframe(Duration::from_secs(3), last.0.clone(), last.1.clone());
+
+        let expected = if millis == 0 { first } else { last };
+
+        assert_eq!(path.sample(Duration::from_millis(millis)), Some(expected));
+    }
+
+    #[test_case((0.0, 0.0, -10.0), (0.0, 0.0, 10.0) ; "front to back")]
+    #[test_case((0.0, 10.0, 0.0), (0.0, -10.0, 0.0) ; "top to bottom")]
+    fn test_sample_swings_between_opposite_sides(from: (f64, f64, f64), to: (f64, f64, f64)) {
+        let path = CameraPath::default()
+            .with_keyframe(
+                Duration::ZERO,
+                Vector3D::new(from.0, from.1, from.2),
+                Vector3D::new(0.0, 0.0, 0.0),
+            )
+            .with_keyframe(
+                Duration::from_secs(2),
+                Vector3D::new(to.0, to.1, to.2),
+                Vector3D::new(0.0, 0.0, 0.0),
+            );
+
+        for millis in [500, 1000, 1500] {
+            let (position, _) = path.sample(Duration::from_millis(millis)).unwrap();
+
+            assert!(approx_eq(position.length(), 10.0), "{position}");
+        }
+    }
+
+    #[test]
+    fn test_sample_without_keyframes() {
+        assert_eq!(CameraPath::default().sample(Duration::from_secs(1)), None);
+    }
+}
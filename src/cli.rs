@@ -0,0 +1,114 @@
+use color_eyre::eyre::{eyre, Result, WrapErr};
+
+use crate::camera::Resolution;
+
+pub const USAGE: &str =
+    "usage: ray-tracer [--width <pixels>] [--height <pixels>] [--scene <file>] [--output <png>]";
+
+// What to render and where, from the command line
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    // Left out, the size comes from the scene file's camera or the default
+    pub width: Option<u16>,
+    pub height: Option<u16>,
+    // JSON scene description, the built-in demo scene is used without one
+    pub scene: Option<String>,
+    // PNG to render to, without one the scene is shown in a window
+    pub output: Option<String>,
+}
+
+impl Config {
+    // Takes the arguments after the program name, as `--flag value` or `--flag=value`
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut config = Config::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| eyre!("`{flag}` needs a value\n{USAGE}"))
+            };
+
+            match flag.as_str() {
+                "--width" => config.width = Some(parse_dimension(&flag, &value()?)?),
+                "--height" => config.height = Some(parse_dimension(&flag, &value()?)?),
+                "--scene" => config.scene = Some(value()?),
+                "--output" => config.output = Some(value()?),
+                _ => return Err(eyre!("unknown argument `{flag}`\n{USAGE}")),
+            }
+        }
+
+        Ok(config)
+    }
+
+    // The size to render at, sizes given on the command line win over the other one
+    pub fn resolution_over(&self, (width, height): Resolution) -> Resolution {
+        (self.width.unwrap_or(width), self.height.unwrap_or(height))
+    }
+}
+
+fn parse_dimension(flag: &str, value: &str) -> Result<u16> {
+    match value.parse() {
+        Ok(0) => Err(eyre!("`{flag}` must be at least one pixel")),
+        parsed => parsed.wrap_err_with(|| format!("`{flag}` must be a number of pixels")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_parse_full_command_line() {
+        let config = Config::parse(args(
+            "--width 320 --height=240 --scene scenes/demo.json --output out.png",
+        ))
+        .unwrap();
+
+        assert_eq!(
+            config,
+            Config {
+                width: Some(320),
+                height: Some(240),
+                scene: Some("scenes/demo.json".to_string()),
+                output: Some("out.png".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_defaults_to_interactive_demo() {
+        assert_eq!(Config::parse(args("")).unwrap(), Config::default());
+    }
+
+    #[test_case("", (800, 600)                      ; "scene size without flags")]
+    #[test_case("--width 320", (320, 600)           ; "width flag wins")]
+    #[test_case("--width 320 --height 240", (320, 240) ; "both flags win")]
+    fn test_resolution_over(line: &str, expected: Resolution) {
+        let config = Config::parse(args(line)).unwrap();
+
+        assert_eq!(config.resolution_over((800, 600)), expected);
+    }
+
+    #[test_case("--width", "needs a value"               ; "missing value")]
+    #[test_case("--width wide", "number of pixels"       ; "not a number")]
+    #[test_case("--height 0", "at least one pixel"       ; "zero height")]
+    #[test_case("--fov 90", "unknown argument `--fov`"   ; "unknown flag")]
+    fn test_parse_errors(line: &str, expected: &str) {
+        let error = Config::parse(args(line)).unwrap_err();
+
+        assert!(format!("{error:#}").contains(expected), "{error:#}");
+    }
+}
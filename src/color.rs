@@ -1,6 +1,11 @@
-use color_eyre::eyre::{eyre, Result};
+use color_eyre::eyre::Result;
 use regex::Regex;
 
+use crate::{
+    error::{self, RayTracerError},
+    json::{FromJson, Json, ToJson},
+};
+
 #[derive(Default, Debug, Clone, Copy)]
 pub struct Color {
     r: u8,
@@ -9,10 +14,27 @@ pub struct Color {
 }
 
 impl Color {
-    pub fn new(r: u8, g: u8, b: u8) -> Self {
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
         Color { r, g, b }
     }
 
+    // Channels from 0 to 1 without any sRGB encoding, out of range values are clamped and the
+    // rest truncated like scale does
+    pub fn from_f64(r: f64, g: f64, b: f64) -> Self {
+        let channel = |value: f64| (value.clamp(0., 1.) * 255.) as u8;
+
+        Color {
+            r: channel(r),
+            g: channel(g),
+            b: channel(b),
+        }
+    }
+
+    // Bytes can't leave the displayable range, this is here to match ColorF::clamp
+    pub fn clamp(&self) -> Self {
+        *self
+    }
+
     pub fn rgba(&self) -> [u8; 4] {
         [self.r, self.g, self.b, 0xff]
     }
@@ -33,9 +55,9 @@ impl Color {
         }
     }
 
-    pub fn scale(&self, factor: f64) -> Result<Self> {
+    pub fn scale(&self, factor: f64) -> error::Result<Self> {
         if factor < 0. {
-            Err(eyre!("Can't scale color values by negative amount"))
+            Err(RayTracerError::NegativeColorScale(factor))
         } else {
             Ok(Color {
                 r: (self.r as f64 * factor) as u8,
@@ -45,62 +67,319 @@ impl Color {
         }
     }
 
+    // Approximate color of a blackbody at the temperature, after Tanner Helland's fit to the
+    // CIE 1964 color matching data. Valid between 1000 K and 40000 K, anything else is clamped.
+    pub fn from_kelvin(kelvin: f64) -> Self {
+        let temperature = kelvin.clamp(1000., 40000.) / 100.;
+        let channel = |value: f64| value.clamp(0., 255.).round() as u8;
+
+        let r = if temperature <= 66. {
+            255.
+        } else {
+            329.698727446 * (temperature - 60.).powf(-0.1332047592)
+        };
+
+        let g = if temperature <= 66. {
+            99.4708025861 * temperature.ln() - 161.1195681661
+        } else {
+            288.1221695283 * (temperature - 60.).powf(-0.0755148492)
+        };
+
+        let b = if temperature >= 66. {
+            255.
+        } else if temperature <= 19. {
+            0.
+        } else {
+            138.5177312231 * (temperature - 10.).ln() - 305.0447927307
+        };
+
+        Color {
+            r: channel(r),
+            g: channel(g),
+            b: channel(b),
+        }
+    }
+
+    // Combines the channels the way image editors' layer modes do, this color being the bottom
+    // layer. Works on the encoded values, not linear light.
+    pub fn blend(&self, other: Color, mode: BlendMode) -> Self {
+        let channel = |base: u8, top: u8| {
+            let blended = mode.apply(base as f64 / 255., top as f64 / 255.);
+
+            (blended.clamp(0., 1.) * 255.).round() as u8
+        };
+
+        Color {
+            r: channel(self.r, other.r),
+            g: channel(self.g, other.g),
+            b: channel(self.b, other.b),
+        }
+    }
+
+    // Convert from the sRGB encoding used for input and display into linear light
+    pub fn srgb_to_linear(&self) -> ColorF {
+        ColorF {
+            r: srgb_channel_to_linear(self.r),
+            g: srgb_channel_to_linear(self.g),
+            b: srgb_channel_to_linear(self.b),
+        }
+    }
+
+    // Relative luminance using Rec. 709 weights over normalized channels
+    pub fn luminance(&self) -> f64 {
+        (0.2126 * self.r as f64 + 0.7152 * self.g as f64 + 0.0722 * self.b as f64) / 255.
+    }
+
+    pub fn grayscale(&self) -> Self {
+        let value = (self.luminance() * 255.).round() as u8;
+
+        Color {
+            r: value,
+            g: value,
+            b: value,
+        }
+    }
+
     // Parse hex colors like #fff, #abc123
-    pub fn parse(color: impl Into<String>) -> Result<Self> {
+    pub fn parse(color: impl Into<String>) -> error::Result<Self> {
         let color: String = color.into().replace(' ', "");
+        let invalid = || RayTracerError::ColorParse(color.clone());
+        let hex = |digits: &str| u8::from_str_radix(digits, 16).map_err(|_| invalid());
 
+        // Counting the leading #
         match color.chars().count() {
-            6 => {
-                let six_digit_regex = Regex::new(r"#([\da-f]{2})([\da-f]{2})([\da-f]{2})/i")?;
+            7 => {
+                let six_digit_regex = Regex::new(r"(?i)^#([\da-f]{2})([\da-f]{2})([\da-f]{2})$")
+                    .map_err(|_| invalid())?;
                 if let Some((_, [r, g, b])) =
                     six_digit_regex.captures(&color).map(|c| c.extract())
                 {
-                    let (r, g, b) = (r.parse()?, g.parse()?, b.parse()?);
+                    let (r, g, b) = (hex(r)?, hex(g)?, hex(b)?);
 
                     Ok(Color { r, g, b })
                 } else {
-                    Err(eyre!(r#"Error parsing color from string: "{color}""#))
+                    Err(invalid())
                 }
             }
-            3 => {
-                let three_digit_regex = Regex::new(r"#([\da-f])([\da-f])([\da-f])")?;
+            4 => {
+                let three_digit_regex =
+                    Regex::new(r"(?i)^#([\da-f])([\da-f])([\da-f])$").map_err(|_| invalid())?;
                 if let Some((_, [r, g, b])) =
                     three_digit_regex.captures(&color).map(|c| c.extract())
                 {
-                    let (r, g, b) = (r.parse()?, g.parse()?, b.parse()?);
+                    // Each digit is repeated, #abc is short for #aabbcc
+                    let (r, g, b) = (hex(r)? * 0x11, hex(g)? * 0x11, hex(b)? * 0x11);
 
                     Ok(Color { r, g, b })
                 } else {
-                    Err(eyre!(r#"Error parsing color from string: "{color}""#))
+                    Err(invalid())
                 }
             }
             _ => {
-                if color.starts_with("rgb(") && color.ends_with(')') && color.len() == 10 {
-                    let colors: Box<[&str]> = color[3..color.len() - 1].split(',').collect();
+                if color.starts_with("rgb(") && color.ends_with(')') {
+                    let colors: Box<[&str]> = color[4..color.len() - 1].split(',').collect();
 
-                    let (r, g, b) = (colors[0].parse()?, colors[1].parse()?, colors[2].parse()?);
+                    if colors.len() != 3 {
+                        return Err(invalid());
+                    }
+
+                    let channel = |text: &str| text.parse().map_err(|_| invalid());
+                    let (r, g, b) = (
+                        channel(colors[0])?,
+                        channel(colors[1])?,
+                        channel(colors[2])?,
+                    );
 
                     Ok(Color { r, g, b })
                 } else {
-                    Err(eyre!(r#"Error parsing color from string: "{color}""#))
+                    Err(invalid())
                 }
             }
         }
     }
 }
 
+// Piecewise sRGB transfer function, see IEC 61966-2-1
+fn srgb_channel_to_linear(channel: u8) -> f64 {
+    let channel = channel as f64 / 255.;
+
+    if channel <= 0.04045 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_channel_to_srgb(channel: f64) -> u8 {
+    let channel = channel.clamp(0., 1.);
+
+    let encoded = if channel <= 0.0031308 {
+        channel * 12.92
+    } else {
+        1.055 * channel.powf(1. / 2.4) - 0.055
+    };
+
+    (encoded * 255.).round() as u8
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+    // Only ever darkens, white leaves the other color as it is
+    Multiply,
+    // Only ever lightens, black leaves the other color as it is
+    Screen,
+    // Multiply on the base's dark channels and screen on its light ones
+    Overlay,
+    Add,
+}
+
+impl BlendMode {
+    // Both channels and the result run from 0 to 1, the caller clamps
+    fn apply(&self, base: f64, top: f64) -> f64 {
+        match self {
+            BlendMode::Multiply => base * top,
+            BlendMode::Screen => 1. - (1. - base) * (1. - top),
+            BlendMode::Overlay if base < 0.5 => 2. * base * top,
+            BlendMode::Overlay => 1. - 2. * (1. - base) * (1. - top),
+            BlendMode::Add => base + top,
+        }
+    }
+}
+
+// How light brighter than white is squeezed into the displayable range before quantizing
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToneMap {
+    // Anything brighter than white is cut off
+    #[default]
+    Clamp,
+    // x / (1 + x), never quite reaches white
+    Reinhard,
+    // Narkowicz's fit of the ACES filmic curve, a gentle toe and a soft shoulder
+    AcesFilmic,
+}
+
+impl ToneMap {
+    pub fn apply(&self, channel: f64) -> f64 {
+        let channel = channel.max(0.);
+
+        match self {
+            ToneMap::Clamp => channel.min(1.),
+            ToneMap::Reinhard => channel / (1. + channel),
+            ToneMap::AcesFilmic => {
+                let mapped =
+                    channel * (2.51 * channel + 0.03) / (channel * (2.43 * channel + 0.59) + 0.14);
+
+                mapped.clamp(0., 1.)
+            }
+        }
+    }
+}
+
+// Floating point color in linear light, used for shading math
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct ColorF {
+    r: f64,
+    g: f64,
+    b: f64,
+}
+
+impl ColorF {
+    pub fn new(r: f64, g: f64, b: f64) -> Self {
+        ColorF { r, g, b }
+    }
+
+    pub fn r(&self) -> f64 {
+        self.r
+    }
+
+    pub fn g(&self) -> f64 {
+        self.g
+    }
+
+    pub fn b(&self) -> f64 {
+        self.b
+    }
+
+    pub fn add(&self, addend: ColorF) -> Self {
+        ColorF {
+            r: self.r + addend.r,
+            g: self.g + addend.g,
+            b: self.b + addend.b,
+        }
+    }
+
+    pub fn multiply(&self, multiplier: ColorF) -> Self {
+        ColorF {
+            r: self.r * multiplier.r,
+            g: self.g * multiplier.g,
+            b: self.b * multiplier.b,
+        }
+    }
+
+    pub fn scale(&self, factor: f64) -> Self {
+        ColorF {
+            r: self.r * factor,
+            g: self.g * factor,
+            b: self.b * factor,
+        }
+    }
+
+    // Every channel limited to the range from 0 to 1
+    pub fn clamp(&self) -> Self {
+        ColorF {
+            r: self.r.clamp(0., 1.),
+            g: self.g.clamp(0., 1.),
+            b: self.b.clamp(0., 1.),
+        }
+    }
+
+    // Convert from linear light back into the sRGB encoding, clamping out of range values
+    pub fn linear_to_srgb(&self) -> Color {
+        self.tone_mapped(ToneMap::Clamp)
+    }
+
+    // Like linear_to_srgb, compressing bright values with the tone map first
+    pub fn tone_mapped(&self, tone_map: ToneMap) -> Color {
+        Color {
+            r: linear_channel_to_srgb(tone_map.apply(self.r)),
+            g: linear_channel_to_srgb(tone_map.apply(self.g)),
+            b: linear_channel_to_srgb(tone_map.apply(self.b)),
+        }
+    }
+}
+
+impl ToJson for Color {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("r", self.r.to_json()),
+            ("g", self.g.to_json()),
+            ("b", self.b.to_json()),
+        ])
+    }
+}
+
+// Colors can also be written as strings in any format parse understands
+impl FromJson for Color {
+    fn from_json(json: &Json) -> Result<Self> {
+        match json {
+            Json::String(color) => Ok(Color::parse(color.as_str())?),
+            _ => Ok(Color::new(json.get("r")?, json.get("g")?, json.get("b")?)),
+        }
+    }
+}
+
 impl TryFrom<String> for Color {
-    type Error = color_eyre::Report;
+    type Error = RayTracerError;
 
-    fn try_from(value: String) -> Result<Self> {
+    fn try_from(value: String) -> error::Result<Self> {
         Color::parse(value)
     }
 }
 
 impl TryFrom<&str> for Color {
-    type Error = color_eyre::Report;
+    type Error = RayTracerError;
 
-    fn try_from(value: &str) -> Result<Self> {
+    fn try_from(value: &str) -> error::Result<Self> {
         Color::parse(value)
     }
 }
@@ -166,3 +445,183 @@ pub const CYAN: Color = Color {
     g: 255,
     b: 255,
 };
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(0x00, 0.0          ; "black stays black")]
+    #[test_case(0x80, 0.2158605    ; "mid-gray is roughly 0.216 linear")]
+    #[test_case(0x0a, 0.0030353    ; "dark values use the linear segment")]
+    #[test_case(0xff, 1.0          ; "white stays white")]
+    fn test_srgb_to_linear(channel: u8, expected: f64) {
+        let linear = Color::new(channel, channel, channel).srgb_to_linear();
+
+        assert!((linear.r() - expected).abs() < 1e-6);
+        assert!((linear.g() - expected).abs() < 1e-6);
+        assert!((linear.b() - expected).abs() < 1e-6);
+    }
+
+    #[test_case(0.2158605, 0x80 ; "mid-gray linear encodes to 0x80")]
+    #[test_case(-1.0, 0x00      ; "negative values clamp to black")]
+    #[test_case(4.0, 0xff       ; "values above one clamp to white")]
+    fn test_linear_to_srgb(channel: f64, expected: u8) {
+        let color = ColorF::new(channel, channel, channel).linear_to_srgb();
+
+        assert_eq!(color.rgba(), [expected, expected, expected, 0xff]);
+    }
+
+    #[test_case((1.5, 0.5, -0.1), (255, 127, 0) ; "out of range channels are clamped")]
+    #[test_case((0.0, 1.0, 0.2), (0, 255, 51)    ; "in range channels are scaled")]
+    fn test_from_f64(channels: (f64, f64, f64), expected: (u8, u8, u8)) {
+        let color = Color::from_f64(channels.0, channels.1, channels.2);
+
+        assert_eq!(color.rgba(), Color::from(expected).rgba());
+        assert_eq!(color.clamp().rgba(), color.rgba());
+    }
+
+    #[test]
+    fn test_color_f_clamp() {
+        let clamped = ColorF::new(1.5, 0.5, -0.1).clamp();
+
+        assert_eq!(clamped, ColorF::new(1.0, 0.5, 0.0));
+    }
+
+    #[test_case(6500. ; "daylight")]
+    #[test_case(6600. ; "where the fit's pieces meet")]
+    fn test_from_kelvin_near_white(kelvin: f64) {
+        let [r, g, b, _] = Color::from_kelvin(kelvin).rgba();
+
+        assert!(
+            [r, g, b].iter().all(|channel| *channel > 235),
+            "{r} {g} {b}"
+        );
+    }
+
+    #[test_case(1900. ; "candle")]
+    #[test_case(3000. ; "warm bulb")]
+    #[test_case(500.  ; "below the range is clamped")]
+    fn test_from_kelvin_warm(kelvin: f64) {
+        let [r, g, b, _] = Color::from_kelvin(kelvin).rgba();
+
+        assert!(r > g && g > b, "{r} {g} {b}");
+    }
+
+    #[test_case(10000. ; "clear sky")]
+    #[test_case(40000. ; "top of the range")]
+    #[test_case(90000. ; "above the range is clamped")]
+    fn test_from_kelvin_cool(kelvin: f64) {
+        let [r, g, b, _] = Color::from_kelvin(kelvin).rgba();
+
+        assert!(b > g && g > r, "{r} {g} {b}");
+    }
+
+    #[test_case(BlendMode::Screen, |blended, grey| blended > grey   ; "screen brightens")]
+    #[test_case(BlendMode::Multiply, |blended, grey| blended < grey ; "multiply darkens")]
+    #[test_case(BlendMode::Add, |blended, _| blended == 0xff        ; "add saturates")]
+    fn test_blend_mid_grays(mode: BlendMode, expected: fn(u8, u8) -> bool) {
+        let grey = Color::new(0x80, 0x80, 0x80);
+        let [r, g, b, _] = grey.blend(grey, mode).rgba();
+
+        assert!(expected(r, grey.r), "{r}");
+        assert_eq!((r, g), (g, b));
+    }
+
+    #[test_case(BlendMode::Multiply, WHITE ; "multiply by white")]
+    #[test_case(BlendMode::Screen, BLACK   ; "screen with black")]
+    #[test_case(BlendMode::Add, BLACK      ; "add black")]
+    #[test_case(BlendMode::Overlay, GREY   ; "overlay with middle gray")]
+    fn test_blend_identity(mode: BlendMode, identity: Color) {
+        let color = Color::new(200, 10, 99);
+
+        assert_eq!(color.blend(identity, mode).rgba(), color.rgba());
+    }
+
+    #[test_case(ToneMap::Clamp, 4.0, |channel| channel == 0xff    ; "clamp cuts bright values to white")]
+    #[test_case(ToneMap::Reinhard, 4.0, |channel| channel < 0xff  ; "reinhard keeps bright values below white")]
+    #[test_case(ToneMap::AcesFilmic, 4.0, |channel| channel < 0xff ; "filmic keeps bright values below white")]
+    #[test_case(ToneMap::Reinhard, 0.0, |channel| channel == 0x00 ; "reinhard leaves black alone")]
+    #[test_case(ToneMap::AcesFilmic, -1.0, |channel| channel == 0x00 ; "filmic clamps negative values")]
+    fn test_tone_mapped(tone_map: ToneMap, channel: f64, expected: fn(u8) -> bool) {
+        let [r, g, b, _] = ColorF::new(channel, channel, channel)
+            .tone_mapped(tone_map)
+            .rgba();
+
+        assert!(expected(r), "{r}");
+        assert_eq!((r, g), (g, b));
+    }
+
+    #[test_case(ToneMap::Reinhard ; "reinhard")]
+    #[test_case(ToneMap::AcesFilmic ; "filmic")]
+    fn test_tone_maps_keep_ordering(tone_map: ToneMap) {
+        let mapped: Vec<f64> = [0.1, 0.5, 1.0, 2.0, 8.0]
+            .iter()
+            .map(|channel| tone_map.apply(*channel))
+            .collect();
+
+        assert!(mapped.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test_case(GREEN, BLUE ; "pure green is brighter than pure blue")]
+    #[test_case(RED, BLUE   ; "pure red is brighter than pure blue")]
+    #[test_case(GREEN, RED  ; "pure green is brighter than pure red")]
+    fn test_luminance_ordering(brighter: Color, darker: Color) {
+        assert!(brighter.luminance() > darker.luminance());
+    }
+
+    #[test_case((0, 0, 0)       ; "black is unchanged")]
+    #[test_case((127, 127, 127) ; "grey is unchanged")]
+    #[test_case((255, 255, 255) ; "white is unchanged")]
+    fn test_grayscale_of_gray_is_unchanged(initial: (u8, u8, u8)) {
+        let color = Color::from(initial);
+
+        assert_eq!(color.grayscale().rgba(), color.rgba());
+    }
+
+    #[test_case((0, 255, 0), 182 ; "green desaturates to its luminance")]
+    fn test_grayscale(initial: (u8, u8, u8), expected: u8) {
+        let gray = Color::from(initial).grayscale();
+
+        assert_eq!(gray.rgba(), [expected, expected, expected, 0xff]);
+    }
+
+    #[test_case("#ff8000", (255, 128, 0)    ; "six digit hex")]
+    #[test_case("#FfA0b1", (255, 160, 177)  ; "mixed case hex")]
+    #[test_case("#f80", (255, 136, 0)       ; "three digit hex repeats digits")]
+    #[test_case("rgb(1, 22, 255)", (1, 22, 255) ; "rgb function with spaces")]
+    fn test_parse(color: &str, expected: (u8, u8, u8)) {
+        assert_eq!(
+            Color::parse(color).unwrap().rgba(),
+            Color::from(expected).rgba()
+        );
+    }
+
+    #[test_case("ff8000"        ; "missing hash")]
+    #[test_case("#ff80zz"       ; "not hex digits")]
+    #[test_case("rgb(1,2)"      ; "too few channels")]
+    #[test_case("rgb(1,2,300)"  ; "channel out of range")]
+    #[test_case("red"           ; "named colors aren't supported")]
+    fn test_parse_errors(color: &str) {
+        assert_eq!(
+            Color::parse(color).unwrap_err(),
+            RayTracerError::ColorParse(color.to_string())
+        );
+    }
+
+    #[test_case(-0.5 ; "negative factor")]
+    fn test_scale_negative(factor: f64) {
+        assert!(matches!(
+            WHITE.scale(factor),
+            Err(RayTracerError::NegativeColorScale(f)) if f == factor
+        ));
+        assert_eq!(WHITE.scale(0.5).unwrap().rgba(), [127, 127, 127, 0xff]);
+    }
+
+    #[test_case((0x12, 0x80, 0xfe) ; "round trip preserves every channel")]
+    fn test_srgb_round_trip(initial: (u8, u8, u8)) {
+        let color = Color::from(initial);
+
+        assert_eq!(color.srgb_to_linear().linear_to_srgb().rgba(), color.rgba());
+    }
+}
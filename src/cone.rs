@@ -0,0 +1,173 @@
+use crate::{
+    aabb::Aabb,
+    body::{Body, Colored, Renderable, Volume},
+    color::Color,
+    material::Material,
+    ray::Ray,
+    roots::Roots,
+    utils::solve_quadratic,
+    vector::Vector3D,
+};
+
+// A finite cone opening from its apex along the axis, with the half angle in radians
+#[derive(Debug)]
+pub struct Cone {
+    body: Body,
+    apex: Vector3D,
+    axis: Vector3D,
+    half_angle: f64,
+    height: f64,
+}
+
+impl Cone {
+    pub fn new(
+        apex: Vector3D,
+        axis: Vector3D,
+        half_angle: f64,
+        height: f64,
+        material: impl Into<Material>,
+    ) -> Self {
+        Cone {
+            body: Body::new(material),
+            apex,
+            axis: axis.unit(),
+            half_angle,
+            height,
+        }
+    }
+
+    // Distance of a point from the apex, measured along the axis
+    fn height_at(&self, point: &Vector3D) -> f64 {
+        Vector3D::from(&self.apex).to(point).dot(&self.axis)
+    }
+}
+
+impl Colored for Cone {
+    fn material(&self) -> &Material {
+        self.body.material()
+    }
+}
+
+impl Volume for Cone {
+    fn intersect(&self, ray: &Ray) -> Roots {
+        let cos_squared = self.half_angle.cos().powi(2);
+        let apex_to_start = Vector3D::from(&self.apex).to(&ray.start);
+
+        let direction_along_axis = ray.direction.dot(&self.axis);
+        let start_along_axis = apex_to_start.dot(&self.axis);
+
+        let a = direction_along_axis * direction_along_axis - cos_squared;
+        let b = 2.
+            * (direction_along_axis * start_along_axis
+                - ray.direction.dot(&apex_to_start) * cos_squared);
+        let c = start_along_axis * start_along_axis - apex_to_start.squid() * cos_squared;
+
+        // The quadratic describes a double cone, so drop the mirrored nappe behind the apex
+        // along with anything past the base
+        solve_quadratic(a, b, c)
+            .into_iter()
+            .filter(|distance| {
+                let point = ray.at(*distance);
+
+                (0. ..=self.height).contains(&self.height_at(&point))
+            })
+            .collect()
+    }
+
+    fn get_normal_at(&self, point: &Vector3D, _time: f64) -> Vector3D {
+        let apex_to_point = Vector3D::from(&self.apex).to(point);
+        let cos_squared = self.half_angle.cos().powi(2);
+
+        (&apex_to_point * cos_squared - &self.axis * apex_to_point.dot(&self.axis)).unit()
+    }
+
+    fn get_color_at(&self, _point: &Vector3D, _time: f64) -> Color {
+        self.color()
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let base_center = &self.apex + &(&self.axis * self.height);
+        let base_radius = self.height * self.half_angle.tan();
+
+        // How far a disk facing along the axis spreads out along each world axis
+        let extent = Vector3D::new(
+            base_radius * (1. - self.axis.x().powi(2)).max(0.).sqrt(),
+            base_radius * (1. - self.axis.y().powi(2)).max(0.).sqrt(),
+            base_radius * (1. - self.axis.z().powi(2)).max(0.).sqrt(),
+        );
+
+        Aabb::new(&(&base_center - &extent), &(&base_center + &extent))
+            .union(&Aabb::new(&self.apex, &self.apex))
+    }
+}
+
+impl Renderable for Cone {
+    fn is_enabled(&self) -> bool {
+        self.body.is_enabled()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.body.set_enabled(enabled);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_4;
+
+    use super::*;
+    use test_case::test_case;
+
+    // Apex at y = 2 opening downwards to a base of radius 2 on the ground
+    fn unit_cone() -> Cone {
+        Cone::new(
+            Vector3D::new(0.0, 2.0, 0.0),
+            Vector3D::new(0.0, -1.0, 0.0),
+            FRAC_PI_4,
+            2.0,
+            Color::new(0, 0, 0),
+        )
+    }
+
+    #[test_case((-5.0, 1.0, 0.0), (1.0, 0.0, 0.0), Some(4.0) ; "ray hits lateral surface")]
+    #[test_case((-5.0, 3.0, 0.0), (1.0, 0.0, 0.0), None      ; "ray through mirror nappe is rejected")]
+    #[test_case((-5.0, -1.0, 0.0), (1.0, 0.0, 0.0), None     ; "ray below the base misses")]
+    #[test_case((-5.0, 1.0, 5.0), (1.0, 0.0, 0.0), None      ; "ray beside the cone misses")]
+    fn test_cone_intersection(
+        start: (f64, f64, f64),
+        direction: (f64, f64, f64),
+        expected_distance: Option<f64>,
+    ) {
+        let cone = unit_cone();
+        let ray = Ray::new(
+            &Vector3D::new(start.0, start.1, start.2),
+            &Vector3D::new(direction.0, direction.1, direction.2),
+        );
+
+        let distance = cone.closest_ray_distance(&ray);
+        match (distance, expected_distance) {
+            (Some(distance), Some(expected)) => assert!((distance - expected).abs() < 1e-9),
+            (distance, expected) => assert_eq!(distance, expected),
+        }
+    }
+
+    #[test_case((-1.0, 1.0, 0.0), (-1.0, 1.0, 0.0) ; "normal points out and up")]
+    fn test_cone_normal(point: (f64, f64, f64), expected: (f64, f64, f64)) {
+        let normal = unit_cone().get_normal_at(&Vector3D::new(point.0, point.1, point.2), 0.);
+        let expected = Vector3D::new(expected.0, expected.1, expected.2).unit();
+
+        assert!((normal.x() - expected.x()).abs() < 1e-9);
+        assert!((normal.y() - expected.y()).abs() < 1e-9);
+        assert!((normal.z() - expected.z()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_cone_bounding_box() {
+        let bounds = unit_cone().bounding_box();
+
+        assert!((bounds.min().x() + 2.0).abs() < 1e-9);
+        assert!((bounds.max().x() - 2.0).abs() < 1e-9);
+        assert!((bounds.min().y()).abs() < 1e-9);
+        assert!((bounds.max().y() - 2.0).abs() < 1e-9);
+    }
+}
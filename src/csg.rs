@@ -0,0 +1,238 @@
+use std::cmp::Ordering;
+
+use crate::{
+    aabb::Aabb,
+    body::{is_on_surface, Colored, Renderable, Volume},
+    color::Color,
+    material::Material,
+    ray::Ray,
+    roots::Roots,
+    vector::Vector3D,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsgOp {
+    Union,
+    Intersection,
+    // The right body is cut out of the left one
+    Difference,
+}
+
+impl CsgOp {
+    fn includes(self, in_left: bool, in_right: bool) -> bool {
+        match self {
+            CsgOp::Union => in_left || in_right,
+            CsgOp::Intersection => in_left && in_right,
+            CsgOp::Difference => in_left && !in_right,
+        }
+    }
+}
+
+// Solid built by combining two bodies, it's shaded with the material of the left one
+pub struct Csg {
+    left: Box<dyn Renderable>,
+    right: Box<dyn Renderable>,
+    op: CsgOp,
+    enabled: bool,
+}
+
+impl Csg {
+    pub fn new(left: Box<dyn Renderable>, right: Box<dyn Renderable>, op: CsgOp) -> Self {
+        Csg {
+            left,
+            right,
+            op,
+            enabled: true,
+        }
+    }
+
+    // The child whose surface the point is on, and whether its normal has to be flipped
+    fn surface_at(&self, point: &Vector3D, time: f64) -> (&dyn Renderable, bool) {
+        if is_on_surface(self.left.as_ref(), point, time) {
+            (self.left.as_ref(), false)
+        } else {
+            // Cut out surfaces are seen from inside the body that was removed
+            (self.right.as_ref(), self.op == CsgOp::Difference)
+        }
+    }
+}
+
+// Walks the boundaries of both interval lists in order, keeping the stretches the op includes
+fn combine(op: CsgOp, left: &[(f64, f64)], right: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let boundaries = |intervals: &[(f64, f64)], is_left: bool| {
+        intervals
+            .iter()
+            .flat_map(move |(entry, exit)| [(*entry, is_left, 1), (*exit, is_left, -1)])
+            .collect::<Vec<_>>()
+    };
+
+    let mut events = boundaries(left, true);
+    events.extend(boundaries(right, false));
+    events.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Greater));
+
+    let (mut left_depth, mut right_depth) = (0, 0);
+    let mut start = None;
+    let mut combined = vec![];
+
+    for (distance, is_left, step) in events {
+        if is_left {
+            left_depth += step;
+        } else {
+            right_depth += step;
+        }
+
+        match (start, op.includes(left_depth > 0, right_depth > 0)) {
+            (None, true) => start = Some(distance),
+            (Some(entry), false) => {
+                // Bodies that only touch leave nothing to see
+                if distance > entry {
+                    combined.push((entry, distance));
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    combined
+}
+
+impl Colored for Csg {
+    fn material(&self) -> &Material {
+        self.left.material()
+    }
+}
+
+impl Volume for Csg {
+    fn intersect(&self, ray: &Ray) -> Roots {
+        self.intervals(ray)
+            .into_iter()
+            .flat_map(|(entry, exit)| [entry, exit])
+            .filter(|distance| distance.is_finite())
+            .collect()
+    }
+
+    fn intervals(&self, ray: &Ray) -> Vec<(f64, f64)> {
+        combine(
+            self.op,
+            &self.left.intervals(ray),
+            &self.right.intervals(ray),
+        )
+    }
+
+    fn get_normal_at(&self, point: &Vector3D, time: f64) -> Vector3D {
+        match self.surface_at(point, time) {
+            (body, true) => body.get_normal_at(point, time).invert(),
+            (body, false) => body.get_normal_at(point, time),
+        }
+    }
+
+    fn get_color_at(&self, point: &Vector3D, time: f64) -> Color {
+        self.surface_at(point, time).0.get_color_at(point, time)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        match self.op {
+            CsgOp::Union => self.left.bounding_box().union(&self.right.bounding_box()),
+            // Nothing outside the left body is kept
+            CsgOp::Intersection | CsgOp::Difference => self.left.bounding_box(),
+        }
+    }
+}
+
+impl Renderable for Csg {
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{body::Sphere, color, plane::Plane};
+    use test_case::test_case;
+
+    fn spheres(op: CsgOp) -> Csg {
+        Csg::new(
+            Box::new(Sphere::new(Vector3D::new(0., 0., 1.), 1., color::RED)),
+            Box::new(Sphere::new(Vector3D::new(0., 0., 0.), 1., color::BLUE)),
+            op,
+        )
+    }
+
+    fn assert_close(actual: &Vector3D, expected: (f64, f64, f64)) {
+        assert!(
+            (actual - &Vector3D::new(expected.0, expected.1, expected.2)).length() < 1e-9,
+            "{actual} != {expected:?}"
+        );
+    }
+
+    #[test_case(CsgOp::Union, 4.        ; "union starts at the right sphere")]
+    #[test_case(CsgOp::Intersection, 5. ; "intersection starts at the left sphere")]
+    #[test_case(CsgOp::Difference, 6.   ; "difference starts behind the bite")]
+    fn test_csg_nearest_hit_along_axis(op: CsgOp, expected: f64) {
+        let ray = Ray::new(&Vector3D::new(0., 0., -5.), &Vector3D::new(0., 0., 1.));
+
+        let distance = spheres(op).closest_ray_distance(&ray).unwrap();
+
+        assert!(
+            (distance - expected).abs() < 1e-9,
+            "{distance} != {expected}"
+        );
+    }
+
+    #[test]
+    fn test_sphere_minus_sphere_is_bitten() {
+        let csg = spheres(CsgOp::Difference);
+
+        // Straight into the bite, the surface is the inside of the removed sphere
+        let into_bite = Ray::new(&Vector3D::new(0., 0., -5.), &Vector3D::new(0., 0., 1.));
+        let point = csg.closest_ray_point(&into_bite).unwrap();
+
+        assert_close(&point, (0., 0., 1.));
+        assert_close(&csg.normal_at(&point, 0.), (0., 0., -1.));
+        assert_eq!(csg.get_color_at(&point, 0.).rgba(), color::BLUE.rgba());
+
+        // Near the rim the bite doesn't reach, so the left sphere is untouched
+        let past_bite = Ray::new(&Vector3D::new(0., 0.9, -5.), &Vector3D::new(0., 0., 1.));
+        let point = csg.closest_ray_point(&past_bite).unwrap();
+        let z = -(1. - 0.9f64 * 0.9).sqrt();
+
+        assert_close(&point, (0., 0.9, 1. + z));
+        assert_close(&csg.normal_at(&point, 0.), (0., 0.9, z));
+        assert_eq!(csg.get_color_at(&point, 0.).rgba(), color::RED.rgba());
+    }
+
+    #[test]
+    fn test_sphere_cut_by_plane() {
+        let hemisphere = Csg::new(
+            Box::new(Sphere::new(Vector3D::new(0., 1., 0.), 1., color::RED)),
+            Box::new(Plane::new(
+                Vector3D::new(0., 1., 0.),
+                Vector3D::new(0., 1., 0.),
+                color::GREEN,
+            )),
+            CsgOp::Intersection,
+        );
+
+        let down = Ray::new(&Vector3D::new(0., 6., 0.), &Vector3D::new(0., -1., 0.));
+        let point = hemisphere.closest_ray_point(&down).unwrap();
+
+        assert_close(&point, (0., 1., 0.));
+        assert_close(&hemisphere.normal_at(&point, 0.), (0., 1., 0.));
+        assert_eq!(hemisphere.intersect(&down), vec![5., 6.]);
+    }
+
+    #[test_case(&[(1., 3.)], &[(2., 4.)], CsgOp::Union, &[(1., 4.)]        ; "union of overlapping")]
+    #[test_case(&[(1., 2.)], &[(3., 4.)], CsgOp::Union, &[(1., 2.), (3., 4.)] ; "union of apart")]
+    #[test_case(&[(1., 3.)], &[(2., 4.)], CsgOp::Intersection, &[(2., 3.)] ; "intersection")]
+    #[test_case(&[(1., 4.)], &[(2., 3.)], CsgOp::Difference, &[(1., 2.), (3., 4.)] ; "difference splits")]
+    #[test_case(&[(1., 2.)], &[(2., 3.)], CsgOp::Intersection, &[]         ; "touching leaves nothing")]
+    fn test_combine(left: &[(f64, f64)], right: &[(f64, f64)], op: CsgOp, expected: &[(f64, f64)]) {
+        assert_eq!(combine(op, left, right), expected);
+    }
+}
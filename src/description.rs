@@ -0,0 +1,222 @@
+use color_eyre::eyre::{eyre, Result};
+
+use crate::{
+    body::{Renderable, Sphere},
+    camera::Camera,
+    color::Color,
+    json::{FromJson, Json, ToJson},
+    scene,
+    vector::Vector3D,
+};
+
+// Plain data describing a scene, so it can be written in a file instead of in code
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraDescription {
+    pub position: Vector3D,
+    pub look_at: Vector3D,
+    pub width: u16,
+    pub height: u16,
+    pub fov: u8,
+}
+
+impl CameraDescription {
+    pub fn to_camera(&self) -> Camera {
+        let mut camera = Camera::new(&self.position, &self.look_at, self.width, self.height);
+        camera.set_fov(self.fov);
+
+        camera
+    }
+}
+
+// Shapes are tagged by a "type" field next to their own fields
+#[derive(Debug)]
+pub enum BodyDescription {
+    Sphere(Sphere),
+}
+
+impl BodyDescription {
+    pub fn into_renderable(self) -> Box<dyn Renderable> {
+        match self {
+            BodyDescription::Sphere(sphere) => Box::new(sphere),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SceneDescription {
+    // Scenes can leave the camera to whoever loads them
+    pub camera: Option<CameraDescription>,
+    pub background: Color,
+    pub ambient: Color,
+    pub bodies: Vec<BodyDescription>,
+}
+
+impl SceneDescription {
+    pub fn parse(text: &str) -> Result<Self> {
+        SceneDescription::from_json(&Json::parse(text)?)
+    }
+
+    pub fn bodies(self) -> Box<[Box<dyn Renderable>]> {
+        self.bodies
+            .into_iter()
+            .map(BodyDescription::into_renderable)
+            .collect()
+    }
+}
+
+impl ToJson for CameraDescription {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("position", self.position.to_json()),
+            ("look_at", self.look_at.to_json()),
+            ("width", self.width.to_json()),
+            ("height", self.height.to_json()),
+            ("fov", self.fov.to_json()),
+        ])
+    }
+}
+
+impl FromJson for CameraDescription {
+    fn from_json(json: &Json) -> Result<Self> {
+        Ok(CameraDescription {
+            position: json.get("position")?,
+            look_at: json.get("look_at")?,
+            width: json.get("width")?,
+            height: json.get("height")?,
+            fov: json.get_or("fov", 60)?,
+        })
+    }
+}
+
+impl ToJson for BodyDescription {
+    fn to_json(&self) -> Json {
+        let (tag, body) = match self {
+            BodyDescription::Sphere(sphere) => ("sphere", sphere.to_json()),
+        };
+
+        let mut fields = vec![("type".to_string(), Json::String(tag.to_string()))];
+        if let Json::Object(body_fields) = body {
+            fields.extend(body_fields);
+        }
+
+        Json::Object(fields)
+    }
+}
+
+impl FromJson for BodyDescription {
+    fn from_json(json: &Json) -> Result<Self> {
+        let tag: String = json.get("type")?;
+
+        match tag.as_str() {
+            "sphere" => Ok(BodyDescription::Sphere(Sphere::from_json(json)?)),
+            other => Err(eyre!("unknown body type `{other}`")),
+        }
+    }
+}
+
+impl ToJson for SceneDescription {
+    fn to_json(&self) -> Json {
+        let json = Json::object([
+            ("background", self.background.to_json()),
+            ("ambient", self.ambient.to_json()),
+            (
+                "bodies",
+                Json::Array(self.bodies.iter().map(ToJson::to_json).collect()),
+            ),
+        ]);
+
+        match (json, &self.camera) {
+            (Json::Object(mut fields), Some(camera)) => {
+                fields.insert(0, ("camera".to_string(), camera.to_json()));
+                Json::Object(fields)
+            }
+            (json, _) => json,
+        }
+    }
+}
+
+impl FromJson for SceneDescription {
+    fn from_json(json: &Json) -> Result<Self> {
+        Ok(SceneDescription {
+            camera: match json.field("camera") {
+                Some(_) => Some(json.get("camera")?),
+                None => None,
+            },
+            background: json.get("background")?,
+            ambient: json.get_or("ambient", scene::DEFAULT_AMBIENT)?,
+            bodies: json.get("bodies")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{body::Colored, color, material::Material};
+    use test_case::test_case;
+
+    fn small_scene() -> SceneDescription {
+        SceneDescription {
+            camera: Some(CameraDescription {
+                position: Vector3D::new(0.0, 1.0, -10.0),
+                look_at: Vector3D::new(0.0, 0.0, 0.0),
+                width: 320,
+                height: 240,
+                fov: 45,
+            }),
+            background: Color::new(10, 20, 30),
+            ambient: color::GREY,
+            bodies: vec![
+                BodyDescription::Sphere(Sphere::new(
+                    Vector3D::new(1.5, -2.0, 0.25),
+                    0.75,
+                    color::RED,
+                )),
+                BodyDescription::Sphere(Sphere::new(
+                    Vector3D::new(0.0, 0.0, 3.0),
+                    2.0,
+                    Material {
+                        reflectivity: 0.5,
+                        ..Material::new(color::CYAN)
+                    },
+                )),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_scene_description_json_round_trip() {
+        let scene = small_scene();
+
+        let text = scene.to_json().to_string();
+        let loaded = SceneDescription::parse(&text).unwrap();
+
+        assert_eq!(loaded.to_json(), scene.to_json());
+        assert_eq!(loaded.camera, scene.camera);
+        assert_eq!(loaded.background.rgba(), [10, 20, 30, 0xff]);
+        assert_eq!(loaded.bodies.len(), 2);
+
+        let BodyDescription::Sphere(sphere) = &loaded.bodies[1];
+        assert_eq!(sphere.material().reflectivity, 0.5);
+        assert_eq!(sphere.color().rgba(), color::CYAN.rgba());
+    }
+
+    #[test_case((1.0, -2.5, 3.25) ; "vector serializes as its components")]
+    fn test_vector_json(components: (f64, f64, f64)) {
+        let vector = Vector3D::new(components.0, components.1, components.2);
+        let json = vector.to_json();
+
+        assert_eq!(json.to_string(), r#"{"x":1,"y":-2.5,"z":3.25}"#);
+        assert_eq!(Vector3D::from_json(&json).unwrap(), vector);
+    }
+
+    #[test_case(r#"{"type": "cube"}"#, "unknown body type" ; "unknown shape tag")]
+    #[test_case(r#"{"radius": 1}"#, "missing field `type`" ; "untagged body")]
+    #[test_case(r#"{"type": "sphere", "center": {"x": 0, "y": 0, "z": 0}, "radius": "big", "material": {"albedo": {"r": 0, "g": 0, "b": 0}}}"#, "`radius`" ; "wrong field type")]
+    fn test_body_description_errors(text: &str, expected: &str) {
+        let error = BodyDescription::from_json(&Json::parse(text).unwrap()).unwrap_err();
+
+        assert!(format!("{error:#}").contains(expected));
+    }
+}
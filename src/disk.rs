@@ -0,0 +1,135 @@
+use crate::{
+    aabb::Aabb,
+    body::{Body, Colored, Renderable, Volume, THRESHOLD},
+    color::Color,
+    material::Material,
+    ray::Ray,
+    roots::Roots,
+    vector::Vector3D,
+};
+
+#[derive(Debug)]
+pub struct Disk {
+    body: Body,
+    center: Vector3D,
+    normal: Vector3D,
+    radius: f64,
+}
+
+impl Disk {
+    pub fn new(
+        center: Vector3D,
+        normal: Vector3D,
+        radius: f64,
+        material: impl Into<Material>,
+    ) -> Self {
+        Disk {
+            body: Body::new(material),
+            center,
+            normal: normal.unit(),
+            radius,
+        }
+    }
+}
+
+impl Colored for Disk {
+    fn material(&self) -> &Material {
+        self.body.material()
+    }
+}
+
+impl Volume for Disk {
+    fn intersect(&self, ray: &Ray) -> Roots {
+        let denominator = ray.direction.dot(&self.normal);
+
+        if denominator.abs() < THRESHOLD {
+            return Roots::new();
+        }
+
+        let distance = Vector3D::from(&ray.start)
+            .to(&self.center)
+            .dot(&self.normal)
+            / denominator;
+        let point = ray.at(distance);
+
+        // Compare squared distances so the rim check doesn't need a square root
+        if Vector3D::from(&self.center).to(&point).squid() > self.radius * self.radius {
+            return Roots::new();
+        }
+
+        Roots::from([distance])
+    }
+
+    fn get_normal_at(&self, _point: &Vector3D, _time: f64) -> Vector3D {
+        self.normal.clone()
+    }
+
+    fn get_color_at(&self, _point: &Vector3D, _time: f64) -> Color {
+        self.color()
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let spread = |component: f64| self.radius * (1. - component * component).max(0.).sqrt();
+        let extent = Vector3D::new(
+            spread(self.normal.x()),
+            spread(self.normal.y()),
+            spread(self.normal.z()),
+        );
+
+        Aabb::new(&(&self.center - &extent), &(&self.center + &extent))
+    }
+}
+
+impl Renderable for Disk {
+    fn is_enabled(&self) -> bool {
+        self.body.is_enabled()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.body.set_enabled(enabled);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::approx_eq;
+    use test_case::test_case;
+
+    #[test_case((0.0, 5.0, 0.0), Some(5.0)  ; "ray hits the center")]
+    #[test_case((0.99, 5.0, 0.0), Some(5.0) ; "ray hits just inside the rim")]
+    #[test_case((1.01, 5.0, 0.0), None      ; "ray misses just outside the rim")]
+    #[test_case((0.7, 5.0, 0.72), None      ; "ray misses diagonally outside the rim")]
+    fn test_disk_intersection(start: (f64, f64, f64), expected_distance: Option<f64>) {
+        let disk = Disk::new(
+            Vector3D::new(0.0, 0.0, 0.0),
+            Vector3D::new(0.0, 1.0, 0.0),
+            1.0,
+            Color::new(0, 0, 0),
+        );
+        let ray = Ray::new(
+            &Vector3D::new(start.0, start.1, start.2),
+            &Vector3D::new(0.0, -1.0, 0.0),
+        );
+
+        let distance = disk.closest_ray_distance(&ray);
+        match (distance, expected_distance) {
+            (Some(distance), Some(expected)) => assert!(approx_eq(distance, expected)),
+            (distance, expected) => assert_eq!(distance, expected),
+        }
+    }
+
+    #[test]
+    fn test_disk_bounding_box_is_flat() {
+        let disk = Disk::new(
+            Vector3D::new(0.0, 2.0, 0.0),
+            Vector3D::new(0.0, 1.0, 0.0),
+            3.0,
+            Color::new(0, 0, 0),
+        );
+        let bounds = disk.bounding_box();
+
+        assert_eq!(bounds.min(), &Vector3D::new(-3.0, 2.0, -3.0));
+        assert_eq!(bounds.max(), &Vector3D::new(3.0, 2.0, 3.0));
+    }
+}
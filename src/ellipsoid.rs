@@ -0,0 +1,151 @@
+use color_eyre::eyre::Result;
+
+use crate::{
+    aabb::Aabb,
+    body::{Colored, Renderable, Sphere, Volume},
+    color::Color,
+    material::Material,
+    matrix::Matrix4,
+    ray::Ray,
+    roots::Roots,
+    transformed::Transformed,
+    vector::Vector3D,
+};
+
+// A sphere stretched by a different radius along each axis. It's a unit sphere scaled and
+// moved into place, so the transform takes care of normals on the stretched surface.
+#[derive(Debug)]
+pub struct Ellipsoid {
+    center: Vector3D,
+    radii: Vector3D,
+    shape: Transformed<Sphere>,
+}
+
+impl Ellipsoid {
+    // Fails when a radius is zero, leaving it flat
+    pub fn new(center: Vector3D, radii: Vector3D, material: impl Into<Material>) -> Result<Self> {
+        let transform = Matrix4::translation(center.x(), center.y(), center.z())
+            * Matrix4::scaling(radii.x(), radii.y(), radii.z());
+        let shape = Transformed::new(
+            Sphere::new(Vector3D::new(0., 0., 0.), 1., material),
+            transform,
+        )?;
+
+        Ok(Ellipsoid {
+            center,
+            radii,
+            shape,
+        })
+    }
+
+    pub fn center(&self) -> &Vector3D {
+        &self.center
+    }
+
+    pub fn radii(&self) -> &Vector3D {
+        &self.radii
+    }
+}
+
+impl Colored for Ellipsoid {
+    fn material(&self) -> &Material {
+        self.shape.material()
+    }
+}
+
+impl Volume for Ellipsoid {
+    fn intersect(&self, ray: &Ray) -> Roots {
+        self.shape.intersect(ray)
+    }
+
+    fn intervals(&self, ray: &Ray) -> Vec<(f64, f64)> {
+        self.shape.intervals(ray)
+    }
+
+    fn get_normal_at(&self, point: &Vector3D, time: f64) -> Vector3D {
+        self.shape.get_normal_at(point, time)
+    }
+
+    fn get_color_at(&self, point: &Vector3D, time: f64) -> Color {
+        self.shape.get_color_at(point, time)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.shape.bounding_box()
+    }
+}
+
+impl Renderable for Ellipsoid {
+    fn is_enabled(&self) -> bool {
+        self.shape.is_enabled()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.shape.set_enabled(enabled);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color, utils::approx_eq_eps};
+    use test_case::test_case;
+
+    fn stretched() -> Ellipsoid {
+        Ellipsoid::new(
+            Vector3D::new(0., 0., 0.),
+            Vector3D::new(2., 1., 1.),
+            color::RED,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_longer_radius_is_hit_farther_out() {
+        let ellipsoid = stretched();
+        let along_x = Ray::new(&Vector3D::new(-5., 0., 0.), &Vector3D::new(1., 0., 0.));
+        let along_y = Ray::new(&Vector3D::new(0., -5., 0.), &Vector3D::new(0., 1., 0.));
+
+        let x_surface = ellipsoid.closest_ray_point(&along_x).unwrap();
+        let y_surface = ellipsoid.closest_ray_point(&along_y).unwrap();
+
+        assert!(approx_eq_eps(x_surface.x(), -2., 1e-9));
+        assert!(approx_eq_eps(y_surface.y(), -1., 1e-9));
+        assert!(x_surface.length() > y_surface.length());
+    }
+
+    #[test_case((2., 0., 0.), (1., 0., 0.) ; "tip of the long axis")]
+    #[test_case((0., 1., 0.), (0., 1., 0.) ; "top")]
+    #[test_case((2f64.sqrt(), 0.5f64.sqrt(), 0.), (0.5, 1., 0.) ; "flank is steeper than a sphere's")]
+    fn test_normal(point: (f64, f64, f64), expected: (f64, f64, f64)) {
+        let normal = stretched().normal_at(&Vector3D::new(point.0, point.1, point.2), 0.);
+        let expected = Vector3D::new(expected.0, expected.1, expected.2).unit();
+
+        assert!(
+            (&normal - &expected).length() < 1e-9,
+            "{normal} != {expected}"
+        );
+    }
+
+    #[test]
+    fn test_bounding_box_and_flat_radii() {
+        let ellipsoid = Ellipsoid::new(
+            Vector3D::new(1., 0., 0.),
+            Vector3D::new(2., 1., 3.),
+            color::RED,
+        )
+        .unwrap();
+
+        assert_eq!(
+            ellipsoid.bounding_box().min(),
+            &Vector3D::new(-1., -1., -3.)
+        );
+        assert_eq!(ellipsoid.bounding_box().max(), &Vector3D::new(3., 1., 3.));
+        assert!(Ellipsoid::new(
+            Vector3D::new(0., 0., 0.),
+            Vector3D::new(1., 0., 1.),
+            color::RED
+        )
+        .is_err());
+    }
+}
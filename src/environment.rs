@@ -0,0 +1,204 @@
+use std::{f64::consts::PI, fs};
+
+use color_eyre::eyre::{eyre, Result};
+
+use crate::{color::ColorF, vector::Vector3D};
+
+// Light arriving from every direction around the scene, stored as an equirectangular image.
+// Columns go once around the horizon and rows from straight up at the top to straight down.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Environment {
+    width: usize,
+    height: usize,
+    // Row-major from the top left, linear light
+    pixels: Vec<ColorF>,
+}
+
+impl Environment {
+    pub fn new(width: usize, height: usize, pixels: Vec<ColorF>) -> Result<Self> {
+        if width == 0 || height == 0 || Some(pixels.len()) != width.checked_mul(height) {
+            return Err(eyre!(
+                "An environment of {width}x{height} needs that many pixels, got {}",
+                pixels.len()
+            ));
+        }
+
+        Ok(Environment {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    // Reads a color Portable Float Map, the simplest format that keeps values above 1
+    pub fn load(path: &str) -> Result<Self> {
+        let contents = fs::read(path)?;
+
+        // Three whitespace separated header lines, the kind, the size and the scale
+        let mut fields = vec![];
+        let mut start = 0;
+        for _ in 0..3 {
+            let end = start
+                + contents[start..]
+                    .iter()
+                    .position(|&byte| byte == b'\n')
+                    .ok_or_else(|| eyre!("{path} ends in the middle of its header"))?;
+            fields.push(String::from_utf8_lossy(&contents[start..end]).into_owned());
+            start = end + 1;
+        }
+
+        if fields[0].trim() != "PF" {
+            return Err(eyre!("{path} isn't a color PFM file"));
+        }
+
+        let size = fields[1]
+            .split_whitespace()
+            .map(|field| field.parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()?;
+        let [width, height] = size[..] else {
+            return Err(eyre!("{path} needs a width and a height"));
+        };
+
+        // A negative scale means the floats are little endian
+        let little_endian = fields[2].trim().parse::<f64>()? < 0.;
+
+        let float_count = width
+            .checked_mul(height)
+            .and_then(|count| count.checked_mul(3))
+            .filter(|count| count.checked_mul(4).is_some())
+            .ok_or_else(|| eyre!("{path} is too large at {width}x{height}"))?;
+
+        let data = &contents[start..];
+        if data.len() < float_count * 4 {
+            return Err(eyre!("{path} is missing pixel data"));
+        }
+
+        let floats: Vec<f64> = data
+            .chunks_exact(4)
+            .take(float_count)
+            .map(|bytes| {
+                let bytes = bytes.try_into().unwrap_or_default();
+                let value = if little_endian {
+                    f32::from_le_bytes(bytes)
+                } else {
+                    f32::from_be_bytes(bytes)
+                };
+                value as f64
+            })
+            .collect();
+
+        // Rows are stored from the bottom up
+        let pixels = floats
+            .chunks_exact(width * 3)
+            .rev()
+            .flat_map(|row| row.chunks_exact(3))
+            .map(|pixel| ColorF::new(pixel[0], pixel[1], pixel[2]))
+            .collect();
+
+        Environment::new(width, height, pixels)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    // Column and row of the pixel seen looking along the direction
+    pub fn pixel_for(&self, direction: &Vector3D) -> (usize, usize) {
+        let direction = direction.unit();
+
+        let u = 0.5 + direction.z().atan2(direction.x()) / (2. * PI);
+        let v = direction.y().clamp(-1., 1.).acos() / PI;
+
+        // u of exactly 1 is the same place as 0, the seam behind the -X axis
+        let column = (u * self.width as f64) as usize % self.width;
+        let row = ((v * self.height as f64) as usize).min(self.height - 1);
+
+        (column, row)
+    }
+
+    pub fn sample(&self, direction: &Vector3D) -> ColorF {
+        let (column, row) = self.pixel_for(direction);
+
+        self.pixels[row * self.width + column]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    // 4x2 map where every pixel has its own color, red counting up from the top left
+    fn write_pfm(name: &str) -> String {
+        let mut contents = b"PF\n4 2\n-1.0\n".to_vec();
+
+        // Bottom row first
+        for index in [4, 5, 6, 7, 0, 1, 2, 3] {
+            for channel in [index as f32, 0.5, 2.0] {
+                contents.extend(channel.to_le_bytes());
+            }
+        }
+
+        let path = std::env::temp_dir().join(name);
+        fs::write(&path, contents).unwrap();
+
+        path.to_str().unwrap().to_owned()
+    }
+
+    #[test]
+    fn test_load_pfm() {
+        let environment = Environment::load(&write_pfm("ray-tracer-load.pfm")).unwrap();
+
+        assert_eq!((environment.width(), environment.height()), (4, 2));
+        assert_eq!(environment.pixels[0], ColorF::new(0.0, 0.5, 2.0));
+        assert_eq!(environment.pixels[6], ColorF::new(6.0, 0.5, 2.0));
+    }
+
+    #[test_case((1.0, 0.0, 0.0)  ; "along x")]
+    #[test_case((0.0, 1.0, 0.0)  ; "up and down")]
+    #[test_case((0.3, 0.2, -0.9) ; "off axis")]
+    fn test_opposite_directions_sample_different_pixels(direction: (f64, f64, f64)) {
+        let name = format!(
+            "ray-tracer-opposite-{}-{}-{}.pfm",
+            direction.0, direction.1, direction.2
+        );
+        let environment = Environment::load(&write_pfm(&name)).unwrap();
+        let direction = Vector3D::new(direction.0, direction.1, direction.2);
+
+        let ahead = environment.pixel_for(&direction);
+        let behind = environment.pixel_for(&direction.invert());
+
+        assert_ne!(ahead, behind);
+        assert_ne!(
+            environment.sample(&direction),
+            environment.sample(&direction.invert())
+        );
+    }
+
+    #[test_case((0.0, 1.0, 0.0), 0  ; "straight up is the top row")]
+    #[test_case((0.0, -1.0, 0.0), 1 ; "straight down is the bottom row")]
+    fn test_rows_go_from_up_to_down(direction: (f64, f64, f64), row: usize) {
+        let environment = Environment::new(1, 2, vec![ColorF::default(); 2]).unwrap();
+        let direction = Vector3D::new(direction.0, direction.1, direction.2);
+
+        assert_eq!(environment.pixel_for(&direction).1, row);
+    }
+
+    #[test]
+    fn test_new_checks_pixel_count() {
+        assert!(Environment::new(2, 2, vec![ColorF::default(); 3]).is_err());
+        assert!(Environment::new(usize::MAX, 2, vec![]).is_err());
+    }
+
+    #[test]
+    fn test_load_rejects_sizes_that_overflow() {
+        let path = std::env::temp_dir().join("ray-tracer-overflow.pfm");
+        fs::write(&path, b"PF\n4294967296 4294967296\n-1.0\n").unwrap();
+
+        assert!(Environment::load(path.to_str().unwrap()).is_err());
+    }
+}
@@ -0,0 +1,39 @@
+use std::fmt;
+
+// Failures callers may want to tell apart. Everything else is reported through color_eyre,
+// which these convert into with `?`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RayTracerError {
+    NegativeColorScale(f64),
+    NonFiniteDistance(f64),
+    // A vector of length 0 has no direction to extend it along
+    ZeroLengthVector,
+    ColorParse(String),
+}
+
+impl fmt::Display for RayTracerError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RayTracerError::NegativeColorScale(factor) => {
+                write!(
+                    formatter,
+                    "Can't scale color values by negative amount {factor}"
+                )
+            }
+            RayTracerError::NonFiniteDistance(distance) => {
+                write!(formatter, "Can't walk a non-finite distance of {distance}")
+            }
+            RayTracerError::ZeroLengthVector => write!(
+                formatter,
+                "A vector of length 0 has no direction and can't be extended for a distance"
+            ),
+            RayTracerError::ColorParse(color) => {
+                write!(formatter, r#"Error parsing color from string: "{color}""#)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RayTracerError {}
+
+pub type Result<T> = std::result::Result<T, RayTracerError>;
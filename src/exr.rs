@@ -0,0 +1,177 @@
+// Minimal OpenEXR encoder for linear RGB images. Channels are stored as uncompressed 32-bit
+// floats one scanline per chunk, so values above 1 survive for later grading.
+
+const MAGIC: [u8; 4] = [0x76, 0x2f, 0x31, 0x01];
+// Version 2, single part scanline file with short names
+const VERSION: u32 = 2;
+
+const PIXEL_TYPE_FLOAT: i32 = 2;
+const NO_COMPRESSION: u8 = 0;
+const INCREASING_Y: u8 = 0;
+
+// Channels have to be listed in alphabetical order, pixel data follows the same order
+const CHANNELS: [&str; 3] = ["B", "G", "R"];
+
+// Encodes row-major linear RGB pixels, three floats per pixel
+pub fn encode(width: u32, height: u32, rgb: &[f32]) -> Vec<u8> {
+    let mut exr = MAGIC.to_vec();
+    exr.extend(VERSION.to_le_bytes());
+
+    let mut channels = vec![];
+    for name in CHANNELS {
+        channels.extend(name.as_bytes());
+        channels.push(0);
+        channels.extend(PIXEL_TYPE_FLOAT.to_le_bytes());
+        // Not perceptually linear, then three reserved bytes
+        channels.extend([0, 0, 0, 0]);
+        // No subsampling along x or y
+        channels.extend(1i32.to_le_bytes());
+        channels.extend(1i32.to_le_bytes());
+    }
+    channels.push(0);
+
+    // Both windows cover the whole image, inclusive of the last pixel
+    let window: Vec<u8> = [0, 0, width as i32 - 1, height as i32 - 1]
+        .iter()
+        .flat_map(|bound| bound.to_le_bytes())
+        .collect();
+
+    write_attribute(&mut exr, "channels", "chlist", &channels);
+    write_attribute(&mut exr, "compression", "compression", &[NO_COMPRESSION]);
+    write_attribute(&mut exr, "dataWindow", "box2i", &window);
+    write_attribute(&mut exr, "displayWindow", "box2i", &window);
+    write_attribute(&mut exr, "lineOrder", "lineOrder", &[INCREASING_Y]);
+    write_attribute(&mut exr, "pixelAspectRatio", "float", &1f32.to_le_bytes());
+    write_attribute(&mut exr, "screenWindowCenter", "v2f", &[0; 8]);
+    write_attribute(&mut exr, "screenWindowWidth", "float", &1f32.to_le_bytes());
+    exr.push(0);
+
+    let row_length = width as usize * CHANNELS.len();
+    let rows: Vec<&[f32]> = rgb
+        .chunks(row_length.max(1))
+        .take(height as usize)
+        .collect();
+
+    // Every chunk is the line number, its size and then each channel's values for the whole line
+    let chunk_size = 8 + row_length * 4;
+    let table_end = exr.len() + rows.len() * 8;
+    for index in 0..rows.len() {
+        exr.extend(((table_end + index * chunk_size) as u64).to_le_bytes());
+    }
+
+    for (y, row) in rows.iter().enumerate() {
+        exr.extend((y as i32).to_le_bytes());
+        exr.extend(((row_length * 4) as i32).to_le_bytes());
+
+        // Stored as B, G, R while the pixels come in as R, G, B
+        for channel in (0..CHANNELS.len()).rev() {
+            for pixel in row.chunks(CHANNELS.len()) {
+                exr.extend(pixel[channel].to_le_bytes());
+            }
+        }
+    }
+
+    exr
+}
+
+fn write_attribute(exr: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    exr.extend(name.as_bytes());
+    exr.push(0);
+    exr.extend(kind.as_bytes());
+    exr.push(0);
+    exr.extend((value.len() as i32).to_le_bytes());
+    exr.extend(value);
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn read_i32(bytes: &[u8], offset: usize) -> i32 {
+        i32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn read_f32(bytes: &[u8], offset: usize) -> f32 {
+        f32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn read_string(bytes: &[u8], offset: &mut usize) -> String {
+        let end = *offset + bytes[*offset..].iter().position(|&byte| byte == 0).unwrap();
+        let string = String::from_utf8(bytes[*offset..end].to_vec()).unwrap();
+        *offset = end + 1;
+
+        string
+    }
+
+    // Reads back what encode writes, returning the dimensions and RGB floats
+    pub fn decode(exr: &[u8]) -> (u32, u32, Vec<f32>) {
+        assert_eq!(&exr[..4], &MAGIC);
+        assert_eq!(read_i32(exr, 4), VERSION as i32);
+
+        let mut offset = 8;
+        let (mut width, mut height) = (0, 0);
+
+        loop {
+            let name = read_string(exr, &mut offset);
+            if name.is_empty() {
+                break;
+            }
+            let kind = read_string(exr, &mut offset);
+            let size = read_i32(exr, offset) as usize;
+            let value = &exr[offset + 4..offset + 4 + size];
+
+            match (name.as_str(), kind.as_str()) {
+                ("dataWindow", "box2i") => {
+                    width = (read_i32(value, 8) + 1) as u32;
+                    height = (read_i32(value, 12) + 1) as u32;
+                }
+                ("compression", _) => assert_eq!(value, [NO_COMPRESSION]),
+                ("channels", _) => {
+                    let mut position = 0;
+                    for expected in CHANNELS {
+                        assert_eq!(read_string(value, &mut position), expected);
+                        assert_eq!(read_i32(value, position), PIXEL_TYPE_FLOAT);
+                        position += 16;
+                    }
+                }
+                _ => {}
+            }
+
+            offset += 4 + size;
+        }
+
+        let mut rgb = vec![0.; (width * height) as usize * CHANNELS.len()];
+
+        for y in 0..height as usize {
+            let chunk =
+                u64::from_le_bytes(exr[offset + y * 8..offset + y * 8 + 8].try_into().unwrap());
+            let chunk = chunk as usize;
+
+            assert_eq!(read_i32(exr, chunk), y as i32);
+            assert_eq!(read_i32(exr, chunk + 4) as u32, width * 12);
+
+            for (stored, channel) in (0..CHANNELS.len()).rev().enumerate() {
+                for x in 0..width as usize {
+                    let value = read_f32(exr, chunk + 8 + (stored * width as usize + x) * 4);
+                    rgb[(y * width as usize + x) * CHANNELS.len() + channel] = value;
+                }
+            }
+        }
+
+        (width, height, rgb)
+    }
+
+    #[test_case(1, 1 ; "single pixel")]
+    #[test_case(3, 2 ; "odd width")]
+    fn test_encode_round_trip(width: u32, height: u32) {
+        let rgb: Vec<f32> = (0..width * height * 3)
+            .map(|value| value as f32 * 0.75)
+            .collect();
+
+        let (decoded_width, decoded_height, decoded) = decode(&encode(width, height, &rgb));
+
+        assert_eq!((decoded_width, decoded_height), (width, height));
+        assert_eq!(decoded, rgb);
+    }
+}
@@ -0,0 +1,405 @@
+use std::cell::RefCell;
+
+use crate::{
+    aabb::Aabb,
+    accelerator::{is_hit_within, Search},
+    body::Renderable,
+    ray::Ray,
+    vector::Vector3D,
+};
+
+// Cells per body on average, a few more cells than bodies keeps each cell's list short
+const CELLS_PER_BODY: f64 = 3.;
+const MAX_CELLS_PER_AXIS: usize = 64;
+
+// Uniform grid over the bodies' bounds, each cell lists the bodies overlapping it by index
+#[derive(Debug, Default)]
+pub struct Grid {
+    bounds: Option<Aabb>,
+    resolution: [usize; 3],
+    cells: Vec<Vec<usize>>,
+    // Bodies without finite bounds don't fit in any cell, so they're always tested
+    unbounded: Vec<usize>,
+}
+
+// Remembers which bodies the current ray was already tested against, so bodies spanning
+// several cells are only intersected once. Each ray gets a new id and a body counts as tested
+// when its slot holds that id, so nothing is cleared or allocated between rays.
+#[derive(Debug, Default)]
+struct Mailbox {
+    ray: u64,
+    tested: Vec<u64>,
+}
+
+impl Mailbox {
+    fn next_ray(&mut self, bodies: usize) {
+        self.ray += 1;
+        if self.tested.len() < bodies {
+            self.tested.resize(bodies, 0);
+        }
+    }
+
+    // True the first time the body comes up for the current ray
+    fn insert(&mut self, index: usize) -> bool {
+        let first = self.tested[index] != self.ray;
+        self.tested[index] = self.ray;
+
+        first
+    }
+}
+
+thread_local! {
+    // Each render thread keeps its own, so they never wait on each other
+    static MAILBOX: RefCell<Mailbox> = RefCell::default();
+}
+
+// Where a ray walking through the grid is, see step
+struct Walk {
+    cell: [usize; 3],
+    step: [isize; 3],
+    // Distance along the ray to the next boundary on each axis and between boundaries
+    next: [f64; 3],
+    delta: [f64; 3],
+}
+
+impl Grid {
+    pub fn new(bodies: &[Box<dyn Renderable>]) -> Self {
+        let (bounded, unbounded): (Vec<_>, Vec<_>) = bodies
+            .iter()
+            .map(|body| body.bounding_box())
+            .enumerate()
+            .partition(|(_, bounds)| bounds.is_finite());
+        let unbounded = unbounded.into_iter().map(|(index, _)| index).collect();
+
+        let Some(bounds) = bounded
+            .iter()
+            .map(|(_, bounds)| bounds.clone())
+            .reduce(|total, bounds| total.union(&bounds))
+        else {
+            return Grid {
+                unbounded,
+                ..Grid::default()
+            };
+        };
+
+        // Flat scenes still need some depth for the cells to have a size
+        let padding = Vector3D::new(1e-6, 1e-6, 1e-6);
+        let bounds = Aabb::new(&(bounds.min() - &padding), &(bounds.max() + &padding));
+
+        // Roughly cube shaped cells, as many as the bodies call for
+        let extent = axes(&(bounds.max() - bounds.min()));
+        let volume: f64 = extent.iter().product();
+        let per_unit = (CELLS_PER_BODY * bounded.len() as f64 / volume).cbrt();
+        let resolution = extent
+            .map(|length| ((length * per_unit).round() as usize).clamp(1, MAX_CELLS_PER_AXIS));
+
+        let mut grid = Grid {
+            bounds: Some(bounds),
+            resolution,
+            cells: vec![vec![]; resolution.iter().product()],
+            unbounded,
+        };
+
+        for (index, body_bounds) in bounded {
+            let low = grid.cell_of(body_bounds.min());
+            let high = grid.cell_of(body_bounds.max());
+
+            for x in low[0]..=high[0] {
+                for y in low[1]..=high[1] {
+                    for z in low[2]..=high[2] {
+                        let cell = grid.cell_index([x, y, z]);
+                        grid.cells[cell].push(index);
+                    }
+                }
+            }
+        }
+
+        grid
+    }
+
+    // Nearest hit along the ray as its distance and the index of the body, the same one a
+    // linear scan would find. Also returns how many bodies were intersected to find it.
+    pub fn hit_counting(
+        &self,
+        bodies: &[Box<dyn Renderable>],
+        ray: &Ray,
+    ) -> (Option<(f64, usize)>, u64) {
+        let mut search = Search::default();
+
+        for index in &self.unbounded {
+            search.test_body(bodies, *index, ray);
+        }
+
+        // Bodies spanning several cells are only tested the first time the ray meets them
+        MAILBOX.with_borrow_mut(|tested| {
+            tested.next_ray(bodies.len());
+
+            self.walk(ray, |indices, exit| {
+                for index in indices {
+                    if tested.insert(*index) {
+                        search.test_body(bodies, *index, ray);
+                    }
+                }
+
+                // A hit beyond this cell could still lose to a body in a later one
+                search.nearest.is_some_and(|(distance, _)| distance <= exit)
+            });
+        });
+
+        (search.nearest, search.tests)
+    }
+
+    // Whether any body is hit closer than max_t, stopping at the first one found. Also returns
+    // how many bodies were intersected on the way.
+    pub fn any_hit(&self, bodies: &[Box<dyn Renderable>], ray: &Ray, max_t: f64) -> (bool, u64) {
+        let mut tests = 0;
+
+        let mut hit = self
+            .unbounded
+            .iter()
+            .any(|index| is_hit_within(bodies, *index, ray, max_t, &mut tests));
+
+        if !hit {
+            MAILBOX.with_borrow_mut(|tested| {
+                tested.next_ray(bodies.len());
+
+                self.walk(ray, |indices, exit| {
+                    hit = indices.iter().any(|index| {
+                        tested.insert(*index)
+                            && is_hit_within(bodies, *index, ray, max_t, &mut tests)
+                    });
+
+                    hit || exit >= max_t
+                });
+            });
+        }
+
+        (hit, tests)
+    }
+
+    // Visits the cells the ray passes through in order, handing each one's bodies and the
+    // distance where the ray leaves it to the closure until it returns true. 3D DDA, see
+    // Amanatides and Woo, "A Fast Voxel Traversal Algorithm for Ray Tracing".
+    fn walk(&self, ray: &Ray, mut visit: impl FnMut(&[usize], f64) -> bool) {
+        let Some(bounds) = &self.bounds else {
+            return;
+        };
+        let Some(entry) = bounds.entry_distance(ray) else {
+            return;
+        };
+
+        let cell = self.cell_of(&ray.at(entry));
+        let (start, direction) = (axes(&ray.start), axes(&ray.direction));
+        let (min, size) = (axes(bounds.min()), self.cell_size());
+
+        let mut walk = Walk {
+            cell,
+            step: [0; 3],
+            next: [f64::INFINITY; 3],
+            delta: [f64::INFINITY; 3],
+        };
+
+        for axis in 0..3 {
+            if direction[axis] == 0. {
+                continue;
+            }
+
+            let boundary_cell = if direction[axis] > 0. {
+                cell[axis] + 1
+            } else {
+                cell[axis]
+            };
+            let boundary = min[axis] + boundary_cell as f64 * size[axis];
+
+            walk.step[axis] = direction[axis].signum() as isize;
+            walk.next[axis] = (boundary - start[axis]) / direction[axis];
+            walk.delta[axis] = size[axis] / direction[axis].abs();
+        }
+
+        loop {
+            let exit = walk.next.iter().copied().fold(f64::INFINITY, f64::min);
+
+            if visit(&self.cells[self.cell_index(walk.cell)], exit) || !self.step(&mut walk) {
+                return;
+            }
+        }
+    }
+
+    // Moves on to the neighbouring cell the ray enters next, false once it leaves the grid
+    fn step(&self, walk: &mut Walk) -> bool {
+        let axis = (0..3)
+            .min_by(|a, b| walk.next[*a].total_cmp(&walk.next[*b]))
+            .unwrap_or(0);
+
+        if walk.step[axis] == 0 {
+            return false;
+        }
+
+        let Some(cell) = walk.cell[axis]
+            .checked_add_signed(walk.step[axis])
+            .filter(|cell| *cell < self.resolution[axis])
+        else {
+            return false;
+        };
+
+        walk.cell[axis] = cell;
+        walk.next[axis] += walk.delta[axis];
+
+        true
+    }
+
+    fn cell_size(&self) -> [f64; 3] {
+        let Some(bounds) = &self.bounds else {
+            return [0.; 3];
+        };
+        let extent = axes(&(bounds.max() - bounds.min()));
+
+        [0, 1, 2].map(|axis| extent[axis] / self.resolution[axis] as f64)
+    }
+
+    // Cell containing the point, points outside the grid go to the nearest cell on its edge
+    fn cell_of(&self, point: &Vector3D) -> [usize; 3] {
+        let Some(bounds) = &self.bounds else {
+            return [0; 3];
+        };
+        let (point, min, size) = (axes(point), axes(bounds.min()), self.cell_size());
+
+        [0, 1, 2].map(|axis| {
+            let cell = ((point[axis] - min[axis]) / size[axis]).floor().max(0.) as usize;
+            cell.min(self.resolution[axis] - 1)
+        })
+    }
+
+    fn cell_index(&self, cell: [usize; 3]) -> usize {
+        (cell[2] * self.resolution[1] + cell[1]) * self.resolution[0] + cell[0]
+    }
+}
+
+fn axes(vector: &Vector3D) -> [f64; 3] {
+    [vector.x(), vector.y(), vector.z()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        accelerator::linear_hit,
+        body::Sphere,
+        color,
+        plane::Plane,
+        rng::{Rng, XorShift},
+        triangle::Triangle,
+    };
+    use test_case::test_case;
+
+    fn sphere_field(count: usize, seed: u64) -> Vec<Box<dyn Renderable>> {
+        let mut rng = XorShift::with_seed(seed);
+        let mut coordinate = || rng.next_f64() * 20. - 10.;
+
+        (0..count)
+            .map(|_| {
+                let center = Vector3D::new(coordinate(), coordinate(), coordinate());
+                Box::new(Sphere::new(center, 0.6, color::RED)) as Box<dyn Renderable>
+            })
+            .collect()
+    }
+
+    fn rays(seed: u64) -> Vec<Ray> {
+        let mut rng = XorShift::with_seed(seed);
+        let mut coordinate = |scale: f64| (rng.next_f64() * 2. - 1.) * scale;
+
+        (0..500)
+            .map(|_| {
+                let start = Vector3D::new(coordinate(15.), coordinate(15.), coordinate(15.));
+                let direction = Vector3D::new(coordinate(1.), coordinate(1.), coordinate(1.));
+                Ray::new(&start, &direction)
+            })
+            .collect()
+    }
+
+    #[test_case(400, 1 ; "dense field")]
+    #[test_case(3, 2   ; "a few spheres")]
+    fn test_grid_matches_linear_scan(count: usize, seed: u64) {
+        let bodies = sphere_field(count, seed);
+        let grid = Grid::new(&bodies);
+
+        for ray in rays(seed) {
+            let expected = linear_hit(&bodies, &ray);
+            let (hit, _) = grid.hit_counting(&bodies, &ray);
+
+            assert_eq!(hit, expected);
+
+            for max_t in [1.0, 10.0, f64::INFINITY] {
+                assert_eq!(
+                    grid.any_hit(&bodies, &ray, max_t).0,
+                    expected.is_some_and(|(distance, _)| distance < max_t)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_grid_tests_fewer_bodies() {
+        let bodies = sphere_field(400, 3);
+        let grid = Grid::new(&bodies);
+
+        let tests: u64 = rays(3)
+            .iter()
+            .map(|ray| grid.hit_counting(&bodies, ray).1)
+            .sum();
+
+        assert!(tests * 4 < 400 * 500, "{tests}");
+    }
+
+    #[test]
+    fn test_grid_tests_each_body_once_per_ray() {
+        // One triangle spanning the grid, the small spheres off to the side only add cells
+        let mut bodies: Vec<Box<dyn Renderable>> = vec![Box::new(Triangle::new(
+            Vector3D::new(-10.0, -10.0, 0.0),
+            Vector3D::new(10.0, -10.0, 0.0),
+            Vector3D::new(-10.0, 10.0, 0.0),
+            color::RED,
+        ))];
+        bodies.extend((0..60).map(|index| {
+            let z = if index % 2 == 0 { 9.0 } else { -9.0 };
+            let center = Vector3D::new(-9.0 + 0.3 * index as f64, -9.0, z);
+            Box::new(Sphere::new(center, 0.1, color::GREEN)) as Box<dyn Renderable>
+        }));
+        let grid = Grid::new(&bodies);
+
+        // Runs alongside the triangle through every cell it's in without touching it
+        let ray = Ray::new(
+            &Vector3D::new(-20.0, 9.0, 0.5),
+            &Vector3D::new(1.0, 0.0, 0.0),
+        );
+
+        assert_eq!(grid.hit_counting(&bodies, &ray), (None, 1));
+        assert_eq!(grid.any_hit(&bodies, &ray, f64::INFINITY), (false, 1));
+    }
+
+    #[test]
+    fn test_grid_always_tests_unbounded_bodies() {
+        let bodies: Vec<Box<dyn Renderable>> = vec![
+            Box::new(Sphere::new(Vector3D::new(0.0, 0.0, 0.0), 1.0, color::RED)),
+            Box::new(Plane::new(
+                Vector3D::new(0.0, -5.0, 0.0),
+                Vector3D::new(0.0, 1.0, 0.0),
+                color::GREEN,
+            )),
+        ];
+        let grid = Grid::new(&bodies);
+
+        let down = Ray::new(
+            &Vector3D::new(10.0, 0.0, 0.0),
+            &Vector3D::new(0.0, -1.0, 0.0),
+        );
+        let ahead = Ray::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 1.0),
+        );
+
+        assert_eq!(grid.hit_counting(&bodies, &down).0, Some((5.0, 1)));
+        assert_eq!(grid.hit_counting(&bodies, &ahead).0, Some((4.0, 0)));
+        assert_eq!(Grid::new(&[]).hit_counting(&[], &ahead), (None, 0));
+    }
+}
@@ -0,0 +1,204 @@
+use std::cmp::Ordering;
+
+use crate::{
+    aabb::Aabb,
+    body::{is_on_surface, Body, Colored, HitRecord, Renderable, Volume},
+    color::Color,
+    material::Material,
+    ray::Ray,
+    roots::Roots,
+    vector::{Vector2D, Vector3D},
+};
+
+// Several bodies handled as one, each hit is shaded by the child it landed on
+pub struct Group {
+    children: Vec<Box<dyn Renderable>>,
+    // Stands in for the material of an empty group
+    body: Body,
+}
+
+impl Group {
+    pub fn new(children: Vec<Box<dyn Renderable>>) -> Self {
+        Group {
+            children,
+            body: Body::new(Material::default()),
+        }
+    }
+
+    pub fn children(&self) -> &[Box<dyn Renderable>] {
+        &self.children
+    }
+
+    pub fn add(&mut self, child: Box<dyn Renderable>) {
+        self.children.push(child);
+    }
+
+    fn nearest_child(&self, ray: &Ray) -> Option<&dyn Renderable> {
+        self.children
+            .iter()
+            .filter_map(|child| {
+                child
+                    .closest_ray_distance(ray)
+                    .map(|distance| (distance, child.as_ref()))
+            })
+            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Greater))
+            .map(|(_, child)| child)
+    }
+
+    fn child_at(&self, point: &Vector3D, time: f64) -> Option<&dyn Renderable> {
+        self.children
+            .iter()
+            .find(|child| is_on_surface(child.as_ref(), point, time))
+            .map(|child| child.as_ref())
+    }
+}
+
+impl Colored for Group {
+    fn material(&self) -> &Material {
+        self.children
+            .first()
+            .map_or(self.body.material(), |child| child.material())
+    }
+
+    fn material_at(&self, point: &Vector3D, time: f64) -> &Material {
+        match self.child_at(point, time) {
+            Some(child) => child.material_at(point, time),
+            None => self.material(),
+        }
+    }
+}
+
+impl Volume for Group {
+    fn intersect(&self, ray: &Ray) -> Roots {
+        self.children
+            .iter()
+            .flat_map(|child| child.intersect(ray))
+            .collect()
+    }
+
+    // Children may overlap, CSG merges the stretches where they do
+    fn intervals(&self, ray: &Ray) -> Vec<(f64, f64)> {
+        self.children
+            .iter()
+            .flat_map(|child| child.intervals(ray))
+            .collect()
+    }
+
+    // Children fill in their own records, the nearest one along the ray if the point drifted off
+    // every surface
+    fn hit_at(&self, ray: &Ray, t: f64) -> HitRecord {
+        let point = ray.at(t);
+
+        match self
+            .child_at(&point, ray.time)
+            .or_else(|| self.nearest_child(ray))
+        {
+            Some(child) => child.hit_at(ray, t),
+            None => HitRecord {
+                t,
+                point,
+                normal: Vector3D::new(0., 0., 0.),
+                front_face: false,
+            },
+        }
+    }
+
+    fn get_normal_at(&self, point: &Vector3D, time: f64) -> Vector3D {
+        self.child_at(point, time)
+            .map_or(Vector3D::new(0., 0., 0.), |child| {
+                child.get_normal_at(point, time)
+            })
+    }
+
+    fn normal_at(&self, point: &Vector3D, time: f64) -> Vector3D {
+        self.child_at(point, time)
+            .map_or(Vector3D::new(0., 0., 0.), |child| {
+                child.normal_at(point, time)
+            })
+    }
+
+    fn uv_frame(&self, point: &Vector3D, time: f64) -> Option<(Vector2D, Vector3D)> {
+        self.child_at(point, time)?.uv_frame(point, time)
+    }
+
+    fn get_color_at(&self, point: &Vector3D, time: f64) -> Color {
+        match self.child_at(point, time) {
+            Some(child) => child.get_color_at(point, time),
+            None => self.color(),
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.children
+            .iter()
+            .map(|child| child.bounding_box())
+            .reduce(|total, bounds| total.union(&bounds))
+            .unwrap_or_else(|| Aabb::new(&Vector3D::new(0., 0., 0.), &Vector3D::new(0., 0., 0.)))
+    }
+}
+
+impl Renderable for Group {
+    fn is_enabled(&self) -> bool {
+        self.body.is_enabled()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.body.set_enabled(enabled);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{body::Sphere, color};
+    use test_case::test_case;
+
+    fn two_spheres() -> Group {
+        Group::new(vec![
+            Box::new(Sphere::new(Vector3D::new(0., 0., 5.), 1., color::RED)),
+            Box::new(Sphere::new(Vector3D::new(0., 0., 0.), 1., color::BLUE)),
+        ])
+    }
+
+    #[test_case((0., 0., -5.), (0., 0., 1.), 4., color::BLUE ; "front sphere is nearer from the front")]
+    #[test_case((0., 0., 10.), (0., 0., -1.), 4., color::RED ; "back sphere is nearer from behind")]
+    fn test_group_nearest_hit(
+        start: (f64, f64, f64),
+        direction: (f64, f64, f64),
+        expected_distance: f64,
+        expected_color: Color,
+    ) {
+        let group = two_spheres();
+        let ray = Ray::new(
+            &Vector3D::new(start.0, start.1, start.2),
+            &Vector3D::new(direction.0, direction.1, direction.2),
+        );
+
+        let hit = group.hit(&ray).unwrap();
+
+        assert!((hit.t - expected_distance).abs() < 1e-9);
+        assert!(hit.front_face);
+        assert_eq!(group.intersect(&ray).len(), 4);
+        assert_eq!(
+            group.get_color_at(&hit.point, 0.).rgba(),
+            expected_color.rgba()
+        );
+    }
+
+    #[test]
+    fn test_group_bounds_cover_children() {
+        let bounds = two_spheres().bounding_box();
+
+        assert_eq!(bounds.min(), &Vector3D::new(-1., -1., -1.));
+        assert_eq!(bounds.max(), &Vector3D::new(1., 1., 6.));
+    }
+
+    #[test]
+    fn test_empty_group_is_never_hit() {
+        let group = Group::new(vec![]);
+        let ray = Ray::new(&Vector3D::new(0., 0., -5.), &Vector3D::new(0., 0., 1.));
+
+        assert!(group.hit(&ray).is_none());
+        assert_eq!(group.color().rgba(), Material::default().albedo.rgba());
+    }
+}
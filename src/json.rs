@@ -0,0 +1,462 @@
+use std::{fmt, iter::Peekable, str::Chars};
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+
+// Arrays and objects nest no deeper than this, the parser recurses for each level and would
+// otherwise overflow the stack on hostile input
+const MAX_DEPTH: usize = 128;
+
+// Just enough JSON to describe scenes in files, objects keep their keys in insertion order
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+pub trait ToJson {
+    fn to_json(&self) -> Json;
+}
+
+pub trait FromJson: Sized {
+    fn from_json(json: &Json) -> Result<Self>;
+}
+
+impl Json {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut parser = Parser {
+            chars: text.chars().peekable(),
+            position: 0,
+            depth: 0,
+        };
+
+        let json = parser.value()?;
+        parser.skip_whitespace();
+
+        match parser.chars.peek().copied() {
+            None => Ok(json),
+            Some(c) => Err(parser.error(&format!("unexpected trailing '{c}'"))),
+        }
+    }
+
+    pub fn object<'a>(fields: impl IntoIterator<Item = (&'a str, Json)>) -> Self {
+        Json::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    pub fn field(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    // Reads a required field, naming it in the error so mistakes in scene files are easy to find
+    pub fn get<T: FromJson>(&self, key: &str) -> Result<T> {
+        let value = self
+            .field(key)
+            .ok_or_else(|| eyre!("missing field `{key}`"))?;
+
+        T::from_json(value).wrap_err_with(|| format!("in field `{key}`"))
+    }
+
+    // Like get, but falls back to a default when the field is left out
+    pub fn get_or<T: FromJson>(&self, key: &str, default: T) -> Result<T> {
+        match self.field(key) {
+            Some(_) => self.get(key),
+            None => Ok(default),
+        }
+    }
+
+    pub fn as_str(&self) -> Result<&str> {
+        match self {
+            Json::String(value) => Ok(value),
+            other => Err(eyre!("expected a string, found {}", other.kind())),
+        }
+    }
+
+    pub fn as_f64(&self) -> Result<f64> {
+        match self {
+            Json::Number(value) => Ok(*value),
+            other => Err(eyre!("expected a number, found {}", other.kind())),
+        }
+    }
+
+    pub fn as_array(&self) -> Result<&[Json]> {
+        match self {
+            Json::Array(values) => Ok(values),
+            other => Err(eyre!("expected an array, found {}", other.kind())),
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Json::Null => "null",
+            Json::Bool(_) => "a boolean",
+            Json::Number(_) => "a number",
+            Json::String(_) => "a string",
+            Json::Array(_) => "an array",
+            Json::Object(_) => "an object",
+        }
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(value) => write!(f, "{value}"),
+            // JSON has no way to spell NaN or infinity
+            Json::Number(value) if !value.is_finite() => write!(f, "null"),
+            Json::Number(value) => write!(f, "{value}"),
+            Json::String(value) => write_string(f, value),
+            Json::Array(values) => {
+                write!(f, "[")?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (index, (key, value)) in fields.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{value}")?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in value.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{c}")?,
+        }
+    }
+    write!(f, "\"")
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    position: usize,
+    // How many arrays and objects the parser is inside
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> color_eyre::Report {
+        eyre!("Invalid JSON at character {}: {message}", self.position)
+    }
+
+    fn next(&mut self) -> Option<char> {
+        self.position += 1;
+        self.chars.next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            self.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(self.error(&format!("expected '{expected}', found '{c}'"))),
+            None => Err(self.error(&format!("expected '{expected}', found the end"))),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Json) -> Result<Json> {
+        for expected in keyword.chars() {
+            self.expect(expected)?;
+        }
+
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Json> {
+        self.skip_whitespace();
+
+        match self.chars.peek().copied() {
+            Some('n') => self.keyword("null", Json::Null),
+            Some('t') => self.keyword("true", Json::Bool(true)),
+            Some('f') => self.keyword("false", Json::Bool(false)),
+            Some('"') => Ok(Json::String(self.string()?)),
+            Some('[') => self.nested(Self::array),
+            Some('{') => self.nested(Self::object),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            Some(c) => Err(self.error(&format!("unexpected '{c}'"))),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Json>) -> Result<Json> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error(&format!("nested deeper than {MAX_DEPTH} levels")));
+        }
+
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+
+        value
+    }
+
+    fn number(&mut self) -> Result<Json> {
+        let mut text = String::new();
+
+        while let Some(c) = self
+            .chars
+            .peek()
+            .filter(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+        {
+            text.push(*c);
+            self.next();
+        }
+
+        text.parse()
+            .map(Json::Number)
+            .map_err(|_| self.error(&format!("invalid number '{text}'")))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        self.expect('"')?;
+        let mut value = String::new();
+
+        loop {
+            match self.next() {
+                Some('"') => return Ok(value),
+                Some('\\') => {
+                    let escaped = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let code: String = (0..4).filter_map(|_| self.next()).collect();
+
+                            u32::from_str_radix(&code, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.error(&format!("invalid escape \\u{code}")))?
+                        }
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    value.push(escaped);
+                }
+                Some(c) => value.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json> {
+        self.expect('[')?;
+        let mut values = vec![];
+
+        self.skip_whitespace();
+        if self.chars.peek() == Some(&']') {
+            self.next();
+            return Ok(Json::Array(values));
+        }
+
+        loop {
+            values.push(self.value()?);
+            self.skip_whitespace();
+
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Json::Array(values)),
+                _ => return Err(self.error("expected ',' or ']' in array")),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Json> {
+        self.expect('{')?;
+        let mut fields = vec![];
+
+        self.skip_whitespace();
+        if self.chars.peek() == Some(&'}') {
+            self.next();
+            return Ok(Json::Object(fields));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            fields.push((key, self.value()?));
+            self.skip_whitespace();
+
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(Json::Object(fields)),
+                _ => return Err(self.error("expected ',' or '}' in object")),
+            }
+        }
+    }
+}
+
+impl ToJson for f64 {
+    fn to_json(&self) -> Json {
+        Json::Number(*self)
+    }
+}
+
+impl FromJson for f64 {
+    fn from_json(json: &Json) -> Result<Self> {
+        json.as_f64()
+    }
+}
+
+impl ToJson for String {
+    fn to_json(&self) -> Json {
+        Json::String(self.clone())
+    }
+}
+
+impl FromJson for String {
+    fn from_json(json: &Json) -> Result<Self> {
+        json.as_str().map(String::from)
+    }
+}
+
+// Whole numbers that have to fit the target type exactly
+macro_rules! integer_json {
+    ($($integer:ty),*) => {
+        $(
+            impl ToJson for $integer {
+                fn to_json(&self) -> Json {
+                    Json::Number(*self as f64)
+                }
+            }
+
+            impl FromJson for $integer {
+                fn from_json(json: &Json) -> Result<Self> {
+                    let value = json.as_f64()?;
+
+                    if value.fract() != 0. || value < <$integer>::MIN as f64 || value > <$integer>::MAX as f64 {
+                        return Err(eyre!("expected a whole number from {} to {}, found {value}", <$integer>::MIN, <$integer>::MAX));
+                    }
+
+                    Ok(value as $integer)
+                }
+            }
+        )*
+    };
+}
+
+integer_json!(u8, u16, u32);
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn to_json(&self) -> Json {
+        Json::Array(self.iter().map(ToJson::to_json).collect())
+    }
+}
+
+impl<T: FromJson> FromJson for Vec<T> {
+    fn from_json(json: &Json) -> Result<Self> {
+        json.as_array()?
+            .iter()
+            .enumerate()
+            .map(|(index, value)| T::from_json(value).wrap_err_with(|| format!("at index {index}")))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("null", Json::Null                         ; "null")]
+    #[test_case(" true ", Json::Bool(true)                 ; "true with whitespace")]
+    #[test_case("-1.5e2", Json::Number(-150.0)             ; "exponent number")]
+    #[test_case(r#""a\"b\nA""#, Json::String("a\"b\nA".to_string()) ; "escaped string")]
+    #[test_case("[1, [], {}]", Json::Array(vec![Json::Number(1.0), Json::Array(vec![]), Json::Object(vec![])]) ; "nested containers")]
+    #[test_case(r#"{"a": 1, "b": [true]}"#, Json::object([("a", Json::Number(1.0)), ("b", Json::Array(vec![Json::Bool(true)]))]) ; "object keeps key order")]
+    fn test_parse(text: &str, expected: Json) {
+        assert_eq!(Json::parse(text).unwrap(), expected);
+    }
+
+    #[test_case(""           ; "empty input")]
+    #[test_case("[1, 2"      ; "unterminated array")]
+    #[test_case(r#"{"a" 1}"# ; "missing colon")]
+    #[test_case("nul"        ; "truncated keyword")]
+    #[test_case("1 2"        ; "trailing value")]
+    #[test_case(r#""abc"#    ; "unterminated string")]
+    fn test_parse_errors(text: &str) {
+        assert!(Json::parse(text).is_err());
+    }
+
+    #[test_case(MAX_DEPTH, true      ; "at the limit")]
+    #[test_case(MAX_DEPTH + 1, false ; "past the limit")]
+    #[test_case(100_000, false       ; "far too deep for the stack")]
+    fn test_parse_depth(depth: usize, parses: bool) {
+        let text = "[".repeat(depth) + &"]".repeat(depth);
+
+        assert_eq!(Json::parse(&text).is_ok(), parses);
+    }
+
+    #[test_case(r#"{"name":"a \"quoted\" word","values":[1,2.5,-3],"flag":false,"none":null}"# ; "mixed document")]
+    fn test_display_round_trip(text: &str) {
+        let json = Json::parse(text).unwrap();
+
+        assert_eq!(json.to_string(), text);
+        assert_eq!(Json::parse(&json.to_string()).unwrap(), json);
+    }
+
+    #[test_case(r#"{"width": 600}"#, Some(600) ; "present field")]
+    #[test_case(r#"{"width": 6.5}"#, None      ; "fractional value")]
+    #[test_case(r#"{"width": -1}"#, None       ; "negative value")]
+    #[test_case(r#"{"height": 1}"#, None       ; "missing field")]
+    fn test_get_integer(text: &str, expected: Option<u16>) {
+        let width = Json::parse(text).unwrap().get::<u16>("width");
+
+        assert_eq!(width.ok(), expected);
+    }
+
+    #[test_case(r#"{"values": [1, "two"]}"# ; "bad array element")]
+    fn test_get_error_names_the_field(text: &str) {
+        let error = Json::parse(text)
+            .unwrap()
+            .get::<Vec<f64>>("values")
+            .unwrap_err();
+        let message = format!("{error:#}");
+
+        assert!(message.contains("`values`"));
+        assert!(message.contains("index 1"));
+    }
+}
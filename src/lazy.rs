@@ -1,16 +1,49 @@
-use std::cell::OnceCell;
+use std::sync::OnceLock;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Lazy<T> {
-    Lazy(OnceCell<T>),
+    Lazy(OnceLock<T>),
     Eager(T),
 }
 
 impl<T: Copy> Lazy<T> {
-    pub fn get_or_init(&self, value: T) -> T {
+    // Only runs init the first time, later calls return what it worked out
+    pub fn get_or_init(&self, init: impl FnOnce() -> T) -> T {
         match &self {
-            Lazy::Lazy(inner) => *inner.get_or_init(|| value),
+            Lazy::Lazy(inner) => *inner.get_or_init(init),
             Lazy::Eager(inner) => *inner,
         }
     }
+
+    pub fn is_initialized(&self) -> bool {
+        match &self {
+            Lazy::Lazy(inner) => inner.get().is_some(),
+            Lazy::Eager(_) => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_init_runs_once() {
+        let calls = Cell::new(0);
+        let lazy = Lazy::Lazy(OnceLock::new());
+        let init = || {
+            calls.set(calls.get() + 1);
+            2.5
+        };
+
+        assert!(!lazy.is_initialized());
+        assert_eq!(lazy.get_or_init(init), 2.5);
+        assert_eq!(lazy.get_or_init(init), 2.5);
+        assert_eq!(calls.get(), 1);
+        assert!(lazy.is_initialized());
+
+        assert_eq!(Lazy::Eager(1.0).get_or_init(init), 1.0);
+        assert_eq!(calls.get(), 1);
+    }
 }
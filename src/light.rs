@@ -0,0 +1,376 @@
+use std::fmt::Debug;
+
+use crate::{
+    color::{Color, ColorF},
+    ray::Ray,
+    rng::{splitmix, Rng, XorShift},
+    scene::Scene,
+    vector::Vector3D,
+};
+
+// Closest a point counts as being to a light when working out falloff, so points right on the
+// light don't get infinite radiance
+const MIN_FALLOFF_DISTANCE: f64 = 1e-3;
+
+// Inverse square falloff over the given squared distance
+fn falloff(distance_squared: f64) -> f64 {
+    1. / distance_squared.max(MIN_FALLOFF_DISTANCE * MIN_FALLOFF_DISTANCE)
+}
+
+pub trait Light: Debug + Send + Sync {
+    // Unit vector pointing from the given point towards the light
+    fn direction_from(&self, point: &Vector3D) -> Vector3D;
+    // Light arriving at a point in linear space, before any surface interaction
+    fn radiance_at(&self, point: &Vector3D) -> ColorF;
+    // Share of the light bodies in the scene block from reaching the point, 0 is fully lit. Time
+    // is the shutter time of the ray being shaded, so moving bodies cast shadows where they are,
+    // and the seed is the ray's for lights that sample randomly.
+    fn occlusion(&self, scene: &Scene, point: &Vector3D, time: f64, seed: u64) -> f64;
+
+    // Share of the radiance left after travelling to the point
+    fn attenuation_at(&self, _point: &Vector3D) -> f64 {
+        1.
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PointLight {
+    position: Vector3D,
+    color: Color,
+    intensity: f64,
+}
+
+impl PointLight {
+    pub fn new(position: Vector3D, color: Color, intensity: f64) -> Self {
+        PointLight {
+            position,
+            color,
+            intensity,
+        }
+    }
+
+    pub fn position(&self) -> &Vector3D {
+        &self.position
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    pub fn intensity(&self) -> f64 {
+        self.intensity
+    }
+}
+
+impl Light for PointLight {
+    fn direction_from(&self, point: &Vector3D) -> Vector3D {
+        Vector3D::from(point).to(&self.position).unit()
+    }
+
+    fn radiance_at(&self, _point: &Vector3D) -> ColorF {
+        self.color.srgb_to_linear().scale(self.intensity)
+    }
+
+    fn occlusion(&self, scene: &Scene, point: &Vector3D, time: f64, _seed: u64) -> f64 {
+        if scene.is_occluded(point, &self.position, time) {
+            1.
+        } else {
+            0.
+        }
+    }
+
+    // Inverse square falloff, the light spreads over a sphere that grows with the distance
+    fn attenuation_at(&self, point: &Vector3D) -> f64 {
+        falloff(self.position.distance_squared_to(point))
+    }
+}
+
+// Parallel light arriving from infinitely far away, like the sun
+#[derive(Debug, Clone)]
+pub struct DirectionalLight {
+    direction: Vector3D,
+    color: Color,
+    intensity: f64,
+}
+
+impl DirectionalLight {
+    // The direction is the way the light travels, not where it comes from
+    pub fn new(direction: Vector3D, color: Color, intensity: f64) -> Self {
+        DirectionalLight {
+            direction: direction.unit(),
+            color,
+            intensity,
+        }
+    }
+
+    pub fn direction(&self) -> &Vector3D {
+        &self.direction
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    pub fn intensity(&self) -> f64 {
+        self.intensity
+    }
+}
+
+impl Light for DirectionalLight {
+    fn direction_from(&self, _point: &Vector3D) -> Vector3D {
+        self.direction.invert()
+    }
+
+    fn radiance_at(&self, _point: &Vector3D) -> ColorF {
+        self.color.srgb_to_linear().scale(self.intensity)
+    }
+
+    fn occlusion(&self, scene: &Scene, point: &Vector3D, time: f64, _seed: u64) -> f64 {
+        let ray = Ray::new(point, &self.direction.invert()).with_time(time);
+
+        if scene.any_hit(&ray, f64::INFINITY) {
+            1.
+        } else {
+            0.
+        }
+    }
+}
+
+// Rectangular light, its corners are the center plus or minus half of each axis. Shadows
+// are sampled on a samples by samples grid over it with every sample jittered inside its cell,
+// which softens their edges into a penumbra
+#[derive(Debug, Clone)]
+pub struct AreaLight {
+    center: Vector3D,
+    u_axis: Vector3D,
+    v_axis: Vector3D,
+    color: Color,
+    intensity: f64,
+    samples: u32,
+}
+
+impl AreaLight {
+    pub fn new(
+        center: Vector3D,
+        u_axis: Vector3D,
+        v_axis: Vector3D,
+        color: Color,
+        intensity: f64,
+        samples: u32,
+    ) -> Self {
+        AreaLight {
+            center,
+            u_axis,
+            v_axis,
+            color,
+            intensity,
+            samples: samples.max(1),
+        }
+    }
+
+    pub fn center(&self) -> &Vector3D {
+        &self.center
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    pub fn intensity(&self) -> f64 {
+        self.intensity
+    }
+
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+}
+
+impl Light for AreaLight {
+    fn direction_from(&self, point: &Vector3D) -> Vector3D {
+        Vector3D::from(point).to(&self.center).unit()
+    }
+
+    fn radiance_at(&self, _point: &Vector3D) -> ColorF {
+        self.color.srgb_to_linear().scale(self.intensity)
+    }
+
+    fn occlusion(&self, scene: &Scene, point: &Vector3D, time: f64, seed: u64) -> f64 {
+        // Seeded by the ray and the point so renders come out the same on any thread and every
+        // run with the same seed
+        let mut rng = XorShift::with_seed(
+            [point.x(), point.y(), point.z()]
+                .iter()
+                .fold(seed, |seed, coordinate| {
+                    splitmix(seed ^ coordinate.to_bits())
+                }),
+        );
+        let cell = 1. / self.samples as f64;
+
+        let blocked = (0..self.samples)
+            .flat_map(|u| (0..self.samples).map(move |v| (u, v)))
+            .filter(|(u, v)| {
+                let s = (*u as f64 + rng.next_f64()) * cell - 0.5;
+                let t = (*v as f64 + rng.next_f64()) * cell - 0.5;
+                let sample = &(&self.center + &self.u_axis.scale(s)) + &self.v_axis.scale(t);
+
+                scene.is_occluded(point, &sample, time)
+            })
+            .count();
+
+        blocked as f64 / (self.samples * self.samples) as f64
+    }
+
+    fn attenuation_at(&self, point: &Vector3D) -> f64 {
+        falloff(self.center.distance_squared_to(point))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{body::Sphere, camera::Camera, color, shading, utils::approx_eq};
+    use test_case::test_case;
+
+    #[test_case((0.0, 10.0, 0.0), (0.0, 0.0, 0.0), (0.0, 1.0, 0.0) ; "light above points up")]
+    #[test_case((3.0, 0.0, 4.0), (0.0, 0.0, 0.0), (0.6, 0.0, 0.8)  ; "direction is normalized")]
+    fn test_point_light_direction_from(
+        position: (f64, f64, f64),
+        point: (f64, f64, f64),
+        expected: (f64, f64, f64),
+    ) {
+        let light = PointLight::new(
+            Vector3D::new(position.0, position.1, position.2),
+            color::WHITE,
+            1.0,
+        );
+        let direction = light.direction_from(&Vector3D::new(point.0, point.1, point.2));
+
+        assert!(approx_eq(direction.x(), expected.0));
+        assert!(approx_eq(direction.y(), expected.1));
+        assert!(approx_eq(direction.z(), expected.2));
+    }
+
+    #[test_case(color::WHITE, 2.0, ColorF::new(2.0, 2.0, 2.0) ; "white light scaled by intensity")]
+    #[test_case(color::RED, 0.5, ColorF::new(0.5, 0.0, 0.0)   ; "colored light tints radiance")]
+    fn test_point_light_radiance(color: Color, intensity: f64, expected: ColorF) {
+        let light = PointLight::new(Vector3D::new(0.0, 0.0, 0.0), color, intensity);
+
+        assert_eq!(light.radiance_at(&Vector3D::new(1.0, 0.0, 0.0)), expected);
+    }
+
+    #[test]
+    fn test_falloff_is_finite_on_the_light() {
+        let center = Vector3D::new(1.0, 2.0, 3.0);
+        let point = PointLight::new(center.clone(), color::WHITE, 1.0);
+        let area = AreaLight::new(
+            center.clone(),
+            Vector3D::new(1.0, 0.0, 0.0),
+            Vector3D::new(0.0, 0.0, 1.0),
+            color::WHITE,
+            1.0,
+            2,
+        );
+
+        for light in [&point as &dyn Light, &area] {
+            assert!(light.attenuation_at(&center).is_finite());
+            assert!(approx_eq(
+                light.attenuation_at(&Vector3D::new(1.0, 4.0, 3.0)),
+                0.25
+            ));
+        }
+    }
+
+    #[test_case((0.0, 1.0, 0.0), 1.0 ; "normal facing the sun is fully lit")]
+    #[test_case((1.0, 0.0, 0.0), 0.0 ; "normal perpendicular to the sun is unlit")]
+    #[test_case((0.0, -1.0, 0.0), 0.0 ; "normal facing away from the sun is unlit")]
+    fn test_directional_light_diffuse(normal: (f64, f64, f64), expected: f64) {
+        let sun = DirectionalLight::new(Vector3D::new(0.0, -1.0, 0.0), color::WHITE, 1.0);
+        let point = Vector3D::new(12.0, -3.0, 40.0);
+
+        let intensity = shading::diffuse(
+            &Vector3D::new(normal.0, normal.1, normal.2),
+            &sun.direction_from(&point),
+        );
+
+        assert!(approx_eq(intensity, expected));
+    }
+
+    #[test_case((0.0, 0.0, 0.0)         ; "radiance near the origin")]
+    #[test_case((1000.0, 0.0, -1000.0) ; "radiance far away is unattenuated")]
+    fn test_directional_light_radiance(point: (f64, f64, f64)) {
+        let sun = DirectionalLight::new(Vector3D::new(1.0, -1.0, 0.0), color::WHITE, 0.75);
+
+        assert_eq!(
+            sun.radiance_at(&Vector3D::new(point.0, point.1, point.2)),
+            ColorF::new(0.75, 0.75, 0.75)
+        );
+    }
+
+    fn occluder_scene(camera: &mut Camera) -> Scene<'_> {
+        Scene::new(
+            camera,
+            color::BLACK,
+            color::BLACK,
+            Box::new([Box::new(Sphere::new(
+                Vector3D::new(0.0, 5.0, 0.0),
+                1.0,
+                color::RED,
+            ))]),
+            Box::new([]),
+        )
+    }
+
+    #[test_case((0.0, 0.0, 0.0), 1.0  ; "umbra right under the occluder")]
+    #[test_case((10.0, 0.0, 0.0), 0.0 ; "fully lit off to the side")]
+    fn test_area_light_occlusion(point: (f64, f64, f64), expected: f64) {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -10.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            800,
+            600,
+        );
+        let scene = occluder_scene(&mut camera);
+        let light = AreaLight::new(
+            Vector3D::new(0.0, 10.0, 0.0),
+            Vector3D::new(2.0, 0.0, 0.0),
+            Vector3D::new(0.0, 0.0, 2.0),
+            color::WHITE,
+            1.0,
+            4,
+        );
+
+        let point = Vector3D::new(point.0, point.1, point.2);
+
+        assert_eq!(light.occlusion(&scene, &point, 0.0, 0), expected);
+    }
+
+    #[test]
+    fn test_area_light_penumbra_is_partial() {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -10.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            800,
+            600,
+        );
+        let scene = occluder_scene(&mut camera);
+        let area = AreaLight::new(
+            Vector3D::new(0.0, 10.0, 0.0),
+            Vector3D::new(2.0, 0.0, 0.0),
+            Vector3D::new(0.0, 0.0, 2.0),
+            color::WHITE,
+            1.0,
+            4,
+        );
+        let point = PointLight::new(Vector3D::new(0.0, 10.0, 0.0), color::WHITE, 1.0);
+
+        // The edge of the shadow, where the occluder covers part of the light
+        let edge = Vector3D::new(1.5, 0.0, 0.0);
+        let occlusion = area.occlusion(&scene, &edge, 0.0, 0);
+
+        assert!(occlusion > 0.0 && occlusion < 1.0, "{occlusion}");
+        assert_eq!(area.occlusion(&scene, &edge, 0.0, 0), occlusion);
+        assert!([0.0, 1.0].contains(&point.occlusion(&scene, &edge, 0.0, 0)));
+        // Other seeds pick other points on the light
+        assert!((1..16).any(|seed| area.occlusion(&scene, &edge, 0.0, seed) != occlusion));
+    }
+}
@@ -1,10 +1,42 @@
+pub mod aabb;
+pub mod accelerator;
 pub mod body;
+pub mod bvh;
 pub mod camera;
+pub mod camera_path;
+pub mod cli;
 pub mod color;
+pub mod cone;
+pub mod csg;
+pub mod description;
+pub mod disk;
+pub mod ellipsoid;
+pub mod environment;
+pub mod error;
+pub mod exr;
+pub mod grid;
+pub mod group;
+pub mod json;
 pub mod lazy;
+pub mod light;
+pub mod material;
+pub mod matrix;
+pub mod mesh;
+pub mod noise;
+pub mod normal_map;
+pub mod optics;
+pub mod plane;
+pub mod png;
 pub mod ray;
 pub mod renderer;
+pub mod rng;
+pub mod roots;
 pub mod scene;
+pub mod shading;
+pub mod texture;
+pub mod torus;
+pub mod transformed;
+pub mod triangle;
 pub mod utils;
 pub mod vector;
 use std::{
@@ -13,16 +45,25 @@ use std::{
 };
 
 use body::Sphere;
-use camera::Camera;
-use color::Color;
-use color_eyre::Result;
-use renderer::{Coordinates2D, Renderer};
+use camera::{Camera, DEFAULT_RESOLUTION};
+use camera_path::CameraPath;
+use cli::Config;
+use color_eyre::{eyre::eyre, Result};
+use light::PointLight;
+use renderer::{DoubleBuffer, Renderer};
 use scene::Scene;
 use vector::Vector3D;
 
-use sdl2::{event::Event, keyboard::Keycode, render::Canvas, video, VideoSubsystem};
+use sdl2::{
+    event::Event,
+    keyboard::Keycode,
+    pixels::PixelFormatEnum,
+    render::{Canvas, Texture},
+    video, VideoSubsystem,
+};
 
 const FULL_CIRCLE: f64 = 2. * PI;
+const ORBIT_KEYFRAMES: u32 = 36;
 
 fn get_xz_plane_rotation_from_time(
     t: Duration,
@@ -46,6 +87,17 @@ fn get_xz_plane_rotation_from_time(
     Vector3D::new(x, initial.y(), z)
 }
 
+// The demo orbit as keyframes close enough together that the camera doesn't visibly leave the
+// circle between them
+fn orbit_path(period: u8, initial: &Vector3D, around: &Vector3D) -> CameraPath {
+    (0..=ORBIT_KEYFRAMES).fold(CameraPath::default(), |path, index| {
+        let time = Duration::from_secs(period.into()) * index / ORBIT_KEYFRAMES;
+        let position = get_xz_plane_rotation_from_time(time, period, initial, around);
+
+        path.with_keyframe(time, position, around.clone())
+    })
+}
+
 fn initialize_window(video: VideoSubsystem, width: u16, height: u16) -> video::Window {
     video
         .window("Roko ray tracing", width.into(), height.into())
@@ -54,49 +106,97 @@ fn initialize_window(video: VideoSubsystem, width: u16, height: u16) -> video::W
         .unwrap()
 }
 
-fn paint_pixel(canvas: &mut Canvas<sdl2::video::Window>, (x, y): Coordinates2D, color: Color) {
-    canvas.set_draw_color(color);
+// Copies the finished front buffer to the window in one go
+fn blit(
+    canvas: &mut Canvas<video::Window>,
+    texture: &mut Texture,
+    buffers: &DoubleBuffer,
+    width: u16,
+) -> Result<()> {
+    texture.update(None, &buffers.front_rgb(), width as usize * 3)?;
     canvas
-        .draw_point((x as i32, y as i32))
-        .unwrap_or_else(|_| panic!("Could not draw color {color:?} to point {x}, {y}."));
+        .copy(texture, None, None)
+        .map_err(|error| eyre!(error))?;
+    canvas.present();
+
+    Ok(())
+}
+
+fn demo_scene(camera: &mut Camera) -> Scene<'_> {
+    Scene::new(
+        camera,
+        color::BLACK,
+        scene::DEFAULT_AMBIENT,
+        Box::new([
+            Box::new(Sphere::new(vector::O, 2., color::WHITE)),
+            Box::new(Sphere::new(Vector3D::new(10., 0., 0.), 2., color::RED)),
+            Box::new(Sphere::new(Vector3D::new(0., 10., 0.), 2., color::GREEN)),
+            Box::new(Sphere::new(Vector3D::new(0., 0., 10.), 2., color::BLUE)),
+        ]),
+        Box::new([demo_light()]),
+    )
+}
+
+fn demo_light() -> Box<PointLight> {
+    Box::new(PointLight::new(
+        Vector3D::new(-20., 30., -10.),
+        color::WHITE,
+        1500.,
+    ))
 }
 
 fn main() -> Result<()> {
     color_eyre::install()?;
 
+    let config = Config::parse(std::env::args().skip(1))?;
+
+    let initial_camera_position = Vector3D::new(-10., 10., -10.);
+    let target = vector::O;
+
+    let (width, height) = config.resolution_over(DEFAULT_RESOLUTION);
+    let mut camera = Camera::new(&initial_camera_position, &target, width, height);
+
+    // Scene files don't describe lights yet, so they're lit like the demo
+    let mut scene = match &config.scene {
+        Some(path) => {
+            let mut scene = Scene::from_file(path, &mut camera)?;
+            scene.add_light(demo_light());
+            scene
+        }
+        None => demo_scene(&mut camera),
+    };
+
+    // A camera in the scene file brings its own resolution, unless the command line gives one
+    let (pixel_width, pixel_height) = config.resolution_over(scene.camera().resolution());
+    scene.set_resolution(pixel_width, pixel_height);
+    let renderer = Renderer::new(pixel_width, pixel_height);
+
+    if let Some(output) = &config.output {
+        return renderer.write_png(&scene, output);
+    }
+
     let sdl_context = sdl2::init().unwrap();
     let video_subsystem = sdl_context.video().unwrap();
 
-    let pixel_width = 600;
-    let pixel_height = 600;
-
     let window = initialize_window(video_subsystem, pixel_width, pixel_height);
 
     let mut canvas = window.into_canvas().build().unwrap();
     let mut event_pump = sdl_context.event_pump().unwrap();
 
-    let initial_camera_position = Vector3D::new(-10., 10., -10.);
-    let target = vector::O;
-
-    let mut camera = Camera::new(&initial_camera_position, &target, pixel_height, pixel_width);
-
-    let mut scene = Scene::new(
-        &mut camera,
-        color::BLACK,
-        Box::new([
-            Box::new(Sphere::new(vector::O, 2., color::WHITE)),
-            Box::new(Sphere::new(Vector3D::new(10., 0., 0.), 2., color::RED)),
-            Box::new(Sphere::new(Vector3D::new(0., 10., 0.), 2., color::GREEN)),
-            Box::new(Sphere::new(Vector3D::new(0., 0., 10.), 2., color::BLUE)),
-        ]),
-    );
-
-    let renderer = Renderer::new(pixel_width, pixel_height);
+    // Frames are traced off screen and only shown once they're complete
+    let texture_creator = canvas.texture_creator();
+    let mut texture = texture_creator.create_texture_streaming(
+        PixelFormatEnum::RGB24,
+        pixel_width.into(),
+        pixel_height.into(),
+    )?;
+    let mut buffers = DoubleBuffer::new(pixel_width, pixel_height);
 
+    let orbit = orbit_path(10, &initial_camera_position, &target);
     let start = Instant::now();
 
     'running: loop {
-        renderer.render(&mut canvas, &scene, &paint_pixel);
+        buffers.render(&renderer, &scene)?;
         for event in event_pump.poll_iter() {
             match event {
                 Event::Quit { .. }
@@ -108,12 +208,12 @@ fn main() -> Result<()> {
             }
         }
 
-        canvas.present();
-        let end = start.elapsed();
+        blit(&mut canvas, &mut texture, &buffers, pixel_width)?;
 
-        let new_pos = get_xz_plane_rotation_from_time(end, 10, &initial_camera_position, &target);
-
-        scene.move_camera(new_pos);
+        let elapsed = start.elapsed().as_secs_f64() % orbit.duration().as_secs_f64();
+        if let Some((position, target)) = orbit.sample(Duration::from_secs_f64(elapsed)) {
+            scene.aim_camera(position, target);
+        }
     }
 
     Ok(())
@@ -0,0 +1,123 @@
+use color_eyre::eyre::Result;
+
+use crate::{
+    color::{self, Color},
+    json::{FromJson, Json, ToJson},
+    normal_map::NormalMap,
+};
+
+// How the highlight from a light is worked out, the two give differently shaped highlights
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SpecularModel {
+    // By how close the view is to the light's reflection
+    Phong,
+    // By how close the normal is to halfway between the light and the view, wider for the same
+    // shininess and doesn't cut off at grazing angles
+    #[default]
+    BlinnPhong,
+}
+
+// Surface properties of a body, independent of its geometry
+#[derive(Debug, Clone, Copy)]
+pub struct Material {
+    pub albedo: Color,
+    // Fraction of light mirrored off the surface, 0 to 1
+    pub reflectivity: f64,
+    // Fraction of light passing through the surface, 0 to 1
+    pub transparency: f64,
+    pub refractive_index: f64,
+    // Specular exponent, higher values give tighter highlights
+    pub shininess: f64,
+    pub specular_model: SpecularModel,
+    // Light given off by the surface itself, added whether or not anything lights it
+    pub emission: Color,
+    // Bends the shading normal on bodies with a UV mapping, others ignore it
+    pub normal_map: Option<NormalMap>,
+}
+
+impl Material {
+    pub fn new(albedo: Color) -> Self {
+        Material {
+            albedo,
+            reflectivity: 0.,
+            transparency: 0.,
+            refractive_index: 1.,
+            shininess: 32.,
+            specular_model: SpecularModel::default(),
+            emission: color::BLACK,
+            normal_map: None,
+        }
+    }
+
+    pub fn is_emissive(&self) -> bool {
+        self.emission.rgba() != color::BLACK.rgba()
+    }
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Material::new(color::WHITE)
+    }
+}
+
+impl From<Color> for Material {
+    fn from(albedo: Color) -> Self {
+        Material::new(albedo)
+    }
+}
+
+impl ToJson for Material {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("albedo", self.albedo.to_json()),
+            ("reflectivity", self.reflectivity.to_json()),
+            ("transparency", self.transparency.to_json()),
+            ("refractive_index", self.refractive_index.to_json()),
+            ("shininess", self.shininess.to_json()),
+            ("emission", self.emission.to_json()),
+        ])
+    }
+}
+
+// Everything but the albedo is optional, matching Material::new
+impl FromJson for Material {
+    fn from_json(json: &Json) -> Result<Self> {
+        let defaults = Material::new(json.get("albedo")?);
+
+        Ok(Material {
+            reflectivity: json.get_or("reflectivity", defaults.reflectivity)?,
+            transparency: json.get_or("transparency", defaults.transparency)?,
+            refractive_index: json.get_or("refractive_index", defaults.refractive_index)?,
+            shininess: json.get_or("shininess", defaults.shininess)?,
+            emission: json.get_or("emission", defaults.emission)?,
+            ..defaults
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case((10, 20, 30) ; "plain color becomes a matte opaque material")]
+    fn test_material_from_color(albedo: (u8, u8, u8)) {
+        let material = Material::from(Color::from(albedo));
+
+        assert_eq!(material.albedo.rgba(), Color::from(albedo).rgba());
+        assert_eq!(material.reflectivity, 0.);
+        assert_eq!(material.transparency, 0.);
+        assert_eq!(material.refractive_index, 1.);
+    }
+
+    #[test_case(r#"{"albedo": {"r": 1, "g": 2, "b": 3}}"#, 0.0, 32.0 ; "only albedo uses the defaults")]
+    #[test_case(r#"{"albedo": {"r": 1, "g": 2, "b": 3}, "reflectivity": 0.5, "shininess": 8}"#, 0.5, 8.0 ; "given fields override the defaults")]
+    fn test_material_from_json(text: &str, reflectivity: f64, shininess: f64) {
+        let material = Material::from_json(&Json::parse(text).unwrap()).unwrap();
+
+        assert_eq!(material.albedo.rgba(), [1, 2, 3, 0xff]);
+        assert_eq!(material.reflectivity, reflectivity);
+        assert_eq!(material.shininess, shininess);
+        assert_eq!(material.transparency, 0.);
+    }
+}
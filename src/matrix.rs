@@ -0,0 +1,246 @@
+use std::ops::Mul;
+
+use crate::vector::Vector3D;
+
+// Pivots smaller than this mean the matrix squashes space flat and can't be undone
+const SINGULAR: f64 = 1e-12;
+
+// Row-major 4x4 matrix for affine transforms of homogeneous coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix4 {
+    rows: [[f64; 4]; 4],
+}
+
+impl Matrix4 {
+    pub fn new(rows: [[f64; 4]; 4]) -> Self {
+        Matrix4 { rows }
+    }
+
+    pub fn identity() -> Self {
+        Matrix4::scaling(1., 1., 1.)
+    }
+
+    pub fn translation(x: f64, y: f64, z: f64) -> Self {
+        Matrix4::new([
+            [1., 0., 0., x],
+            [0., 1., 0., y],
+            [0., 0., 1., z],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    pub fn scaling(x: f64, y: f64, z: f64) -> Self {
+        Matrix4::new([
+            [x, 0., 0., 0.],
+            [0., y, 0., 0.],
+            [0., 0., z, 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    // Rotations are counterclockwise looking down the axis towards the origin, angles in radians
+    pub fn rotation_x(angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+
+        Matrix4::new([
+            [1., 0., 0., 0.],
+            [0., cos, -sin, 0.],
+            [0., sin, cos, 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    pub fn rotation_y(angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+
+        Matrix4::new([
+            [cos, 0., sin, 0.],
+            [0., 1., 0., 0.],
+            [-sin, 0., cos, 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    pub fn rotation_z(angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+
+        Matrix4::new([
+            [cos, -sin, 0., 0.],
+            [sin, cos, 0., 0.],
+            [0., 0., 1., 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> f64 {
+        self.rows[row][column]
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut rows = [[0.; 4]; 4];
+
+        for (row, values) in rows.iter_mut().enumerate() {
+            for (column, value) in values.iter_mut().enumerate() {
+                *value = self.rows[column][row];
+            }
+        }
+
+        Matrix4::new(rows)
+    }
+
+    // Points are at w = 1, so they're moved by translations
+    pub fn transform_point(&self, point: &Vector3D) -> Vector3D {
+        self.transform([point.x(), point.y(), point.z(), 1.])
+    }
+
+    // Directions are at w = 0, so translations leave them alone
+    pub fn transform_vector(&self, vector: &Vector3D) -> Vector3D {
+        self.transform([vector.x(), vector.y(), vector.z(), 0.])
+    }
+
+    fn transform(&self, coordinates: [f64; 4]) -> Vector3D {
+        let row = |index: usize| {
+            self.rows[index]
+                .iter()
+                .zip(coordinates)
+                .map(|(a, b)| a * b)
+                .sum::<f64>()
+        };
+
+        Vector3D::new(row(0), row(1), row(2))
+    }
+
+    // Gauss-Jordan elimination with partial pivoting, None when the matrix is singular
+    pub fn inverse(&self) -> Option<Self> {
+        let mut rows = self.rows;
+        let mut inverse = Matrix4::identity().rows;
+
+        for column in 0..4 {
+            let pivot = (column..4)
+                .max_by(|a, b| rows[*a][column].abs().total_cmp(&rows[*b][column].abs()))?;
+
+            if rows[pivot][column].abs() < SINGULAR {
+                return None;
+            }
+
+            rows.swap(column, pivot);
+            inverse.swap(column, pivot);
+
+            let scale = rows[column][column];
+            for index in 0..4 {
+                rows[column][index] /= scale;
+                inverse[column][index] /= scale;
+            }
+
+            for row in (0..4).filter(|row| *row != column) {
+                let factor = rows[row][column];
+
+                for index in 0..4 {
+                    rows[row][index] -= factor * rows[column][index];
+                    inverse[row][index] -= factor * inverse[column][index];
+                }
+            }
+        }
+
+        Some(Matrix4::new(inverse))
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Matrix4;
+
+    // Applying the product is applying the right matrix first, then the left one
+    fn mul(self, rhs: Matrix4) -> Self::Output {
+        let mut rows = [[0.; 4]; 4];
+
+        for (row, values) in rows.iter_mut().enumerate() {
+            for (column, value) in values.iter_mut().enumerate() {
+                *value = (0..4)
+                    .map(|index| self.rows[row][index] * rhs.rows[index][column])
+                    .sum();
+            }
+        }
+
+        Matrix4::new(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    use super::*;
+    use crate::utils::approx_eq;
+    use test_case::test_case;
+
+    fn assert_vector_approx_eq(actual: &Vector3D, expected: (f64, f64, f64)) {
+        assert!(
+            approx_eq(actual.x(), expected.0)
+                && approx_eq(actual.y(), expected.1)
+                && approx_eq(actual.z(), expected.2),
+            "{actual} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn test_translation_moves_point() {
+        let point = Matrix4::translation(5., -3., 2.).transform_point(&Vector3D::new(-3., 4., 5.));
+
+        assert_eq!(point, Vector3D::new(2., 1., 7.));
+    }
+
+    #[test_case(Matrix4::rotation_z(FRAC_PI_2), (1., 0., 0.), (0., 1., 0.) ; "z quarter turn maps X to Y")]
+    #[test_case(Matrix4::rotation_x(FRAC_PI_2), (0., 1., 0.), (0., 0., 1.) ; "x quarter turn maps Y to Z")]
+    #[test_case(Matrix4::rotation_y(FRAC_PI_2), (0., 0., 1.), (1., 0., 0.) ; "y quarter turn maps Z to X")]
+    #[test_case(Matrix4::rotation_y(PI), (1., 0., 0.), (-1., 0., 0.)       ; "half turn flips")]
+    fn test_rotation(matrix: Matrix4, vector: (f64, f64, f64), expected: (f64, f64, f64)) {
+        let rotated = matrix.transform_vector(&Vector3D::new(vector.0, vector.1, vector.2));
+
+        assert_vector_approx_eq(&rotated, expected);
+    }
+
+    #[test_case(Matrix4::translation(5., -3., 2.)                            ; "translation")]
+    #[test_case(Matrix4::scaling(2., 4., 0.5)                                ; "scaling")]
+    #[test_case(Matrix4::rotation_x(0.3) * Matrix4::rotation_z(1.1)          ; "rotations")]
+    #[test_case(Matrix4::translation(1., 2., 3.) * Matrix4::rotation_y(0.7) * Matrix4::scaling(2., 2., 2.) ; "composed")]
+    fn test_inverse_composed_with_original_is_identity(matrix: Matrix4) {
+        let inverse = matrix.inverse().unwrap();
+
+        for product in [inverse * matrix, matrix * inverse] {
+            for row in 0..4 {
+                for column in 0..4 {
+                    let expected = if row == column { 1. } else { 0. };
+
+                    assert!(
+                        approx_eq(product.get(row, column), expected),
+                        "{product:?} isn't the identity"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test_case(Matrix4::translation(1., 2., 3.), (2., 3., 4.), (1., 1., 1.) ; "translation only moves points")]
+    #[test_case(Matrix4::scaling(2., 3., 4.), (2., 3., 4.), (2., 3., 4.)     ; "scaling stretches both")]
+    fn test_points_and_vectors(
+        matrix: Matrix4,
+        expected_point: (f64, f64, f64),
+        expected_vector: (f64, f64, f64),
+    ) {
+        let ones = Vector3D::new(1., 1., 1.);
+
+        assert_eq!(
+            matrix.transform_point(&ones),
+            Vector3D::new(expected_point.0, expected_point.1, expected_point.2)
+        );
+        assert_eq!(
+            matrix.transform_vector(&ones),
+            Vector3D::new(expected_vector.0, expected_vector.1, expected_vector.2)
+        );
+    }
+
+    #[test]
+    fn test_singular_matrix_has_no_inverse() {
+        assert_eq!(Matrix4::scaling(1., 0., 1.).inverse(), None);
+    }
+}
@@ -0,0 +1,242 @@
+use std::{cmp::Ordering, fs};
+
+use color_eyre::eyre::{eyre, Result};
+
+use crate::{
+    aabb::Aabb,
+    body::{Body, Colored, Renderable, Volume},
+    color::Color,
+    material::Material,
+    ray::Ray,
+    roots::Roots,
+    triangle::Triangle,
+    vector::Vector3D,
+};
+
+#[derive(Debug)]
+pub struct TriangleMesh {
+    body: Body,
+    triangles: Vec<Triangle>,
+}
+
+// OBJ indices are 1-based, negative indices count back from the last vertex
+fn parse_face_index(token: &str, vertex_count: usize) -> Result<usize> {
+    let index: i64 = token
+        .split('/')
+        .next()
+        .ok_or_else(|| eyre!(r#"Missing vertex index in face element "{token}""#))?
+        .parse()?;
+
+    let resolved = match index.cmp(&0) {
+        Ordering::Greater => index - 1,
+        Ordering::Less => vertex_count as i64 + index,
+        Ordering::Equal => return Err(eyre!("OBJ vertex indices start at 1")),
+    };
+
+    if resolved < 0 || resolved as usize >= vertex_count {
+        return Err(eyre!(
+            "Face references vertex {index} but only {vertex_count} vertices are defined"
+        ));
+    }
+
+    Ok(resolved as usize)
+}
+
+impl TriangleMesh {
+    pub fn new(triangles: Vec<Triangle>, material: impl Into<Material>) -> Self {
+        TriangleMesh {
+            body: Body::new(material),
+            triangles,
+        }
+    }
+
+    pub fn from_obj(path: &str, material: impl Into<Material>) -> Result<Self> {
+        let material = material.into();
+        let contents = fs::read_to_string(path)?;
+
+        let mut vertices: Vec<Vector3D> = vec![];
+        let mut triangles: Vec<Triangle> = vec![];
+
+        for (line_number, line) in contents.lines().enumerate() {
+            let mut tokens = line.split_whitespace();
+
+            match tokens.next() {
+                Some("v") => {
+                    let coordinates = tokens
+                        .take(3)
+                        .map(|token| token.parse::<f64>())
+                        .collect::<Result<Vec<f64>, _>>()?;
+
+                    if coordinates.len() != 3 {
+                        return Err(eyre!(
+                            "Vertex on line {} needs three coordinates",
+                            line_number + 1
+                        ));
+                    }
+
+                    vertices.push(Vector3D::new(
+                        coordinates[0],
+                        coordinates[1],
+                        coordinates[2],
+                    ));
+                }
+                Some("f") => {
+                    let indices = tokens
+                        .map(|token| parse_face_index(token, vertices.len()))
+                        .collect::<Result<Vec<usize>>>()?;
+
+                    if indices.len() < 3 {
+                        return Err(eyre!(
+                            "Face on line {} needs at least three vertices",
+                            line_number + 1
+                        ));
+                    }
+
+                    // Fan triangulation, fine for the convex polygons OBJ exporters produce
+                    for window in 1..indices.len() - 1 {
+                        triangles.push(Triangle::new(
+                            vertices[indices[0]].clone(),
+                            vertices[indices[window]].clone(),
+                            vertices[indices[window + 1]].clone(),
+                            material,
+                        ));
+                    }
+                }
+                // Normals, texture coordinates, groups and comments aren't supported yet
+                _ => {}
+            }
+        }
+
+        Ok(TriangleMesh::new(triangles, material))
+    }
+
+    pub fn triangles(&self) -> &[Triangle] {
+        &self.triangles
+    }
+}
+
+impl Colored for TriangleMesh {
+    fn material(&self) -> &Material {
+        self.body.material()
+    }
+}
+
+impl Volume for TriangleMesh {
+    fn intersect(&self, ray: &Ray) -> Roots {
+        self.triangles
+            .iter()
+            .filter_map(|triangle| triangle.closest_ray_distance(ray))
+            .min_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Greater))
+            .into_iter()
+            .collect()
+    }
+
+    // Normal of the triangle the point lies on. Hit points drift a little off the surface, so
+    // this is whichever triangle is nearest.
+    fn get_normal_at(&self, point: &Vector3D, time: f64) -> Vector3D {
+        self.triangles
+            .iter()
+            .map(|triangle| {
+                (
+                    triangle.closest_point(point).distance_squared_to(point),
+                    triangle,
+                )
+            })
+            .min_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+            .map(|(_, triangle)| triangle.get_normal_at(point, time))
+            .unwrap_or_else(|| Vector3D::new(0., 0., 0.))
+    }
+
+    fn get_color_at(&self, _point: &Vector3D, _time: f64) -> Color {
+        self.color()
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::from_points(
+            self.triangles
+                .iter()
+                .flat_map(|triangle| triangle.vertices()),
+        )
+        .unwrap_or_else(|| Aabb::new(&Vector3D::new(0., 0., 0.), &Vector3D::new(0., 0., 0.)))
+    }
+}
+
+impl Renderable for TriangleMesh {
+    fn is_enabled(&self) -> bool {
+        self.body.is_enabled()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.body.set_enabled(enabled);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::approx_eq;
+    use test_case::test_case;
+
+    const CUBE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/cube.obj");
+
+    #[test]
+    fn test_mesh_from_obj_triangulates_quads() {
+        let mesh = TriangleMesh::from_obj(CUBE, Color::new(1, 2, 3)).unwrap();
+
+        assert_eq!(mesh.triangles().len(), 12);
+        assert_eq!(mesh.color().rgba(), Color::new(1, 2, 3).rgba());
+    }
+
+    #[test_case((0.0, 0.0, -5.0), (0.0, 0.0, 1.0), Some(4.0) ; "ray down the center hits the front face")]
+    #[test_case((0.0, 5.0, 0.0), (0.0, -1.0, 0.0), Some(4.0) ; "ray from above hits the top face")]
+    #[test_case((0.0, 0.0, 0.0), (1.0, 0.0, 0.0), Some(1.0)  ; "ray from inside hits the far wall")]
+    #[test_case((3.0, 3.0, -5.0), (0.0, 0.0, 1.0), None      ; "ray beside the cube misses")]
+    fn test_mesh_intersection(
+        start: (f64, f64, f64),
+        direction: (f64, f64, f64),
+        expected_distance: Option<f64>,
+    ) {
+        let mesh = TriangleMesh::from_obj(CUBE, Color::new(0, 0, 0)).unwrap();
+        let ray = Ray::new(
+            &Vector3D::new(start.0, start.1, start.2),
+            &Vector3D::new(direction.0, direction.1, direction.2),
+        );
+
+        let distance = mesh.closest_ray_distance(&ray);
+        match (distance, expected_distance) {
+            (Some(distance), Some(expected)) => assert!(approx_eq(distance, expected)),
+            (distance, expected) => assert_eq!(distance, expected),
+        }
+    }
+
+    #[test_case((0.5, 0.2, -1.001), (0.0, 0.0, -1.0) ; "just in front of the front face")]
+    #[test_case((0.3, 1.01, 0.4), (0.0, 1.0, 0.0)    ; "just above the top face")]
+    #[test_case((2.0, 0.1, 0.0), (1.0, 0.0, 0.0)     ; "well off the side")]
+    fn test_mesh_normal_off_the_surface(point: (f64, f64, f64), expected: (f64, f64, f64)) {
+        let mesh = TriangleMesh::from_obj(CUBE, Color::new(0, 0, 0)).unwrap();
+        let normal = mesh.get_normal_at(&Vector3D::new(point.0, point.1, point.2), 0.);
+
+        assert_eq!(normal, Vector3D::new(expected.0, expected.1, expected.2));
+    }
+
+    #[test]
+    fn test_mesh_bounding_box() {
+        let mesh = TriangleMesh::from_obj(CUBE, Color::new(0, 0, 0)).unwrap();
+        let bounds = mesh.bounding_box();
+
+        assert_eq!(bounds.min(), &Vector3D::new(-1.0, -1.0, -1.0));
+        assert_eq!(bounds.max(), &Vector3D::new(1.0, 1.0, 1.0));
+    }
+
+    #[test_case("missing-vertex", "v 0 0 0\nf 1 2 3\n" ; "face referencing missing vertices")]
+    #[test_case("short-vertex", "v 0 0\n"              ; "vertex with too few coordinates")]
+    #[test_case("zero-index", "v 0 0 0\nf 0 1 1\n"     ; "zero vertex index")]
+    fn test_mesh_from_obj_errors(name: &str, contents: &str) {
+        let path = std::env::temp_dir().join(format!("ray-tracer-{name}.obj"));
+        fs::write(&path, contents).unwrap();
+
+        assert!(TriangleMesh::from_obj(path.to_str().unwrap(), Color::new(0, 0, 0)).is_err());
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{rng::splitmix, vector::Vector3D};
+
+// Random value in [0, 1) for a corner of the integer lattice
+fn lattice_value(x: i64, y: i64, z: i64, seed: u64) -> f64 {
+    let hash = [x, y, z].iter().fold(splitmix(seed), |hash, coordinate| {
+        splitmix(hash ^ *coordinate as u64)
+    });
+
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+// Eases in and out of every lattice cell so the noise has no visible creases along cell edges
+fn smoothstep(t: f64) -> f64 {
+    t * t * (3. - 2. * t)
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+// Value noise in [0, 1), smooth random values at the lattice corners blended across each unit
+// cell. It's a pure function of the point and the seed, so every render comes out the same.
+pub fn value_noise(point: &Vector3D, seed: u64) -> f64 {
+    let (x, y, z) = (point.x().floor(), point.y().floor(), point.z().floor());
+    let (tx, ty, tz) = (
+        smoothstep(point.x() - x),
+        smoothstep(point.y() - y),
+        smoothstep(point.z() - z),
+    );
+    let (x, y, z) = (x as i64, y as i64, z as i64);
+
+    let corner = |dx: i64, dy: i64, dz: i64| lattice_value(x + dx, y + dy, z + dz, seed);
+    let edge = |dy: i64, dz: i64| lerp(corner(0, dy, dz), corner(1, dy, dz), tx);
+    let face = |dz: i64| lerp(edge(0, dz), edge(1, dz), ty);
+
+    lerp(face(0), face(1), tz)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case((0.3, 1.7, -2.2), 0 ; "plain point")]
+    #[test_case((-10.5, 0.0, 3.25), 99 ; "seeded point")]
+    fn test_value_noise_is_repeatable(point: (f64, f64, f64), seed: u64) {
+        let point = Vector3D::new(point.0, point.1, point.2);
+        let value = value_noise(&point, seed);
+
+        assert!((0. ..1.).contains(&value));
+        assert_eq!(value, value_noise(&point, seed));
+    }
+
+    #[test]
+    fn test_value_noise_is_continuous() {
+        // Stepping across a lattice boundary doesn't jump
+        for step in 0..200 {
+            let x = -1. + step as f64 * 0.01;
+            let a = value_noise(&Vector3D::new(x, 0.4, 0.7), 3);
+            let b = value_noise(&Vector3D::new(x + 1e-4, 0.4, 0.7), 3);
+
+            assert!((a - b).abs() < 1e-2, "{a} vs {b} at {x}");
+        }
+    }
+
+    #[test]
+    fn test_seeds_give_different_noise() {
+        let point = Vector3D::new(0.5, 0.5, 0.5);
+
+        assert_ne!(value_noise(&point, 1), value_noise(&point, 2));
+    }
+}
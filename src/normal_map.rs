@@ -0,0 +1,146 @@
+use crate::{
+    noise,
+    vector::{Vector2D, Vector3D},
+};
+
+// Step for the finite differences that turn noise into slopes, in UV units before scaling
+const SLOPE_STEP: f64 = 1e-3;
+
+// Detail added to a surface's shading without changing its shape. Normals are given in tangent
+// space, x along increasing u, y along increasing v and z straight out of the surface.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NormalMap {
+    // The same tilt everywhere, zero for both leaves the surface as it is
+    Tilted {
+        u: f64,
+        v: f64,
+    },
+    // Random bumps from value noise, strength scales how steep they get
+    Noise {
+        strength: f64,
+        scale: f64,
+        seed: u64,
+    },
+}
+
+impl NormalMap {
+    // Unit normal in tangent space at the surface coordinates
+    pub fn normal_at(&self, uv: Vector2D) -> Vector3D {
+        match *self {
+            NormalMap::Tilted { u, v } => Vector3D::new(u, v, 1.).unit(),
+            NormalMap::Noise {
+                strength,
+                scale,
+                seed,
+            } => {
+                let height = |u: f64, v: f64| {
+                    noise::value_noise(&Vector3D::new(u * scale, v * scale, 0.), seed)
+                };
+                let (u, v) = (uv.x(), uv.y());
+
+                let slope_u =
+                    (height(u + SLOPE_STEP, v) - height(u - SLOPE_STEP, v)) / (2. * SLOPE_STEP);
+                let slope_v =
+                    (height(u, v + SLOPE_STEP) - height(u, v - SLOPE_STEP)) / (2. * SLOPE_STEP);
+
+                Vector3D::new(-strength * slope_u, -strength * slope_v, 1.).unit()
+            }
+        }
+    }
+
+    // The world space normal to shade with, from the surface's normal and its direction of
+    // increasing u
+    pub fn perturb(&self, uv: Vector2D, normal: &Vector3D, tangent: &Vector3D) -> Vector3D {
+        let mapped = self.normal_at(uv);
+        let bitangent = tangent.cross(normal);
+
+        tangent
+            .scale(mapped.x())
+            .append(&bitangent.scale(mapped.y()))
+            .append(&normal.scale(mapped.z()))
+            .unit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        body::Sphere,
+        camera::Camera,
+        color::{self, Color},
+        light::PointLight,
+        material::Material,
+        scene::Scene,
+        utils::approx_eq,
+    };
+    use test_case::test_case;
+
+    #[test_case(NormalMap::Tilted { u: 0.0, v: 0.0 } ; "flat")]
+    #[test_case(NormalMap::Tilted { u: 0.5, v: -2.0 } ; "tilted")]
+    #[test_case(NormalMap::Noise { strength: 0.3, scale: 8.0, seed: 1 } ; "noise")]
+    fn test_normal_at_is_unit_and_outward(map: NormalMap) {
+        for (u, v) in [(0.1, 0.2), (0.5, 0.5), (0.93, 0.07)] {
+            let normal = map.normal_at(Vector2D::new(u, v));
+
+            assert!(approx_eq(normal.length(), 1.0));
+            assert!(normal.z() > 0.);
+        }
+    }
+
+    #[test]
+    fn test_flat_map_keeps_the_normal() {
+        let normal = Vector3D::new(0.0, 0.0, 1.0);
+        let tangent = Vector3D::new(1.0, 0.0, 0.0);
+
+        let perturbed = NormalMap::Tilted { u: 0.0, v: 0.0 }.perturb(
+            Vector2D::new(0.3, 0.3),
+            &normal,
+            &tangent,
+        );
+
+        assert_eq!(perturbed, normal);
+    }
+
+    fn lit_sphere_color(normal_map: Option<NormalMap>) -> Color {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            60,
+            60,
+        );
+        let material = Material {
+            normal_map,
+            shininess: 1000.,
+            ..Material::new(color::WHITE)
+        };
+        let scene = Scene::new(
+            &mut camera,
+            color::BLACK,
+            color::BLACK,
+            Box::new([Box::new(Sphere::new(
+                Vector3D::new(0.0, 0.0, 0.0),
+                1.0,
+                material,
+            ))]),
+            Box::new([Box::new(PointLight::new(
+                Vector3D::new(0.0, 0.0, -5.0),
+                color::WHITE,
+                10.0,
+            ))]),
+        );
+
+        scene.trace(30, 30).unwrap()
+    }
+
+    #[test_case(None, false ; "no map")]
+    #[test_case(Some(NormalMap::Tilted { u: 0.0, v: 0.0 }), false ; "flat map shades the same")]
+    #[test_case(Some(NormalMap::Tilted { u: 1.0, v: 0.5 }), true  ; "tilted map turns away from the light")]
+    fn test_normal_map_changes_shading(normal_map: Option<NormalMap>, changed: bool) {
+        let plain = lit_sphere_color(None).rgba();
+        let mapped = lit_sphere_color(normal_map).rgba();
+
+        assert_eq!(mapped != plain, changed, "{mapped:?} vs {plain:?}");
+        assert!(mapped[0] <= plain[0]);
+    }
+}
@@ -0,0 +1,62 @@
+// Fraction of light reflected off a surface, using Schlick's approximation of the Fresnel
+// equations. cos_theta is the cosine of the incidence angle and eta_ratio the incoming over the
+// outgoing refractive index.
+pub fn fresnel(cos_theta: f64, eta_ratio: f64) -> f64 {
+    let cos_theta = cos_theta.clamp(0., 1.);
+
+    // Going into a less dense medium the transmitted angle is the larger one, so use that instead
+    let cos = if eta_ratio > 1. {
+        let sin_squared_transmitted = eta_ratio * eta_ratio * (1. - cos_theta * cos_theta);
+
+        if sin_squared_transmitted > 1. {
+            return 1.;
+        }
+
+        (1. - sin_squared_transmitted).sqrt()
+    } else {
+        cos_theta
+    };
+
+    let r0 = ((1. - eta_ratio) / (1. + eta_ratio)).powi(2);
+
+    r0 + (1. - r0) * (1. - cos).powi(5)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(1.0 / 1.5 ; "entering glass")]
+    #[test_case(1.0 / 1.33 ; "entering water")]
+    fn test_fresnel_normal_incidence_is_small(eta_ratio: f64) {
+        let reflectance = fresnel(1., eta_ratio);
+
+        assert!(reflectance > 0.);
+        assert!(reflectance < 0.05);
+    }
+
+    #[test_case(1.0 / 1.5 ; "entering glass")]
+    #[test_case(1.0 / 1.33 ; "entering water")]
+    fn test_fresnel_grazing_incidence_approaches_one(eta_ratio: f64) {
+        assert!(fresnel(1e-3, eta_ratio) > 0.98);
+        assert!((fresnel(0., eta_ratio) - 1.).abs() < 1e-12);
+    }
+
+    #[test_case(1.0 / 1.5 ; "entering glass")]
+    fn test_fresnel_increases_towards_grazing(eta_ratio: f64) {
+        let reflectances: Vec<f64> = [1.0, 0.8, 0.5, 0.2, 0.0]
+            .iter()
+            .map(|cos_theta| fresnel(*cos_theta, eta_ratio))
+            .collect();
+
+        assert!(reflectances.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test_case(1.0, 1.0, 0.0 ; "matching indices don't reflect head on")]
+    #[test_case(1.0, 1.5, 0.04 ; "leaving glass head on matches entering it")]
+    #[test_case(0.2, 1.5, 1.0 ; "past the critical angle everything reflects")]
+    fn test_fresnel_values(cos_theta: f64, eta_ratio: f64, expected: f64) {
+        assert!((fresnel(cos_theta, eta_ratio) - expected).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,196 @@
+use crate::{
+    aabb::Aabb,
+    body::{Body, Colored, Renderable, Volume, THRESHOLD},
+    color::Color,
+    material::Material,
+    ray::Ray,
+    roots::Roots,
+    texture::Texture,
+    vector::{Vector2D, Vector3D},
+};
+
+#[derive(Debug)]
+pub struct Plane {
+    body: Body,
+    point: Vector3D,
+    normal: Vector3D,
+    texture: Option<Box<dyn Texture>>,
+}
+
+impl Plane {
+    pub fn new(point: Vector3D, normal: Vector3D, material: impl Into<Material>) -> Self {
+        Plane {
+            body: Body::new(material),
+            point,
+            normal: normal.unit(),
+            texture: None,
+        }
+    }
+
+    // Colors the plane with the texture instead of the material's albedo
+    pub fn with_texture(mut self, texture: impl Texture + 'static) -> Self {
+        self.texture = Some(Box::new(texture));
+        self
+    }
+}
+
+impl Colored for Plane {
+    fn material(&self) -> &Material {
+        self.body.material()
+    }
+}
+
+impl Volume for Plane {
+    fn intersect(&self, ray: &Ray) -> Roots {
+        let denominator = ray.direction.dot(&self.normal);
+
+        // The ray runs parallel to the plane, so it either never touches it or lies inside it
+        if denominator.abs() < THRESHOLD {
+            return Roots::new();
+        }
+
+        let ray_start_to_point = Vector3D::from(&ray.start).to(&self.point);
+
+        Roots::from([ray_start_to_point.dot(&self.normal) / denominator])
+    }
+
+    // As a solid the plane fills the half space its normal points away from
+    fn intervals(&self, ray: &Ray) -> Vec<(f64, f64)> {
+        let denominator = ray.direction.dot(&self.normal);
+
+        if denominator.abs() < THRESHOLD {
+            let height = Vector3D::from(&self.point).to(&ray.start).dot(&self.normal);
+
+            return if height < 0. {
+                vec![(f64::NEG_INFINITY, f64::INFINITY)]
+            } else {
+                vec![]
+            };
+        }
+
+        let distance = Vector3D::from(&ray.start).to(&self.point).dot(&self.normal) / denominator;
+
+        if denominator < 0. {
+            vec![(distance, f64::INFINITY)]
+        } else {
+            vec![(f64::NEG_INFINITY, distance)]
+        }
+    }
+
+    fn get_normal_at(&self, _point: &Vector3D, _time: f64) -> Vector3D {
+        self.normal.clone()
+    }
+
+    fn get_color_at(&self, point: &Vector3D, _time: f64) -> Color {
+        match &self.texture {
+            // Planes have no UV mapping, textures on them go by the point
+            Some(texture) => texture.sample(Vector2D::default(), point),
+            None => self.color(),
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::infinite()
+    }
+}
+
+impl Renderable for Plane {
+    fn is_enabled(&self) -> bool {
+        self.body.is_enabled()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.body.set_enabled(enabled);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{texture::Checkerboard, utils::approx_eq};
+    use test_case::test_case;
+
+    #[test_case(
+        (1.0, 5.0, 2.0), (0.0, -1.0, 0.0), vec![5.0], Some(Vector3D::new(1.0, 0.0, 2.0))
+        ; "ray straight down hits ground plane")]
+    #[test_case(
+        (1.0, -4.0, -2.0), (0.0, 1.0, 0.0), vec![4.0], Some(Vector3D::new(1.0, 0.0, -2.0))
+        ; "ray from below hits ground plane")]
+    #[test_case(
+        (0.0, 5.0, 0.0), (1.0, 0.0, 0.0), vec![], None
+        ; "ray parallel to ground plane misses")]
+    #[test_case(
+        (0.0, 5.0, 0.0), (0.0, 1.0, 0.0), vec![-5.0], None
+        ; "ray pointing away from ground plane misses")]
+    fn test_plane_intersection(
+        start: (f64, f64, f64),
+        direction: (f64, f64, f64),
+        expected_ts: Vec<f64>,
+        expected_closest_point: Option<Vector3D>,
+    ) {
+        let plane = Plane::new(
+            Vector3D::new(0.0, 0.0, 0.0),
+            Vector3D::new(0.0, 1.0, 0.0),
+            Color::new(0, 0, 0),
+        );
+        let ray = Ray::new(
+            &Vector3D::new(start.0, start.1, start.2),
+            &Vector3D::new(direction.0, direction.1, direction.2),
+        );
+
+        let intersections = plane.intersect(&ray);
+        assert_eq!(intersections.len(), expected_ts.len());
+        assert!(intersections
+            .iter()
+            .zip(expected_ts.iter())
+            .all(|(t, expected)| approx_eq(*t, *expected)));
+
+        let closest = plane.closest_ray_point(&ray);
+        match (closest, expected_closest_point) {
+            (Some(closest), Some(expected)) => {
+                assert!(approx_eq(closest.x(), expected.x()));
+                assert!(approx_eq(closest.y(), expected.y()));
+                assert!(approx_eq(closest.z(), expected.z()));
+            }
+            (closest, expected) => assert_eq!(closest, expected),
+        }
+    }
+
+    #[test_case((0.0, 3.0, 0.0) ; "plane normal is normalized")]
+    fn test_plane_normal(normal: (f64, f64, f64)) {
+        let plane = Plane::new(
+            Vector3D::new(0.0, 0.0, 0.0),
+            Vector3D::new(normal.0, normal.1, normal.2),
+            Color::new(0, 0, 0),
+        );
+        let normal = plane.get_normal_at(&Vector3D::new(1.0, 0.0, 1.0), 0.);
+
+        assert!(approx_eq(normal.length(), 1.0));
+        assert!(approx_eq(normal.y(), 1.0));
+    }
+
+    #[test_case((0.5, 0.5), (1.5, 0.5) ; "neighbours along X alternate")]
+    #[test_case((0.5, 0.5), (0.5, 1.5) ; "neighbours along Z alternate")]
+    fn test_plane_checkerboard(a: (f64, f64), b: (f64, f64)) {
+        let plane = Plane::new(
+            Vector3D::new(0.0, 0.0, 0.0),
+            Vector3D::new(0.0, 1.0, 0.0),
+            Color::new(1, 2, 3),
+        )
+        .with_texture(Checkerboard::new(
+            Color::new(255, 255, 255),
+            Color::new(0, 0, 0),
+            1.0,
+        ));
+
+        let hit = |x: f64, z: f64| {
+            let ray = Ray::new(&Vector3D::new(x, 5.0, z), &Vector3D::new(0.0, -1.0, 0.0));
+            let point = plane.closest_ray_point(&ray).unwrap();
+
+            plane.get_color_at(&point, 0.).rgba()
+        };
+
+        assert_ne!(hit(a.0, a.1), hit(b.0, b.1));
+        assert_eq!(plane.color().rgba(), Color::new(1, 2, 3).rgba());
+    }
+}
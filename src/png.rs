@@ -0,0 +1,739 @@
+// Minimal PNG encoder for 8-bit RGBA images. The pixel data isn't compressed, it's wrapped in
+// stored deflate blocks which every decoder understands, trading file size for simplicity.
+// The decoder reads any non-interlaced 8-bit PNG, compressed or not.
+
+use color_eyre::eyre::{eyre, Result};
+
+pub const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+// Deflate stored blocks hold at most this many bytes
+const MAX_STORED_BLOCK: usize = 0xffff;
+
+const BIT_DEPTH: u8 = 8;
+const COLOR_TYPE_GRAY: u8 = 0;
+const COLOR_TYPE_RGB: u8 = 2;
+const COLOR_TYPE_PALETTE: u8 = 3;
+const COLOR_TYPE_GRAY_ALPHA: u8 = 4;
+const COLOR_TYPE_RGBA: u8 = 6;
+const FILTER_NONE: u8 = 0;
+const FILTER_SUB: u8 = 1;
+const FILTER_UP: u8 = 2;
+const FILTER_AVERAGE: u8 = 3;
+const FILTER_PAETH: u8 = 4;
+
+// Encodes row-major RGBA pixels, four bytes per pixel
+pub fn encode(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    let mut png = SIGNATURE.to_vec();
+
+    let mut header = Vec::with_capacity(13);
+    header.extend(width.to_be_bytes());
+    header.extend(height.to_be_bytes());
+    // Compression, filter and interlace methods are all the defaults
+    header.extend([BIT_DEPTH, COLOR_TYPE_RGBA, 0, 0, 0]);
+    write_chunk(&mut png, b"IHDR", &header);
+
+    // Every scanline starts with the filter it was encoded with
+    let row_length = width as usize * 4;
+    let scanlines: Vec<u8> = rgba
+        .chunks(row_length.max(1))
+        .take(height as usize)
+        .flat_map(|row| std::iter::once(FILTER_NONE).chain(row.iter().copied()))
+        .collect();
+    write_chunk(&mut png, b"IDAT", &zlib_stored(&scanlines));
+
+    write_chunk(&mut png, b"IEND", &[]);
+
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    png.extend(kind);
+    png.extend(data);
+
+    let crc = crc32(kind.iter().chain(data));
+    png.extend(crc.to_be_bytes());
+}
+
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    // Deflate with a 32K window and no preset dictionary, the check bits make this 0x7801
+    let mut zlib = vec![0x78, 0x01];
+
+    let blocks: Vec<&[u8]> = data.chunks(MAX_STORED_BLOCK).collect();
+
+    if blocks.is_empty() {
+        zlib.extend([1, 0, 0, 0xff, 0xff]);
+    }
+
+    for (index, block) in blocks.iter().enumerate() {
+        let is_final = index == blocks.len() - 1;
+        let length = block.len() as u16;
+
+        zlib.push(is_final as u8);
+        zlib.extend(length.to_le_bytes());
+        zlib.extend((!length).to_le_bytes());
+        zlib.extend(*block);
+    }
+
+    zlib.extend(adler32(data).to_be_bytes());
+
+    zlib
+}
+
+// Reads row-major RGBA pixels back out of a PNG, along with its width and height
+pub fn decode(png: &[u8]) -> Result<(u32, u32, Vec<u8>)> {
+    if !png.starts_with(&SIGNATURE) {
+        return Err(eyre!("Not a PNG"));
+    }
+
+    let mut offset = SIGNATURE.len();
+    let (mut header, mut palette, mut idat) = (None, vec![], vec![]);
+
+    loop {
+        let length = png
+            .get(offset..offset + 4)
+            .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+            .ok_or_else(|| eyre!("PNG ends before its IEND chunk"))?;
+        let chunk = png
+            .get(offset + 4..offset + 12 + length)
+            .ok_or_else(|| eyre!("PNG ends in the middle of a chunk"))?;
+        let (kind, data) = (&chunk[..4], &chunk[4..4 + length]);
+        let crc = &chunk[4 + length..];
+
+        if crc32(chunk[..4 + length].iter()).to_be_bytes() != crc {
+            return Err(eyre!(
+                "Corrupt {} chunk in PNG",
+                String::from_utf8_lossy(kind)
+            ));
+        }
+
+        match kind {
+            b"IHDR" => header = Some(Header::parse(data)?),
+            b"PLTE" => {
+                palette = data
+                    .chunks_exact(3)
+                    .map(|rgb| [rgb[0], rgb[1], rgb[2]])
+                    .collect()
+            }
+            b"IDAT" => idat.extend(data),
+            b"IEND" => break,
+            // Ancillary chunks like gamma or text don't change the pixels
+            _ => {}
+        }
+
+        offset += 12 + length;
+    }
+
+    let header = header.ok_or_else(|| eyre!("PNG has no IHDR chunk"))?;
+    let channels = header.channels();
+    let row_length = (header.width as usize)
+        .checked_mul(channels)
+        .ok_or_else(|| eyre!("PNG is too large at {}x{}", header.width, header.height))?;
+    let scanlines_length = (row_length + 1)
+        .checked_mul(header.height as usize)
+        .ok_or_else(|| eyre!("PNG is too large at {}x{}", header.width, header.height))?;
+
+    let scanlines = zlib_decompress(&idat, scanlines_length)?;
+    if scanlines.len() != scanlines_length {
+        return Err(eyre!("PNG has the wrong amount of pixel data"));
+    }
+
+    let samples = unfilter(&scanlines, row_length, channels)?;
+
+    let rgba = samples
+        .chunks_exact(channels)
+        .map(|sample| match header.color_type {
+            COLOR_TYPE_GRAY => Ok([sample[0], sample[0], sample[0], 255]),
+            COLOR_TYPE_GRAY_ALPHA => Ok([sample[0], sample[0], sample[0], sample[1]]),
+            COLOR_TYPE_RGB => Ok([sample[0], sample[1], sample[2], 255]),
+            COLOR_TYPE_PALETTE => palette
+                .get(sample[0] as usize)
+                .map(|[r, g, b]| [*r, *g, *b, 255])
+                .ok_or_else(|| eyre!("PNG palette has no color {}", sample[0])),
+            _ => Ok([sample[0], sample[1], sample[2], sample[3]]),
+        })
+        .collect::<Result<Vec<_>>>()?
+        .concat();
+
+    Ok((header.width, header.height, rgba))
+}
+
+struct Header {
+    width: u32,
+    height: u32,
+    color_type: u8,
+}
+
+impl Header {
+    fn parse(data: &[u8]) -> Result<Self> {
+        let [w0, w1, w2, w3, h0, h1, h2, h3, bit_depth, color_type, compression, filter, interlace] =
+            *data
+        else {
+            return Err(eyre!("PNG header has the wrong length"));
+        };
+
+        if bit_depth != BIT_DEPTH {
+            return Err(eyre!("Only 8-bit PNGs are supported, got {bit_depth}-bit"));
+        }
+        if ![
+            COLOR_TYPE_GRAY,
+            COLOR_TYPE_RGB,
+            COLOR_TYPE_PALETTE,
+            COLOR_TYPE_GRAY_ALPHA,
+            COLOR_TYPE_RGBA,
+        ]
+        .contains(&color_type)
+        {
+            return Err(eyre!("Unknown PNG color type {color_type}"));
+        }
+        if compression != 0 || filter != 0 {
+            return Err(eyre!("Unknown PNG compression or filter method"));
+        }
+        if interlace != 0 {
+            return Err(eyre!("Interlaced PNGs aren't supported"));
+        }
+
+        Ok(Header {
+            width: u32::from_be_bytes([w0, w1, w2, w3]),
+            height: u32::from_be_bytes([h0, h1, h2, h3]),
+            color_type,
+        })
+    }
+
+    // Bytes per pixel, every sample is a byte at this bit depth
+    fn channels(&self) -> usize {
+        match self.color_type {
+            COLOR_TYPE_GRAY | COLOR_TYPE_PALETTE => 1,
+            COLOR_TYPE_GRAY_ALPHA => 2,
+            COLOR_TYPE_RGB => 3,
+            _ => 4,
+        }
+    }
+}
+
+// Undoes the filter each scanline was written with, every filter predicts a byte from the
+// ones to its left and above
+fn unfilter(scanlines: &[u8], row_length: usize, channels: usize) -> Result<Vec<u8>> {
+    let mut samples: Vec<u8> = Vec::with_capacity(scanlines.len());
+    let blank = vec![0; row_length];
+
+    for (row, scanline) in scanlines.chunks_exact(row_length + 1).enumerate() {
+        let start = row * row_length;
+        let (filter, line) = (scanline[0], &scanline[1..]);
+
+        for (index, byte) in line.iter().enumerate() {
+            let previous = &samples[start.saturating_sub(row_length)..];
+            let above = if row == 0 { &blank } else { previous };
+            let left = match index.checked_sub(channels) {
+                Some(left) => samples[start + left],
+                None => 0,
+            };
+            let up = above[index];
+            let up_left = match index.checked_sub(channels) {
+                Some(left) => above[left],
+                None => 0,
+            };
+
+            let prediction = match filter {
+                FILTER_NONE => 0,
+                FILTER_SUB => left,
+                FILTER_UP => up,
+                FILTER_AVERAGE => ((left as u16 + up as u16) / 2) as u8,
+                FILTER_PAETH => paeth(left, up, up_left),
+                _ => return Err(eyre!("Unknown PNG filter {filter} on row {row}")),
+            };
+
+            samples.push(byte.wrapping_add(prediction));
+        }
+    }
+
+    Ok(samples)
+}
+
+// Whichever neighbour is closest to left + up - up_left, preferring left then up on ties
+fn paeth(left: u8, up: u8, up_left: u8) -> u8 {
+    let estimate = left as i16 + up as i16 - up_left as i16;
+    let (to_left, to_up, to_up_left) = (
+        (estimate - left as i16).abs(),
+        (estimate - up as i16).abs(),
+        (estimate - up_left as i16).abs(),
+    );
+
+    if to_left <= to_up && to_left <= to_up_left {
+        left
+    } else if to_up <= to_up_left {
+        up
+    } else {
+        up_left
+    }
+}
+
+// Inflates a zlib stream, giving up once it grows past the limit so a corrupt or hostile file
+// can't use up all the memory
+fn zlib_decompress(zlib: &[u8], limit: usize) -> Result<Vec<u8>> {
+    let [method, flags, ..] = *zlib else {
+        return Err(eyre!("PNG data is missing its zlib header"));
+    };
+
+    if method & 0x0f != 8 || !(method as u16 * 256 + flags as u16).is_multiple_of(31) {
+        return Err(eyre!("PNG data isn't deflate compressed"));
+    }
+    if flags & 0x20 != 0 {
+        return Err(eyre!("PNG data needs a preset dictionary"));
+    }
+
+    let mut bits = Bits::new(&zlib[2..]);
+    let data = inflate(&mut bits, limit)?;
+
+    if adler32(&data).to_be_bytes() != bits.aligned_bytes()? {
+        return Err(eyre!("PNG data fails its Adler-32 check"));
+    }
+
+    Ok(data)
+}
+
+// Lengths and distances of back references are a base plus some extra bits, see RFC 1951 3.2.5
+const LENGTH_BASES: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA_BITS: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASES: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA_BITS: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+// Order the code lengths of the code length alphabet are sent in
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+const END_OF_BLOCK: u16 = 256;
+
+fn inflate(bits: &mut Bits, limit: usize) -> Result<Vec<u8>> {
+    let mut data = vec![];
+
+    loop {
+        let is_final = bits.take(1)? == 1;
+
+        match bits.take(2)? {
+            0 => {
+                let [low, high, not_low, not_high] = bits.aligned_bytes()?;
+                let length = u16::from_le_bytes([low, high]);
+                if length != !u16::from_le_bytes([not_low, not_high]) {
+                    return Err(eyre!("PNG data has a corrupt stored block"));
+                }
+                data.extend(bits.bytes(length as usize)?);
+            }
+            1 => {
+                let (literals, distances) = Huffman::fixed();
+                inflate_block(bits, &literals, &distances, &mut data, limit)?;
+            }
+            2 => {
+                let (literals, distances) = Huffman::dynamic(bits)?;
+                inflate_block(bits, &literals, &distances, &mut data, limit)?;
+            }
+            _ => return Err(eyre!("PNG data has an unknown block type")),
+        }
+
+        if data.len() > limit {
+            return Err(eyre!("PNG data is larger than the image"));
+        }
+        if is_final {
+            return Ok(data);
+        }
+    }
+}
+
+fn inflate_block(
+    bits: &mut Bits,
+    literals: &Huffman,
+    distances: &Huffman,
+    data: &mut Vec<u8>,
+    limit: usize,
+) -> Result<()> {
+    loop {
+        let symbol = literals.decode(bits)?;
+
+        if symbol < END_OF_BLOCK {
+            data.push(symbol as u8);
+        } else if symbol == END_OF_BLOCK {
+            return Ok(());
+        } else {
+            let index = (symbol - END_OF_BLOCK - 1) as usize;
+            let (Some(base), Some(extra)) = (LENGTH_BASES.get(index), LENGTH_EXTRA_BITS.get(index))
+            else {
+                return Err(eyre!("PNG data has an invalid length code"));
+            };
+            let length = (*base as u32 + bits.take(*extra)?) as usize;
+
+            let index = distances.decode(bits)? as usize;
+            let (Some(base), Some(extra)) =
+                (DISTANCE_BASES.get(index), DISTANCE_EXTRA_BITS.get(index))
+            else {
+                return Err(eyre!("PNG data has an invalid distance code"));
+            };
+            let distance = (*base as u32 + bits.take(*extra)?) as usize;
+
+            if distance > data.len() {
+                return Err(eyre!("PNG data refers back past its start"));
+            }
+            // The copy can overlap what it writes, so it goes a byte at a time
+            let start = data.len() - distance;
+            for offset in 0..length {
+                data.push(data[start + offset]);
+            }
+        }
+
+        if data.len() > limit {
+            return Err(eyre!("PNG data is larger than the image"));
+        }
+    }
+}
+
+// Deflate packs bits starting from the least significant one of each byte
+struct Bits<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Bits<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Bits { bytes, position: 0 }
+    }
+
+    fn take(&mut self, count: u8) -> Result<u32> {
+        (0..count).try_fold(0, |value, bit| {
+            let byte = self
+                .bytes
+                .get(self.position / 8)
+                .ok_or_else(|| eyre!("PNG data ends too early"))?;
+            let value = value | ((*byte as u32 >> (self.position % 8)) & 1) << bit;
+            self.position += 1;
+
+            Ok(value)
+        })
+    }
+
+    // Skips to the next whole byte and reads from there
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8]> {
+        let start = self.position.div_ceil(8);
+        let bytes = self
+            .bytes
+            .get(start..start + count)
+            .ok_or_else(|| eyre!("PNG data ends too early"))?;
+        self.position = (start + count) * 8;
+
+        Ok(bytes)
+    }
+
+    fn aligned_bytes<const N: usize>(&mut self) -> Result<[u8; N]> {
+        self.bytes(N)?
+            .try_into()
+            .map_err(|_| eyre!("PNG data ends too early"))
+    }
+}
+
+// Canonical Huffman code, only the number of codes of each length and the symbols in order are
+// needed to decode it
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self> {
+        let mut counts = [0; 16];
+        for length in lengths {
+            counts[*length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        // More codes of a length than there's room for can't be decoded
+        let mut left: i32 = 1;
+        for count in &counts[1..] {
+            left = left * 2 - *count as i32;
+            if left < 0 {
+                return Err(eyre!("PNG data has an invalid Huffman code"));
+            }
+        }
+
+        let mut symbols = vec![];
+        for length in 1..16 {
+            symbols.extend(
+                lengths
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, symbol_length)| **symbol_length == length)
+                    .map(|(symbol, _)| symbol as u16),
+            );
+        }
+
+        Ok(Huffman { counts, symbols })
+    }
+
+    // The codes deflate uses for blocks that don't send their own
+    fn fixed() -> (Huffman, Huffman) {
+        let literals: Vec<u8> = (0..288)
+            .map(|symbol| match symbol {
+                0..=143 => 8,
+                144..=255 => 9,
+                256..=279 => 7,
+                _ => 8,
+            })
+            .collect();
+
+        (
+            Huffman::new(&literals).expect("the fixed literal code is complete"),
+            Huffman::new(&[5; 30]).expect("the fixed distance code is complete"),
+        )
+    }
+
+    // Reads the block's code lengths, themselves Huffman coded with runs of repeats
+    fn dynamic(bits: &mut Bits) -> Result<(Huffman, Huffman)> {
+        let literal_count = bits.take(5)? as usize + 257;
+        let distance_count = bits.take(5)? as usize + 1;
+        let code_length_count = bits.take(4)? as usize + 4;
+
+        let mut code_lengths = [0; 19];
+        for index in &CODE_LENGTH_ORDER[..code_length_count] {
+            code_lengths[*index] = bits.take(3)? as u8;
+        }
+        let code_lengths = Huffman::new(&code_lengths)?;
+
+        let mut lengths = vec![];
+        while lengths.len() < literal_count + distance_count {
+            let (length, repeat) = match code_lengths.decode(bits)? {
+                16 => (
+                    *lengths
+                        .last()
+                        .ok_or_else(|| eyre!("PNG data repeats a length before the first"))?,
+                    3 + bits.take(2)?,
+                ),
+                17 => (0, 3 + bits.take(3)?),
+                18 => (0, 11 + bits.take(7)?),
+                length => (length as u8, 1),
+            };
+
+            lengths.extend(std::iter::repeat_n(length, repeat as usize));
+        }
+
+        if lengths.len() != literal_count + distance_count {
+            return Err(eyre!("PNG data repeats code lengths past the end"));
+        }
+
+        let (literals, distances) = lengths.split_at(literal_count);
+
+        Ok((Huffman::new(literals)?, Huffman::new(distances)?))
+    }
+
+    // Codes are read a bit at a time from their most significant bit, each length's codes
+    // follow on from the shorter ones
+    fn decode(&self, bits: &mut Bits) -> Result<u16> {
+        let (mut code, mut first, mut index) = (0, 0, 0);
+
+        for count in &self.counts[1..] {
+            code |= bits.take(1)? as i32;
+            let count = *count as i32;
+
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+
+        Err(eyre!("PNG data has an invalid Huffman code"))
+    }
+}
+
+// CRC-32 as used by PNG, computed bitwise since the images are small
+fn crc32<'a>(bytes: impl Iterator<Item = &'a u8>) -> u32 {
+    let crc = bytes.fold(0xffff_ffffu32, |crc, byte| {
+        (0..8).fold(crc ^ *byte as u32, |crc, _| {
+            if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            }
+        })
+    });
+
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    const MODULUS: u32 = 65521;
+
+    let (a, b) = bytes.iter().fold((1u32, 0u32), |(a, b), byte| {
+        let a = (a + *byte as u32) % MODULUS;
+        (a, (b + a) % MODULUS)
+    });
+
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    // Settings are the bit depth, color type, compression, filter and interlace methods
+    fn png_with(
+        width: u32,
+        height: u32,
+        settings: [u8; 5],
+        palette: &[u8],
+        scanlines: &[u8],
+    ) -> Vec<u8> {
+        let mut png = SIGNATURE.to_vec();
+
+        let header = [&width.to_be_bytes()[..], &height.to_be_bytes(), &settings].concat();
+        write_chunk(&mut png, b"IHDR", &header);
+        if !palette.is_empty() {
+            write_chunk(&mut png, b"PLTE", palette);
+        }
+        write_chunk(&mut png, b"IDAT", &zlib_stored(scanlines));
+        write_chunk(&mut png, b"IEND", &[]);
+
+        png
+    }
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|index| u8::from_str_radix(&hex[index..index + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test_case(b"", 0x0000_0000          ; "empty input")]
+    #[test_case(b"IEND", 0xae42_6082      ; "end chunk type")]
+    #[test_case(b"123456789", 0xcbf4_3926 ; "standard check value")]
+    fn test_crc32(bytes: &[u8], expected: u32) {
+        assert_eq!(crc32(bytes.iter()), expected);
+    }
+
+    #[test_case(b"", 0x0000_0001          ; "empty input")]
+    #[test_case(b"Wikipedia", 0x11e6_0398 ; "reference value")]
+    fn test_adler32(bytes: &[u8], expected: u32) {
+        assert_eq!(adler32(bytes), expected);
+    }
+
+    // Checked against Python's zlib, which also decompresses the IDAT data
+    #[test]
+    fn test_encode_single_red_pixel() {
+        let expected: Vec<u8> = [
+            &SIGNATURE[..],
+            // IHDR, 1x1 RGBA with its CRC
+            &[
+                0, 0, 0, 13, b'I', b'H', b'D', b'R', 0, 0, 0, 1, 0, 0, 0, 1, 8, 6, 0, 0, 0,
+            ],
+            &[0x1f, 0x15, 0xc4, 0x89],
+            // IDAT, the zlib header, one final stored block with the scanline and its Adler-32
+            &[
+                0, 0, 0, 16, b'I', b'D', b'A', b'T', 0x78, 0x01, 1, 5, 0, 0xfa, 0xff,
+            ],
+            &[0, 255, 0, 0, 255],
+            &[0x05, 0x00, 0x01, 0xff],
+            &[0xfa, 0x5c, 0x88, 0xd1],
+            // IEND
+            &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82],
+        ]
+        .concat();
+
+        assert_eq!(encode(1, 1, &[255, 0, 0, 255]), expected);
+    }
+
+    #[test_case(1, 1 ; "single pixel")]
+    #[test_case(3, 2 ; "odd width")]
+    #[test_case(200, 100 ; "spans several stored blocks")]
+    fn test_encode_round_trip(width: u32, height: u32) {
+        let rgba: Vec<u8> = (0..width * height * 4).map(|byte| byte as u8).collect();
+
+        let (decoded_width, decoded_height, decoded) =
+            decode(&encode(width, height, &rgba)).unwrap();
+
+        assert_eq!((decoded_width, decoded_height), (width, height));
+        assert_eq!(decoded, rgba);
+    }
+
+    // Compressed by Python's zlib, which picks fixed codes for the short text and its own for
+    // the longer one
+    #[test_case("7801010500faff68656c6c6f062c0215", b"hello" ; "stored block")]
+    #[test_case("78dacb48cdc9c957c8402701680308b1", b"hello hello hello hello" ; "fixed codes")]
+    #[test_case(
+        "78da0dc6c10d80201004c056b600abb183932c42029e598448f730af396d3614da20be4404ab94edcafb9dc0414dbcf9673970797f02e131a266c9d516b89e16b6",
+        b"Rays leave the camera through every pixel, bounce off mirrors"
+        ; "dynamic codes")]
+    fn test_zlib_decompress(hex: &str, expected: &[u8]) {
+        let zlib = from_hex(hex);
+
+        assert_eq!(zlib_decompress(&zlib, expected.len()).unwrap(), expected);
+        // Output past the limit is refused rather than buffered
+        assert!(zlib_decompress(&zlib, expected.len() - 1).is_err());
+    }
+
+    #[test_case("78dacb48cdc9c957c8402701680308b2" ; "wrong checksum")]
+    #[test_case("78dbcb48cdc9c957c8402701680308b1" ; "bad header check bits")]
+    #[test_case("78dacb48cdc9c957c840"             ; "cut short")]
+    #[test_case("7801010500faef68656c6c6f062c0215" ; "stored length mismatch")]
+    fn test_zlib_decompress_rejects(hex: &str) {
+        assert!(zlib_decompress(&from_hex(hex), 100).is_err());
+    }
+
+    #[test_case(FILTER_NONE, [8, 39, 45, 39]       ; "none")]
+    #[test_case(FILTER_SUB, [8, 47, 92, 131]       ; "sub adds the byte to the left")]
+    #[test_case(FILTER_UP, [84, 125, 135, 82]      ; "up adds the byte above")]
+    #[test_case(FILTER_AVERAGE, [46, 105, 142, 131] ; "average of left and above")]
+    #[test_case(FILTER_PAETH, [84, 125, 170, 129]  ; "paeth picks above, left and up left")]
+    fn test_decode_filters(filter: u8, expected: [u8; 4]) {
+        let scanlines = [&[FILTER_NONE, 76, 86, 90, 43][..], &[filter, 8, 39, 45, 39]].concat();
+        let png = png_with(4, 2, [8, COLOR_TYPE_GRAY, 0, 0, 0], &[], &scanlines);
+
+        let (_, _, rgba) = decode(&png).unwrap();
+        let second_row: Vec<u8> = rgba[16..].chunks(4).map(|pixel| pixel[0]).collect();
+
+        assert_eq!(second_row, expected);
+    }
+
+    #[test_case(COLOR_TYPE_GRAY, &[], &[90], [90, 90, 90, 255]              ; "gray")]
+    #[test_case(COLOR_TYPE_GRAY_ALPHA, &[], &[90, 7], [90, 90, 90, 7]       ; "gray with alpha")]
+    #[test_case(COLOR_TYPE_RGB, &[], &[1, 2, 3], [1, 2, 3, 255]             ; "rgb")]
+    #[test_case(COLOR_TYPE_PALETTE, &[9, 9, 9, 10, 20, 30], &[1], [10, 20, 30, 255] ; "palette")]
+    #[test_case(COLOR_TYPE_RGBA, &[], &[1, 2, 3, 4], [1, 2, 3, 4]           ; "rgba")]
+    fn test_decode_color_types(color_type: u8, palette: &[u8], pixel: &[u8], expected: [u8; 4]) {
+        let scanline = [&[FILTER_NONE][..], pixel].concat();
+        let png = png_with(1, 1, [8, color_type, 0, 0, 0], palette, &scanline);
+
+        assert_eq!(decode(&png).unwrap(), (1, 1, expected.to_vec()));
+    }
+
+    #[test_case([16, COLOR_TYPE_RGBA, 0, 0, 0], &[0; 9], &[] ; "16-bit")]
+    #[test_case([8, COLOR_TYPE_RGBA, 0, 0, 1], &[0; 9], &[]  ; "interlaced")]
+    #[test_case([8, 5, 0, 0, 0], &[0; 9], &[]                ; "unknown color type")]
+    #[test_case([8, COLOR_TYPE_RGBA, 0, 0, 0], &[0; 5], &[]  ; "missing pixel data")]
+    #[test_case([8, COLOR_TYPE_RGBA, 0, 0, 0], &[0; 13], &[] ; "extra pixel data")]
+    #[test_case([8, COLOR_TYPE_RGBA, 0, 0, 0], &[5, 0, 0, 0, 0, 0, 0, 0, 0], &[] ; "unknown filter")]
+    #[test_case([8, COLOR_TYPE_PALETTE, 0, 0, 0], &[0, 2, 0], &[1, 2, 3, 4, 5, 6] ; "color missing from the palette")]
+    fn test_decode_rejects_unsupported(settings: [u8; 5], scanlines: &[u8], palette: &[u8]) {
+        assert!(decode(&png_with(2, 1, settings, palette, scanlines)).is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_broken_files() {
+        let png = encode(2, 2, &[7; 16]);
+        let mut corrupt = png.clone();
+        corrupt[20] ^= 1;
+
+        assert!(decode(&png).is_ok());
+        assert!(decode(b"P6 2 2 255\n").is_err());
+        assert!(decode(&corrupt).is_err());
+        assert!(decode(&png[..png.len() - 12]).is_err());
+    }
+}
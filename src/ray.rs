@@ -1,13 +1,27 @@
-use std::cmp::Ordering;
+use crate::{
+    body::{shading_normal, HitRecord},
+    color::{Color, ColorF},
+    error::Result,
+    optics,
+    scene::Scene,
+    shading,
+    vector::Vector3D,
+};
 
-use color_eyre::eyre::Result;
+// Nudge for secondary rays so they don't hit the surface they start on due to rounding
+pub const SURFACE_BIAS: f64 = 1e-9;
 
-use crate::{color::Color, scene::Scene, vector::Vector3D};
+// Slack on bounding spheres so rounding can't cull a body the ray touches
+const SPHERE_MARGIN: f64 = 1e-6;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Ray {
     pub start: Vector3D,
     pub direction: Vector3D,
+    // When during the frame the ray was cast, moving bodies are placed by it
+    pub time: f64,
+    // Seeds the random parts of shading what the ray hits, such as soft shadows
+    pub seed: u64,
 }
 
 impl Ray {
@@ -15,28 +29,134 @@ impl Ray {
         Ray {
             start: start.into(),
             direction: direction.unit(),
+            time: 0.,
+            seed: 0,
         }
     }
 
+    pub fn with_time(mut self, time: f64) -> Self {
+        self.time = time;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    // Point the given distance along the ray
+    pub fn at(&self, t: f64) -> Vector3D {
+        &self.start + &self.direction.scale(t)
+    }
+
+    // Whether the ray clearly passes by a sphere, points behind the start don't count. Spheres
+    // it only grazes aren't missed, so nothing the full intersection would find is lost.
+    pub fn misses_sphere(&self, center: &Vector3D, radius: f64) -> bool {
+        let to_center = center - &self.start;
+        let along = to_center.dot(&self.direction).max(0.);
+        let distance_squared = to_center.squid() - along * along;
+        let radius = radius * (1. + SPHERE_MARGIN) + SPHERE_MARGIN;
+
+        distance_squared > radius * radius
+    }
+
     pub fn trace(&self, scene: &Scene) -> Result<Color> {
-        let shortest_distance = scene
-            .bodies
-            .iter()
-            .filter_map(|shape| {
-                let distance = shape.closest_ray_distance(self);
+        Ok(self.trace_linear(scene)?.linear_to_srgb())
+    }
 
-                distance.and_then(|distance| Some((distance, shape)))
-            })
-            .min_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(Ordering::Greater));
+    // Light along the ray in linear space, for callers that average several rays
+    pub fn trace_linear(&self, scene: &Scene) -> Result<ColorF> {
+        self.trace_depth(scene, scene.max_depth())
+    }
 
-        match shortest_distance {
+    // Follows the ray through the scene, returning light in linear space. Depth counts down with
+    // every bounce, at zero surfaces are shaded without spawning any more rays.
+    fn trace_depth(&self, scene: &Scene, depth: u32) -> Result<ColorF> {
+        match scene.closest_hit(self) {
             Some((distance, shape)) => {
-                let way = Vector3D::from(&self.start)
-                    .for_distance_in_direction(distance, &self.direction)?;
+                let HitRecord {
+                    point: way,
+                    normal,
+                    front_face: entering,
+                    ..
+                } = shape.hit_at(self, distance);
+
+                let color = shape.get_color_at(&way, self.time);
+                let material = shape.material_at(&way, self.time);
 
-                Ok(shape.get_color_at(&way))
+                // Without any lights there's nothing to shade with, keep the flat body color
+                // unless the body gives off its own light
+                let local = if scene.lights().is_empty() && material.is_emissive() {
+                    material.emission.srgb_to_linear()
+                } else if scene.lights().is_empty() {
+                    color.srgb_to_linear()
+                } else {
+                    shading::shade(
+                        scene,
+                        material,
+                        color,
+                        &way,
+                        &shading_normal(shape, &way, &normal, self.time),
+                        self,
+                    )
+                };
+
+                if (material.reflectivity <= 0. && material.transparency <= 0.) || depth == 0 {
+                    return Ok(local);
+                }
+
+                let facing_normal = if entering { normal } else { normal.invert() };
+
+                let reflect = || {
+                    Ray::new(
+                        &(&way + &facing_normal.scale(SURFACE_BIAS)),
+                        &self.direction.reflect(&facing_normal),
+                    )
+                    .with_time(self.time)
+                    .with_seed(self.seed)
+                    .trace_depth(scene, depth - 1)
+                };
+
+                let reflected = if material.reflectivity > 0. {
+                    reflect()?.scale(material.reflectivity)
+                } else {
+                    ColorF::default()
+                };
+
+                let refracted = if material.transparency > 0. {
+                    let eta_ratio = if entering {
+                        1. / material.refractive_index
+                    } else {
+                        material.refractive_index
+                    };
+
+                    let reflectance =
+                        optics::fresnel(-self.direction.dot(&facing_normal), eta_ratio);
+
+                    // Light that can't escape the surface is reflected back inside instead
+                    let transmitted = match self.direction.refract(&facing_normal, eta_ratio) {
+                        Some(direction) if reflectance < 1. => {
+                            Ray::new(&(&way - &facing_normal.scale(SURFACE_BIAS)), &direction)
+                                .with_time(self.time)
+                                .with_seed(self.seed)
+                                .trace_depth(scene, depth - 1)?
+                                .scale(1. - reflectance)
+                                .add(reflect()?.scale(reflectance))
+                        }
+                        _ => reflect()?,
+                    };
+
+                    transmitted.scale(material.transparency)
+                } else {
+                    ColorF::default()
+                };
+
+                Ok(local
+                    .scale((1. - material.reflectivity - material.transparency).max(0.))
+                    .add(reflected)
+                    .add(refracted))
             }
-            None => Ok(scene.background()),
+            None => Ok(scene.background().sample(&self.direction)),
         }
     }
 }
@@ -50,9 +170,29 @@ impl std::fmt::Display for Ray {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{utils::approx_eq, Sphere};
+    use crate::{
+        light::PointLight, material::Material, plane::Plane, roots::Roots, scene::Background,
+        utils::approx_eq, Sphere,
+    };
     use test_case::test_case;
 
+    #[test_case((0.0, 0.0, 0.0), (1.0, 0.0, 0.0), 5.0, (5.0, 0.0, 0.0)   ; "along x from the origin")]
+    #[test_case((1.0, 2.0, 3.0), (0.0, 0.0, -2.0), 1.5, (1.0, 2.0, 1.5) ; "direction is normalized")]
+    #[test_case((1.0, 2.0, 3.0), (0.0, 1.0, 0.0), 0.0, (1.0, 2.0, 3.0)  ; "zero is the start")]
+    fn test_ray_at(
+        start: (f64, f64, f64),
+        direction: (f64, f64, f64),
+        t: f64,
+        expected: (f64, f64, f64),
+    ) {
+        let ray = Ray::new(
+            &Vector3D::new(start.0, start.1, start.2),
+            &Vector3D::new(direction.0, direction.1, direction.2),
+        );
+
+        assert_eq!(ray.at(t), Vector3D::new(expected.0, expected.1, expected.2));
+    }
+
     #[test_case(
         (0.0, 0.0, -5.0), (0.0, 0.0, 1.0), (1, 0, 0), (1, 0, 0)
         ; "ray hits sphere")]
@@ -86,7 +226,9 @@ mod tests {
         let scene = Scene::new(
             &mut dummy_camera,
             Color::new(5, 5, 5),
+            Color::new(0, 0, 0),
             Box::new([Box::new(sphere)]),
+            Box::new([]),
         );
 
         let result_color = ray.trace(&scene).unwrap();
@@ -95,6 +237,369 @@ mod tests {
             Color::new(expected_color.0, expected_color.1, expected_color.2).rgba()
         );
     }
+    #[test_case((0.0, 0.0, -10.0), (255, 255, 255) ; "lit side of sphere is full brightness")]
+    #[test_case((0.0, 0.0, 10.0), (0, 0, 0)        ; "unlit side of sphere is black")]
+    fn test_ray_trace_diffuse_shading(light_position: (f64, f64, f64), expected: (u8, u8, u8)) {
+        let ray = Ray::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 1.0),
+        );
+        let sphere = Sphere::new(Vector3D::new(0.0, 0.0, 0.0), 1.0, Color::new(255, 255, 255));
+
+        let mut dummy_camera = crate::camera::Camera::new(
+            &Vector3D::new(0.0, 0.0, -10.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            800,
+            600,
+        );
+        let scene = Scene::new(
+            &mut dummy_camera,
+            Color::new(5, 5, 5),
+            Color::new(0, 0, 0),
+            Box::new([Box::new(sphere)]),
+            Box::new([Box::new(PointLight::new(
+                Vector3D::new(light_position.0, light_position.1, light_position.2),
+                Color::new(255, 255, 255),
+                100.0,
+            ))]),
+        );
+
+        let result_color = ray.trace(&scene).unwrap();
+        assert_eq!(result_color.rgba(), Color::from(expected).rgba());
+    }
+
+    #[test_case(1.0, (255, 0, 0)   ; "perfect mirror shows the sphere behind the camera")]
+    #[test_case(0.5, (255, 188, 188) ; "half mirror blends its own color with the reflection")]
+    #[test_case(0.0, (255, 255, 255) ; "matte sphere shows only its own color")]
+    fn test_ray_trace_reflection(reflectivity: f64, expected: (u8, u8, u8)) {
+        let ray = Ray::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 1.0),
+        );
+        let mirror = Sphere::new(
+            Vector3D::new(0.0, 0.0, 0.0),
+            1.0,
+            Material {
+                reflectivity,
+                ..Material::new(Color::new(255, 255, 255))
+            },
+        );
+        let behind = Sphere::new(Vector3D::new(0.0, 0.0, -10.0), 1.0, Color::new(255, 0, 0));
+
+        let mut dummy_camera = crate::camera::Camera::new(
+            &Vector3D::new(0.0, 0.0, -10.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            800,
+            600,
+        );
+        let scene = Scene::new(
+            &mut dummy_camera,
+            Color::new(0, 0, 255),
+            Color::new(0, 0, 0),
+            Box::new([Box::new(mirror), Box::new(behind)]),
+            Box::new([]),
+        );
+
+        let result_color = ray.trace(&scene).unwrap();
+        assert_eq!(result_color.rgba(), Color::from(expected).rgba());
+    }
+
+    #[test_case(0, (255, 255, 255) ; "no bounces leaves the mirror's own color")]
+    #[test_case(1, (255, 0, 0)     ; "one bounce reaches the sphere behind")]
+    fn test_ray_trace_respects_max_depth(max_depth: u32, expected: (u8, u8, u8)) {
+        let ray = Ray::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 1.0),
+        );
+        let mirror = Sphere::new(
+            Vector3D::new(0.0, 0.0, 0.0),
+            1.0,
+            Material {
+                reflectivity: 1.0,
+                ..Material::new(Color::new(255, 255, 255))
+            },
+        );
+        let behind = Sphere::new(Vector3D::new(0.0, 0.0, -10.0), 1.0, Color::new(255, 0, 0));
+
+        let mut dummy_camera = crate::camera::Camera::new(
+            &Vector3D::new(0.0, 0.0, -10.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            800,
+            600,
+        );
+        let mut scene = Scene::new(
+            &mut dummy_camera,
+            Color::new(0, 0, 255),
+            Color::new(0, 0, 0),
+            Box::new([Box::new(mirror), Box::new(behind)]),
+            Box::new([]),
+        );
+        scene.set_max_depth(max_depth);
+
+        let result_color = ray.trace(&scene).unwrap();
+        assert_eq!(result_color.rgba(), Color::from(expected).rgba());
+    }
+
+    #[test]
+    fn test_ray_trace_between_mirrors_terminates() {
+        let ray = Ray::new(&Vector3D::new(0.0, 0.0, 0.0), &Vector3D::new(0.0, 0.0, 1.0));
+        let mirror = || Material {
+            reflectivity: 0.9,
+            ..Material::new(Color::new(10, 200, 10))
+        };
+
+        let mut dummy_camera = crate::camera::Camera::new(
+            &Vector3D::new(0.0, 0.0, -10.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            800,
+            600,
+        );
+        let scene = Scene::new(
+            &mut dummy_camera,
+            Color::new(0, 0, 255),
+            Color::new(0, 0, 0),
+            Box::new([
+                Box::new(Plane::new(
+                    Vector3D::new(0.0, 0.0, 5.0),
+                    Vector3D::new(0.0, 0.0, -1.0),
+                    mirror(),
+                )),
+                Box::new(Plane::new(
+                    Vector3D::new(0.0, 0.0, -5.0),
+                    Vector3D::new(0.0, 0.0, 1.0),
+                    mirror(),
+                )),
+            ]),
+            Box::new([]),
+        );
+
+        let result_color = ray.trace(&scene).unwrap();
+        assert_eq!(result_color.rgba(), Color::new(10, 200, 10).rgba());
+    }
+
+    #[test_case(0, (0, 255, 0)  ; "no bounces stops on the plane ahead")]
+    #[test_case(1, (255, 0, 0)  ; "one bounce stops on the plane behind")]
+    #[test_case(64, (0, 255, 0) ; "many bounces still stop")]
+    fn test_ray_trace_between_mirrors_stops_at_max_depth(max_depth: u32, expected: (u8, u8, u8)) {
+        let ray = Ray::new(&Vector3D::new(0.0, 0.0, 0.0), &Vector3D::new(0.0, 0.0, 1.0));
+        let mirror = |color: Color| Material {
+            reflectivity: 1.0,
+            ..Material::new(color)
+        };
+
+        let mut dummy_camera = crate::camera::Camera::new(
+            &Vector3D::new(0.0, 0.0, -10.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            800,
+            600,
+        );
+        let mut scene = Scene::new(
+            &mut dummy_camera,
+            Color::new(0, 0, 255),
+            Color::new(0, 0, 0),
+            Box::new([
+                Box::new(Plane::new(
+                    Vector3D::new(0.0, 0.0, 5.0),
+                    Vector3D::new(0.0, 0.0, -1.0),
+                    mirror(Color::new(0, 255, 0)),
+                )),
+                Box::new(Plane::new(
+                    Vector3D::new(0.0, 0.0, -5.0),
+                    Vector3D::new(0.0, 0.0, 1.0),
+                    mirror(Color::new(255, 0, 0)),
+                )),
+            ]),
+            Box::new([]),
+        );
+        scene.set_max_depth(max_depth);
+
+        // Perfect mirrors add nothing of their own, so the color is that of the plane where
+        // the bounces ran out
+        let color = ray.trace_linear(&scene).unwrap();
+
+        assert!([color.r(), color.g(), color.b()]
+            .iter()
+            .all(|channel| channel.is_finite()));
+        assert_eq!(color.linear_to_srgb().rgba(), Color::from(expected).rgba());
+    }
+
+    #[test_case(1.5, (246, 0, 79) ; "glass bends the ray onto the sphere below the axis")]
+    #[test_case(1.0, (0, 0, 255) ; "matching index passes straight through to the background")]
+    fn test_ray_trace_refraction(refractive_index: f64, expected: (u8, u8, u8)) {
+        let ray = Ray::new(
+            &Vector3D::new(0.0, 0.3, -5.0),
+            &Vector3D::new(0.0, 0.0, 1.0),
+        );
+        let glass = Sphere::new(
+            Vector3D::new(0.0, 0.0, 0.0),
+            1.0,
+            Material {
+                transparency: 1.0,
+                refractive_index,
+                ..Material::new(Color::new(255, 255, 255))
+            },
+        );
+        let below = Sphere::new(Vector3D::new(0.0, -0.3, 3.0), 0.2, Color::new(255, 0, 0));
+
+        let mut dummy_camera = crate::camera::Camera::new(
+            &Vector3D::new(0.0, 0.0, -10.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            800,
+            600,
+        );
+        let scene = Scene::new(
+            &mut dummy_camera,
+            Color::new(0, 0, 255),
+            Color::new(0, 0, 0),
+            Box::new([Box::new(glass), Box::new(below)]),
+            Box::new([]),
+        );
+
+        let result_color = ray.trace(&scene).unwrap();
+        assert_eq!(result_color.rgba(), Color::from(expected).rgba());
+    }
+
+    #[test_case((0.0, 1.0, 0.0), (0, 0, 255)      ; "straight up sees the top color")]
+    #[test_case((0.0, -1.0, 0.0), (255, 255, 255) ; "straight down sees the bottom color")]
+    #[test_case((1.0, 0.0, 0.0), (188, 188, 255)  ; "horizon is halfway between in linear light")]
+    fn test_ray_trace_gradient_background(direction: (f64, f64, f64), expected: (u8, u8, u8)) {
+        let ray = Ray::new(
+            &Vector3D::new(0.0, 0.0, 0.0),
+            &Vector3D::new(direction.0, direction.1, direction.2),
+        );
+
+        let mut dummy_camera = crate::camera::Camera::new(
+            &Vector3D::new(0.0, 0.0, -10.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            800,
+            600,
+        );
+        let scene = Scene::new(
+            &mut dummy_camera,
+            Background::Gradient {
+                top: Color::new(0, 0, 255),
+                bottom: Color::new(255, 255, 255),
+            },
+            Color::new(0, 0, 0),
+            Box::new([]),
+            Box::new([]),
+        );
+
+        let result_color = ray.trace(&scene).unwrap();
+        assert_eq!(result_color.rgba(), Color::from(expected).rgba());
+    }
+
+    #[test_case(0.0, (0, 0, 255) ; "miss sees the environment ahead")]
+    #[test_case(1.0, (255, 0, 0) ; "mirror reflects the environment behind")]
+    fn test_ray_trace_environment(reflectivity: f64, expected: (u8, u8, u8)) {
+        let ray = Ray::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 1.0),
+        );
+
+        let mut dummy_camera = crate::camera::Camera::new(
+            &Vector3D::new(0.0, 0.0, -10.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            800,
+            600,
+        );
+        // Red all around the -z half, blue around the +z half
+        let environment = crate::environment::Environment::new(
+            2,
+            1,
+            vec![ColorF::new(1.0, 0.0, 0.0), ColorF::new(0.0, 0.0, 1.0)],
+        )
+        .unwrap();
+        let mirror = Material {
+            reflectivity,
+            ..Material::new(Color::new(0, 0, 0))
+        };
+        let bodies: Box<[Box<dyn crate::body::Renderable>]> = if reflectivity > 0. {
+            Box::new([Box::new(Sphere::new(
+                Vector3D::new(0.0, 0.0, 0.0),
+                1.0,
+                mirror,
+            ))])
+        } else {
+            Box::new([])
+        };
+        let scene = Scene::new(
+            &mut dummy_camera,
+            environment,
+            Color::new(0, 0, 0),
+            bodies,
+            Box::new([]),
+        );
+
+        let result_color = ray.trace(&scene).unwrap();
+        assert_eq!(result_color.rgba(), Color::from(expected).rgba());
+    }
+
+    // Sphere whose upper half is a different color, so tracing has to sample the hit point
+    struct TwoToneSphere(Sphere);
+
+    impl crate::body::Colored for TwoToneSphere {
+        fn material(&self) -> &Material {
+            self.0.material()
+        }
+    }
+
+    impl crate::body::Volume for TwoToneSphere {
+        fn intersect(&self, ray: &Ray) -> Roots {
+            self.0.intersect(ray)
+        }
+
+        fn get_normal_at(&self, point: &Vector3D, time: f64) -> Vector3D {
+            self.0.get_normal_at(point, time)
+        }
+
+        fn get_color_at(&self, point: &Vector3D, _time: f64) -> Color {
+            if point.y() > 0. {
+                Color::new(255, 0, 0)
+            } else {
+                Color::new(0, 255, 0)
+            }
+        }
+
+        fn bounding_box(&self) -> crate::aabb::Aabb {
+            self.0.bounding_box()
+        }
+    }
+
+    impl crate::body::Renderable for TwoToneSphere {}
+
+    #[test_case(0.5, (255, 0, 0)  ; "upper half is sampled at the hit point")]
+    #[test_case(-0.5, (0, 255, 0) ; "lower half is sampled at the hit point")]
+    #[test_case(2.0, (0, 0, 255)  ; "miss sees the background")]
+    fn test_ray_trace_samples_color_at_hit(height: f64, expected: (u8, u8, u8)) {
+        let ray = Ray::new(
+            &Vector3D::new(0.0, height, -5.0),
+            &Vector3D::new(0.0, 0.0, 1.0),
+        );
+        let sphere = TwoToneSphere(Sphere::new(
+            Vector3D::new(0.0, 0.0, 0.0),
+            1.0,
+            Color::new(255, 255, 255),
+        ));
+
+        let mut dummy_camera = crate::camera::Camera::new(
+            &Vector3D::new(0.0, 0.0, -10.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            800,
+            600,
+        );
+        let scene = Scene::new(
+            &mut dummy_camera,
+            Color::new(0, 0, 255),
+            Color::new(0, 0, 0),
+            Box::new([Box::new(sphere)]),
+            Box::new([]),
+        );
+
+        let result_color = ray.trace(&scene).unwrap();
+        assert_eq!(result_color.rgba(), Color::from(expected).rgba());
+    }
+
     #[test_case(
     (0.0, 0.0, 0.0), (10.0, 0.0, 0.0), (1.0, 0.0, 0.0)
     ; "normalize ray direction")]
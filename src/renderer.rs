@@ -1,13 +1,184 @@
-use color_eyre::eyre::Result;
-use sdl2::render::Canvas;
+use std::{
+    fs, mem,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
 
-use crate::{color::Color, scene::Scene};
+use color_eyre::eyre::{eyre, Result};
+
+use crate::{
+    color::{self, Color, ColorF, ToneMap},
+    exr, png,
+    rng::{splitmix, Rng, XorShift},
+    scene::Scene,
+    shading,
+};
 
 pub type Coordinates2D = (u16, u16);
 
+// Edge length of the square tiles the image is traced in
+pub const DEFAULT_TILE_SIZE: u16 = 32;
+
+// Spends more rays on pixels whose samples disagree, like those on the edge of a body
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptiveSampling {
+    // Samples every pixel gets before its variance is looked at
+    pub base_samples: u32,
+    pub max_samples: u32,
+    // Variance of a pixel's samples, summed over the channels, below which it's left alone.
+    // At zero every pixel takes max_samples like plain uniform sampling
+    pub threshold: f64,
+}
+
+// How samples are placed within a pixel
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SamplePattern {
+    // Anywhere in the pixel, independently of each other
+    Random,
+    // One per cell of a grid over the pixel, jittered within it, so an edge through the pixel is
+    // hard to miss and the samples can't bunch up
+    #[default]
+    Stratified,
+}
+
+impl SamplePattern {
+    // Where the index'th of count samples goes, both coordinates from 0 to 1. Indices past count
+    // fall back to random placement.
+    pub fn offset(self, index: u32, count: u32, rng: &mut impl Rng) -> (f64, f64) {
+        match self {
+            SamplePattern::Stratified if index < count => {
+                let grid = (count as f64).sqrt().ceil() as u32;
+                let cell = (index % grid, index / grid);
+
+                (
+                    (cell.0 as f64 + rng.next_f64()) / grid as f64,
+                    (cell.1 as f64 + rng.next_f64()) / grid as f64,
+                )
+            }
+            _ => (rng.next_f64(), rng.next_f64()),
+        }
+    }
+}
+
+// Settings for render_ao, how many rays probe each point and how far they look
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AmbientOcclusion {
+    pub samples: u32,
+    pub radius: f64,
+}
+
+// Work done for one render, for performance tuning
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RenderStats {
+    // Rays cast from the camera, one per sample
+    pub primary_rays: u64,
+    pub shadow_rays: u64,
+    // Bodies a ray was intersected with after the BVH culled the rest
+    pub intersection_tests: u64,
+    pub elapsed: Duration,
+}
+
+// Told how much of a render is done as a fraction from 0 to 1
+pub type ProgressCallback = dyn Fn(f32) + Send + Sync;
+
 pub struct Renderer {
     canvas_width: u16,
     canvas_height: u16,
+    tile_size: u16,
+    // Without it every pixel is traced once through its center
+    sampling: Option<AdaptiveSampling>,
+    pattern: SamplePattern,
+    // Every pixel's sampling jitter comes from a stream derived from this
+    seed: u64,
+    tone_map: ToneMap,
+    progress: Option<Arc<ProgressCallback>>,
+}
+
+// The frame on screen and the one being rendered behind it. Frames only reach the front once
+// they're fully traced, so a window showing the front never catches one half drawn.
+#[derive(Debug)]
+pub struct DoubleBuffer {
+    front: Vec<Color>,
+    back: Vec<Color>,
+    // How many finished frames have been swapped to the front
+    presented: u64,
+}
+
+impl DoubleBuffer {
+    // Shows black until the first frame is done
+    pub fn new(width: u16, height: u16) -> Self {
+        let pixels = vec![color::BLACK; width as usize * height as usize];
+
+        DoubleBuffer {
+            front: pixels.clone(),
+            back: pixels,
+            presented: 0,
+        }
+    }
+
+    pub fn front(&self) -> &[Color] {
+        &self.front
+    }
+
+    pub fn presented(&self) -> u64 {
+        self.presented
+    }
+
+    // The front as packed RGB bytes, row-major, ready to copy into a texture
+    pub fn front_rgb(&self) -> Vec<u8> {
+        self.front
+            .iter()
+            .flat_map(|pixel| {
+                let [r, g, b, _] = pixel.rgba();
+                [r, g, b]
+            })
+            .collect()
+    }
+
+    // Traces a whole frame into the back buffer and then swaps it to the front. If tracing
+    // fails the front keeps showing the last finished frame.
+    pub fn render(&mut self, renderer: &Renderer, scene: &Scene) -> Result<()> {
+        let frame = renderer.render_to_buffer(scene)?;
+
+        self.swap(frame)
+    }
+
+    fn swap(&mut self, frame: Vec<Color>) -> Result<()> {
+        if frame.len() != self.front.len() {
+            return Err(eyre!(
+                "Frame has {} pixels but the buffers hold {}",
+                frame.len(),
+                self.front.len()
+            ));
+        }
+
+        self.back = frame;
+        mem::swap(&mut self.front, &mut self.back);
+        self.presented += 1;
+
+        Ok(())
+    }
+}
+
+// Rectangle of pixels traced together, tiles along the right and bottom edges may be cut short
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Tile {
+    x: u16,
+    y: u16,
+    width: u16,
+    height: u16,
+}
+
+impl Tile {
+    // Row-major within the tile
+    fn pixels(self) -> impl Iterator<Item = Coordinates2D> {
+        (self.y..self.y + self.height)
+            .flat_map(move |pixel_y| (self.x..self.x + self.width).map(move |x| (x, pixel_y)))
+    }
 }
 
 impl Renderer {
@@ -15,23 +186,1329 @@ impl Renderer {
         Renderer {
             canvas_width,
             canvas_height,
+            tile_size: DEFAULT_TILE_SIZE,
+            sampling: None,
+            pattern: SamplePattern::default(),
+            seed: 0,
+            tone_map: ToneMap::default(),
+            progress: None,
         }
     }
 
-    pub fn render(
+    // Renders with the same seed come out byte for byte identical
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    // Applied to the 8-bit outputs, the linear render and EXR are left as traced
+    pub fn with_tone_map(mut self, tone_map: ToneMap) -> Self {
+        self.tone_map = tone_map;
+        self
+    }
+
+    pub fn with_adaptive_sampling(mut self, sampling: AdaptiveSampling) -> Self {
+        self.sampling = Some(sampling);
+        self
+    }
+
+    // Placement of the base samples, only used with adaptive sampling
+    pub fn with_sample_pattern(mut self, pattern: SamplePattern) -> Self {
+        self.pattern = pattern;
+        self
+    }
+
+    // Called with 0 when a render starts and again after every tile, the fractions never go down
+    // and 1 comes exactly once, when the last tile is done. Cancelled renders stop short of it.
+    pub fn with_progress(mut self, progress: impl Fn(f32) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Arc::new(progress));
+        self
+    }
+
+    pub fn with_tile_size(mut self, tile_size: u16) -> Self {
+        self.tile_size = tile_size.max(1);
+        self
+    }
+
+    pub fn tile_size(&self) -> u16 {
+        self.tile_size
+    }
+
+    // Covers the canvas left to right, top to bottom
+    fn tiles(&self) -> impl Iterator<Item = Tile> {
+        let (width, height, size) = (self.canvas_width, self.canvas_height, self.tile_size);
+
+        (0..height).step_by(size as usize).flat_map(move |y| {
+            (0..width).step_by(size as usize).map(move |x| Tile {
+                x,
+                y,
+                width: size.min(width - x),
+                height: size.min(height - y),
+            })
+        })
+    }
+
+    // Binary P6 PPM, a short text header followed by row-major RGB bytes
+    pub fn write_ppm(&self, scene: &Scene, path: &str) -> Result<()> {
+        let pixels = self.render_to_buffer(scene)?;
+
+        let mut contents =
+            format!("P6\n{} {}\n255\n", self.canvas_width, self.canvas_height).into_bytes();
+        contents.extend(pixels.iter().flat_map(|pixel| {
+            let [r, g, b, _] = pixel.rgba();
+            [r, g, b]
+        }));
+
+        fs::write(path, contents)?;
+
+        Ok(())
+    }
+
+    pub fn write_png(&self, scene: &Scene, path: &str) -> Result<()> {
+        let pixels = self.render_to_buffer(scene)?;
+        let rgba: Vec<u8> = pixels.iter().flat_map(|pixel| pixel.rgba()).collect();
+
+        fs::write(
+            path,
+            png::encode(self.canvas_width as u32, self.canvas_height as u32, &rgba),
+        )?;
+
+        Ok(())
+    }
+
+    // Uncompressed 24-bit BMP. Rows are stored bottom to top in BGR order, each padded out to a
+    // multiple of four bytes.
+    pub fn write_bmp(&self, scene: &Scene, path: &str) -> Result<()> {
+        let pixels = self.render_to_buffer(scene)?;
+        let (width, height) = (self.canvas_width as usize, self.canvas_height as usize);
+
+        let row_length = (width * 3).div_ceil(4) * 4;
+        let image_size = row_length * height;
+        let header_size = 14 + 40;
+
+        let mut contents = Vec::with_capacity(header_size + image_size);
+        contents.extend(b"BM");
+        contents.extend(((header_size + image_size) as u32).to_le_bytes());
+        contents.extend([0; 4]);
+        contents.extend((header_size as u32).to_le_bytes());
+
+        // BITMAPINFOHEADER, a positive height means the rows are bottom-up
+        contents.extend(40u32.to_le_bytes());
+        contents.extend((width as i32).to_le_bytes());
+        contents.extend((height as i32).to_le_bytes());
+        contents.extend(1u16.to_le_bytes());
+        contents.extend(24u16.to_le_bytes());
+        // No compression
+        contents.extend(0u32.to_le_bytes());
+        contents.extend((image_size as u32).to_le_bytes());
+        // About 72 DPI, then no palette
+        contents.extend(2835i32.to_le_bytes());
+        contents.extend(2835i32.to_le_bytes());
+        contents.extend([0; 8]);
+
+        for row in pixels.chunks(width.max(1)).rev() {
+            let start = contents.len();
+
+            contents.extend(row.iter().flat_map(|pixel| {
+                let [r, g, b, _] = pixel.rgba();
+                [b, g, r]
+            }));
+            contents.resize(start + row_length, 0);
+        }
+
+        fs::write(path, contents)?;
+
+        Ok(())
+    }
+
+    // OpenEXR of the linear render, light brighter than white isn't clamped
+    pub fn write_exr(&self, scene: &Scene, path: &str) -> Result<()> {
+        let pixels = self.render_linear(scene)?;
+        let rgb: Vec<f32> = pixels
+            .iter()
+            .flat_map(|pixel| [pixel.r() as f32, pixel.g() as f32, pixel.b() as f32])
+            .collect();
+
+        fs::write(
+            path,
+            exr::encode(self.canvas_width as u32, self.canvas_height as u32, &rgb),
+        )?;
+
+        Ok(())
+    }
+
+    // Traces every pixel into a row-major buffer without touching SDL
+    pub fn render_to_buffer(&self, scene: &Scene) -> Result<Vec<Color>> {
+        self.render_tone_mapped(scene, &AtomicBool::new(false))
+            .map(|(pixels, _)| pixels)
+    }
+
+    // Stops handing out tiles once the flag is set, so a preview can be dropped when the camera
+    // moves. Returns the frame so far, with tiles that weren't traced left black, and whether
+    // every tile was traced.
+    pub fn render_cancellable(
+        &self,
+        scene: &Scene,
+        cancel: Arc<AtomicBool>,
+    ) -> Result<(Vec<Color>, bool)> {
+        self.render_tone_mapped(scene, &cancel)
+    }
+
+    fn render_tone_mapped(&self, scene: &Scene, cancel: &AtomicBool) -> Result<(Vec<Color>, bool)> {
+        self.render_tiles(scene, cancel, |x, y| {
+            self.sample_pixel(scene, x, y)
+                .map(|(color, _)| color.tone_mapped(self.tone_map))
+        })
+    }
+
+    // Renders again after bodies changed, only tracing pixels whose ray through the center passes
+    // through one of the scene's dirty regions and copying the rest from the previous frame.
+    // Lit scenes trace everything again since shadows can fall anywhere, reflections of changed
+    // bodies falling outside their regions aren't updated.
+    // Returns the frame and how many pixels were traced.
+    pub fn render_dirty(&self, scene: &Scene, previous: &[Color]) -> Result<(Vec<Color>, usize)> {
+        let pixel_count = self.canvas_width as usize * self.canvas_height as usize;
+
+        let regions = match scene.dirty_regions() {
+            Some(regions) if previous.len() == pixel_count => regions,
+            _ => return Ok((self.render_to_buffer(scene)?, pixel_count)),
+        };
+
+        let pixels = self.render_pixels(scene, |x, y| {
+            let ray = scene.camera().ray_for_pixel(x as i32, y as i32);
+
+            if !regions.iter().any(|region| region.hit(&ray)) {
+                let index = y as usize * self.canvas_width as usize + x as usize;
+                return Ok((previous[index], false));
+            }
+
+            self.sample_pixel(scene, x, y)
+                .map(|(color, _)| (color.tone_mapped(self.tone_map), true))
+        })?;
+
+        let traced = pixels.iter().filter(|(_, traced)| *traced).count();
+
+        Ok((pixels.into_iter().map(|(color, _)| color).collect(), traced))
+    }
+
+    // Light reaching every pixel in linear space, before it's clamped into 8-bit colors
+    pub fn render_linear(&self, scene: &Scene) -> Result<Vec<ColorF>> {
+        self.render_pixels(scene, |x, y| {
+            self.sample_pixel(scene, x, y).map(|(color, _)| color)
+        })
+    }
+
+    // Like render_to_buffer, along with the work it took
+    pub fn render_with_stats(&self, scene: &Scene) -> Result<(Vec<Color>, RenderStats)> {
+        let counters = scene.counters();
+        let was_counting = counters.is_enabled();
+        counters.set_enabled(true);
+        let before = (
+            counters.primary_rays(),
+            counters.shadow_rays(),
+            counters.intersection_tests(),
+        );
+        let started = Instant::now();
+
+        let pixels = self.render_to_buffer(scene);
+        counters.set_enabled(was_counting);
+        let pixels = pixels?;
+
+        let stats = RenderStats {
+            primary_rays: counters.primary_rays() - before.0,
+            shadow_rays: counters.shadow_rays() - before.1,
+            intersection_tests: counters.intersection_tests() - before.2,
+            elapsed: started.elapsed(),
+        };
+
+        Ok((pixels, stats))
+    }
+
+    // Distance to the nearest body through the center of every pixel, infinite where the ray
+    // misses everything
+    pub fn render_depth(&self, scene: &Scene) -> Result<Vec<f64>> {
+        self.render_pixels(scene, |x, y| {
+            let ray = scene.camera().ray_for_pixel(x as i32, y as i32);
+
+            Ok(scene
+                .closest_hit(&ray)
+                .map_or(f64::INFINITY, |(distance, _)| distance))
+        })
+    }
+
+    // Ambient occlusion as grays, white where nothing nearby blocks the sky over the surface and
+    // darker in creases. Rays that miss everything are white.
+    pub fn render_ao(&self, scene: &Scene, occlusion: AmbientOcclusion) -> Result<Vec<Color>> {
+        self.render_pixels(scene, |x, y| {
+            let ray = scene.camera().ray_for_pixel(x as i32, y as i32);
+
+            let Some((distance, body)) = scene.closest_hit(&ray) else {
+                return Ok(color::WHITE);
+            };
+            let hit = body.hit_at(&ray, distance);
+            let normal = if hit.front_face {
+                hit.normal
+            } else {
+                hit.normal.invert()
+            };
+
+            let pixel = ((x as u64) << 32) | y as u64;
+            let mut rng = XorShift::with_seed(self.seed ^ splitmix(pixel));
+            let visibility = shading::ambient_occlusion(
+                scene,
+                &hit.point,
+                &normal,
+                occlusion.samples,
+                occlusion.radius,
+                &mut rng,
+            );
+
+            Ok(Color::from_f64(visibility, visibility, visibility))
+        })
+    }
+
+    // Surface normals as colors for debugging shading, each axis from -1 to 1 maps to a channel
+    // from 0 to 255. Rays that miss show the background.
+    pub fn render_normals(&self, scene: &Scene) -> Result<Vec<Color>> {
+        self.render_pixels(scene, |x, y| {
+            let ray = scene.camera().ray_for_pixel(x as i32, y as i32);
+
+            Ok(match scene.closest_hit(&ray) {
+                Some((distance, body)) => {
+                    let normal = body.hit_at(&ray, distance).normal;
+                    let channel = |axis: f64| ((axis + 1.) / 2. * 255.).round() as u8;
+
+                    Color::new(
+                        channel(normal.x()),
+                        channel(normal.y()),
+                        channel(normal.z()),
+                    )
+                }
+                None => scene.background().sample(&ray.direction).linear_to_srgb(),
+            })
+        })
+    }
+
+    // Fills a row-major buffer with whatever the closure works out for each pixel of the scene
+    fn render_pixels<T, F>(&self, scene: &Scene, pixel: F) -> Result<Vec<T>>
+    where
+        T: Clone + Default + Send,
+        F: Fn(u16, u16) -> Result<T> + Sync,
+    {
+        self.render_tiles(scene, &AtomicBool::new(false), pixel)
+            .map(|(pixels, _)| pixels)
+    }
+
+    // Like render_pixels, along with whether every tile was traced before the flag was set.
+    // Threads take tiles off a shared counter until there are none left, so slow parts of the
+    // image don't hold one up.
+    fn render_tiles<T, F>(
         &self,
-        canvas: &mut Canvas<sdl2::video::Window>,
         scene: &Scene,
-        paint_callback: &dyn Fn(&mut Canvas<sdl2::video::Window>, Coordinates2D, Color),
-    ) -> Result<()> {
-        for pixel_y in 0..self.canvas_height {
-            for pixel_x in 0..self.canvas_width {
-                let pixel_color = scene.trace(pixel_x as i32, pixel_y as i32)?;
+        cancel: &AtomicBool,
+        pixel: F,
+    ) -> Result<(Vec<T>, bool)>
+    where
+        T: Clone + Default + Send,
+        F: Fn(u16, u16) -> Result<T> + Sync,
+    {
+        let width = self.canvas_width as usize;
+        let mut pixels = vec![T::default(); width * self.canvas_height as usize];
+        let report = |fraction: f32| {
+            if let Some(progress) = &self.progress {
+                progress(fraction);
+            }
+        };
+
+        report(0.);
+
+        if pixels.is_empty() {
+            report(1.);
+            return Ok((pixels, true));
+        }
+
+        // Built before the threads start, instead of by whichever gets to it first while the
+        // rest wait
+        scene.camera().precompute_rays();
+
+        let tiles: Vec<Tile> = self.tiles().collect();
+        let next_tile = AtomicUsize::new(0);
+        // Reported while holding the lock so threads finishing together can't go out of order
+        let finished_tiles = Mutex::new(0);
+        let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+
+        let traced = thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads.min(tiles.len()))
+                .map(|_| {
+                    scope.spawn(|| -> Result<Vec<(Tile, Vec<T>)>> {
+                        let mut traced = vec![];
 
-                paint_callback(canvas, (pixel_x, pixel_y), pixel_color);
+                        while !cancel.load(Ordering::Relaxed) {
+                            let Some(tile) = tiles.get(next_tile.fetch_add(1, Ordering::Relaxed))
+                            else {
+                                break;
+                            };
+
+                            let values = tile
+                                .pixels()
+                                .map(|(x, y)| pixel(x, y))
+                                .collect::<Result<_>>()?;
+
+                            traced.push((*tile, values));
+
+                            let mut finished = finished_tiles
+                                .lock()
+                                .map_err(|_| eyre!("Render thread panicked"))?;
+                            *finished += 1;
+                            report(if *finished == tiles.len() {
+                                1.
+                            } else {
+                                *finished as f32 / tiles.len() as f32
+                            });
+                        }
+
+                        Ok(traced)
+                    })
+                })
+                .collect();
+
+            workers
+                .into_iter()
+                .map(|worker| worker.join().map_err(|_| eyre!("Render thread panicked"))?)
+                .collect::<Result<Vec<_>>>()
+        })?;
+
+        let mut traced_tiles = 0;
+        for (tile, values) in traced.into_iter().flatten() {
+            for ((x, y), value) in tile.pixels().zip(values) {
+                pixels[y as usize * width + x as usize] = value;
             }
+            traced_tiles += 1;
         }
 
-        Ok(())
+        Ok((pixels, traced_tiles == tiles.len()))
+    }
+
+    // The pixel's linear color and how many samples it took
+    fn sample_pixel(&self, scene: &Scene, x: u16, y: u16) -> Result<(ColorF, u32)> {
+        let (x, y) = (x as i32, y as i32);
+
+        let Some(sampling) = self.sampling else {
+            // The camera's cached rays are the unseeded ones
+            if self.seed == 0 {
+                return Ok((scene.trace_linear(x, y)?, 1));
+            }
+
+            return Ok((scene.trace_sample(x, y, (0.5, 0.5), self.seed)?, 1));
+        };
+
+        // Seeded by the pixel too, so it comes out the same on whichever thread traces it
+        let pixel = ((x as u32 as u64) << 32) | y as u32 as u64;
+        let mut rng = XorShift::with_seed(self.seed ^ splitmix(pixel));
+        let max_samples = sampling.max_samples.max(sampling.base_samples).max(1);
+
+        let (mut sum, mut sum_of_squares) = (ColorF::default(), ColorF::default());
+        let mut samples = 0;
+
+        while samples < max_samples {
+            if samples >= sampling.base_samples.max(1) && sampling.threshold > 0. {
+                let n = samples as f64;
+                let mean = sum.scale(1. / n);
+                let mean_of_squares = sum_of_squares.scale(1. / n);
+                let variance = [
+                    mean_of_squares.r() - mean.r() * mean.r(),
+                    mean_of_squares.g() - mean.g() * mean.g(),
+                    mean_of_squares.b() - mean.b() * mean.b(),
+                ]
+                .iter()
+                .sum::<f64>();
+
+                if variance <= sampling.threshold {
+                    break;
+                }
+            }
+
+            let offset = self
+                .pattern
+                .offset(samples, sampling.base_samples, &mut rng);
+
+            let sample = scene.trace_sample(x, y, offset, self.seed)?;
+            sum = sum.add(sample);
+            sum_of_squares = sum_of_squares.add(sample.multiply(sample));
+            samples += 1;
+        }
+
+        Ok((sum.scale(1. / samples as f64), samples))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        body::Sphere,
+        camera::Camera,
+        color,
+        light::{Light, PointLight},
+        material::Material,
+        vector::Vector3D,
+    };
+    use test_case::test_case;
+
+    #[test_case(16, 12 ; "landscape")]
+    #[test_case(7, 13  ; "odd sized portrait")]
+    #[test_case(1, 1   ; "single pixel")]
+    fn test_parallel_matches_serial(width: u16, height: u16) {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            width,
+            height,
+        );
+        let scene = Scene::new(
+            &mut camera,
+            color::BLUE,
+            color::GREY,
+            Box::new([
+                Box::new(Sphere::new(
+                    Vector3D::new(0.0, 0.0, 0.0),
+                    1.0,
+                    Material {
+                        reflectivity: 0.3,
+                        ..Material::new(color::RED)
+                    },
+                )),
+                Box::new(Sphere::new(Vector3D::new(1.5, 0.5, 1.0), 0.8, color::GREEN)),
+            ]),
+            Box::new([Box::new(PointLight::new(
+                Vector3D::new(-5.0, 5.0, -5.0),
+                color::WHITE,
+                1.0,
+            ))]),
+        );
+
+        let serial: Vec<[u8; 4]> = (0..height as i32)
+            .flat_map(|y| (0..width as i32).map(move |x| (x, y)))
+            .map(|(x, y)| scene.trace(x, y).unwrap().rgba())
+            .collect();
+
+        let parallel: Vec<[u8; 4]> = Renderer::new(width, height)
+            .with_tile_size(5)
+            .render_to_buffer(&scene)
+            .unwrap()
+            .iter()
+            .map(|pixel| pixel.rgba())
+            .collect();
+
+        assert_eq!(parallel.len(), width as usize * height as usize);
+        assert_eq!(parallel, serial);
+    }
+
+    #[test_case(64, 64, 32 ; "exact multiple of the tile size")]
+    #[test_case(70, 45, 32 ; "ragged right and bottom edges")]
+    #[test_case(5, 3, 2    ; "small tiles")]
+    #[test_case(1, 1, 32   ; "image smaller than a tile")]
+    #[test_case(4, 4, 0    ; "zero tile size is clamped")]
+    fn test_tiles_cover_every_pixel_once(width: u16, height: u16, tile_size: u16) {
+        let renderer = Renderer::new(width, height).with_tile_size(tile_size);
+        let mut covered = vec![0; width as usize * height as usize];
+
+        for tile in renderer.tiles() {
+            assert!(tile.width <= renderer.tile_size() && tile.height <= renderer.tile_size());
+
+            for (x, y) in tile.pixels() {
+                covered[y as usize * width as usize + x as usize] += 1;
+            }
+        }
+
+        assert!(covered.iter().all(|count| *count == 1));
+    }
+
+    fn edge_scene(camera: &mut Camera) -> Scene<'_> {
+        Scene::new(
+            camera,
+            color::BLUE,
+            color::BLACK,
+            Box::new([Box::new(Sphere::new(
+                Vector3D::new(0.0, 0.0, 0.0),
+                1.0,
+                color::RED,
+            ))]),
+            Box::new([]),
+        )
+    }
+
+    #[test]
+    fn test_adaptive_sampling_refines_edges() {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            20,
+            20,
+        );
+        let scene = edge_scene(&mut camera);
+        let renderer = Renderer::new(20, 20).with_adaptive_sampling(AdaptiveSampling {
+            base_samples: 4,
+            max_samples: 64,
+            threshold: 0.001,
+        });
+
+        // The sphere covers about three and a half pixels either side of the center
+        let (interior, interior_samples) = renderer.sample_pixel(&scene, 10, 10).unwrap();
+        let (background, background_samples) = renderer.sample_pixel(&scene, 0, 0).unwrap();
+        let (edge, edge_samples) = renderer.sample_pixel(&scene, 13, 9).unwrap();
+        let (interior, background, edge) = (
+            interior.linear_to_srgb(),
+            background.linear_to_srgb(),
+            edge.linear_to_srgb(),
+        );
+
+        assert_eq!(interior.rgba(), color::RED.rgba());
+        assert_eq!(background.rgba(), color::BLUE.rgba());
+        assert_eq!((interior_samples, background_samples), (4, 4));
+        assert!(edge_samples > interior_samples, "{edge_samples}");
+
+        let [r, _, b, _] = edge.rgba();
+        assert!(
+            r > 0 && b > 0,
+            "{edge:?} isn't a blend of the sphere and background"
+        );
+    }
+
+    #[test]
+    fn test_seed_makes_renders_reproducible() {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            20,
+            20,
+        );
+        let scene = edge_scene(&mut camera);
+        let render = |seed: u64| {
+            Renderer::new(20, 20)
+                .with_tile_size(7)
+                .with_adaptive_sampling(AdaptiveSampling {
+                    base_samples: 4,
+                    max_samples: 4,
+                    threshold: 0.0,
+                })
+                .with_seed(seed)
+                .render_to_buffer(&scene)
+                .unwrap()
+                .iter()
+                .map(|pixel| pixel.rgba())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(render(42), render(42));
+        // Only the jitter along the sphere's edge depends on the seed
+        assert_ne!(render(42), render(43));
+    }
+
+    #[test]
+    fn test_zero_threshold_samples_uniformly() {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            20,
+            20,
+        );
+        let scene = edge_scene(&mut camera);
+        let renderer = Renderer::new(20, 20).with_adaptive_sampling(AdaptiveSampling {
+            base_samples: 4,
+            max_samples: 16,
+            threshold: 0.0,
+        });
+
+        for (x, y) in [(10, 10), (0, 0), (13, 9)] {
+            assert_eq!(renderer.sample_pixel(&scene, x, y).unwrap().1, 16);
+        }
+    }
+
+    #[test_case(600, 600 ; "square")]
+    #[test_case(80, 60   ; "landscape")]
+    fn test_render_to_buffer(width: u16, height: u16) {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            width,
+            height,
+        );
+        let scene = Scene::new(
+            &mut camera,
+            color::BLUE,
+            color::BLACK,
+            Box::new([Box::new(Sphere::new(
+                Vector3D::new(0.0, 0.0, 0.0),
+                1.0,
+                color::RED,
+            ))]),
+            Box::new([]),
+        );
+
+        let pixels = Renderer::new(width, height)
+            .render_to_buffer(&scene)
+            .unwrap();
+
+        let (center_x, center_y) = (width / 2, height / 2);
+        let center = pixels[center_y as usize * width as usize + center_x as usize];
+
+        assert_eq!(pixels.len(), width as usize * height as usize);
+        assert_eq!(
+            center.rgba(),
+            scene
+                .trace(center_x as i32, center_y as i32)
+                .unwrap()
+                .rgba()
+        );
+        assert_eq!(center.rgba(), color::RED.rgba());
+        assert_eq!(pixels[0].rgba(), color::BLUE.rgba());
+    }
+
+    #[test]
+    fn test_double_buffer_only_presents_whole_frames() {
+        let (width, height) = (8, 6);
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            width,
+            height,
+        );
+        let scene = Scene::new(
+            &mut camera,
+            color::BLUE,
+            color::BLACK,
+            Box::new([]),
+            Box::new([]),
+        );
+        let renderer = Renderer::new(width, height);
+        let mut buffers = DoubleBuffer::new(width, height);
+
+        assert_eq!(buffers.presented(), 0);
+        assert!(buffers
+            .front()
+            .iter()
+            .all(|pixel| pixel.rgba() == color::BLACK.rgba()));
+
+        buffers.render(&renderer, &scene).unwrap();
+
+        assert_eq!(buffers.presented(), 1);
+        assert!(buffers
+            .front()
+            .iter()
+            .all(|pixel| pixel.rgba() == color::BLUE.rgba()));
+        // The old front is now behind, ready to be drawn over
+        assert!(buffers
+            .back
+            .iter()
+            .all(|pixel| pixel.rgba() == color::BLACK.rgba()));
+
+        // Half a frame never makes it to the front
+        let partial = vec![color::RED; buffers.front().len() / 2];
+        assert!(buffers.swap(partial).is_err());
+        assert_eq!(buffers.presented(), 1);
+        assert!(buffers
+            .front()
+            .iter()
+            .all(|pixel| pixel.rgba() == color::BLUE.rgba()));
+
+        buffers.swap(vec![color::RED; 48]).unwrap();
+
+        assert_eq!(buffers.presented(), 2);
+        assert_eq!(buffers.front_rgb()[..6], [255, 0, 0, 255, 0, 0]);
+        assert_eq!(buffers.front_rgb().len(), 48 * 3);
+    }
+
+    #[test]
+    fn test_render_dirty_only_traces_changed_pixels() {
+        let (width, height) = (60, 60);
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            width,
+            height,
+        );
+        let mut scene = Scene::new(
+            &mut camera,
+            color::BLUE,
+            color::BLACK,
+            Box::new([
+                Box::new(Sphere::new(Vector3D::new(0.0, 0.0, 0.0), 1.0, color::RED)),
+                Box::new(Sphere::new(Vector3D::new(1.5, 1.5, 0.0), 0.2, color::GREEN)),
+            ]),
+            // Unlit, lights would have any move trace the whole frame again
+            Box::new([]),
+        );
+        let renderer = Renderer::new(width, height);
+
+        // Nothing to reuse for the first frame
+        let (previous, traced) = renderer.render_dirty(&scene, &[]).unwrap();
+        assert_eq!(traced, 3600);
+        scene.clear_dirty();
+
+        scene.replace_body(
+            1,
+            Box::new(Sphere::new(Vector3D::new(1.5, 1.2, 0.0), 0.2, color::GREEN)),
+        );
+        let (pixels, traced) = renderer.render_dirty(&scene, &previous).unwrap();
+        let full = renderer.render_to_buffer(&scene).unwrap();
+
+        assert!(traced > 0 && traced * 20 < 3600, "{traced}");
+        assert!(pixels
+            .iter()
+            .zip(&full)
+            .all(|(pixel, expected)| pixel.rgba() == expected.rgba()));
+        assert!(pixels
+            .iter()
+            .zip(&previous)
+            .any(|(pixel, before)| pixel.rgba() != before.rgba()));
+
+        // With nothing changed everything is reused
+        scene.clear_dirty();
+        assert_eq!(renderer.render_dirty(&scene, &pixels).unwrap().1, 0);
+    }
+
+    #[test_case(false ; "light added between frames")]
+    #[test_case(true  ; "body moved in a lit scene")]
+    fn test_render_dirty_retraces_lighting_changes(lit: bool) {
+        let (width, height) = (30, 30);
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            width,
+            height,
+        );
+        let light = || -> Box<dyn Light> {
+            Box::new(PointLight::new(
+                Vector3D::new(0.0, 0.0, -10.0),
+                color::WHITE,
+                100.0,
+            ))
+        };
+        let mut scene = Scene::new(
+            &mut camera,
+            color::BLUE,
+            color::BLACK,
+            Box::new([
+                Box::new(Sphere::new(Vector3D::new(0.0, 0.0, 0.0), 1.0, color::RED)),
+                Box::new(Sphere::new(Vector3D::new(1.5, 1.5, 0.0), 0.2, color::GREEN)),
+            ]),
+            Box::new([]),
+        );
+        if lit {
+            scene.add_light(light());
+        }
+        let renderer = Renderer::new(width, height);
+
+        let (previous, _) = renderer.render_dirty(&scene, &[]).unwrap();
+        scene.clear_dirty();
+
+        if lit {
+            // The moved sphere's shadow could land on anything
+            scene.replace_body(
+                1,
+                Box::new(Sphere::new(
+                    Vector3D::new(0.5, 0.5, -1.5),
+                    0.2,
+                    color::GREEN,
+                )),
+            );
+        } else {
+            scene.add_light(light());
+        }
+        let (pixels, traced) = renderer.render_dirty(&scene, &previous).unwrap();
+        let full = renderer.render_to_buffer(&scene).unwrap();
+
+        assert_eq!(traced, 900);
+        assert!(pixels
+            .iter()
+            .zip(&full)
+            .all(|(pixel, expected)| pixel.rgba() == expected.rgba()));
+        assert!(pixels
+            .iter()
+            .zip(&previous)
+            .any(|(pixel, before)| pixel.rgba() != before.rgba()));
+    }
+
+    #[test_case(SamplePattern::Stratified, true ; "stratified fills every cell")]
+    #[test_case(SamplePattern::Random, false     ; "random clumps")]
+    fn test_sample_pattern_spread(pattern: SamplePattern, spread: bool) {
+        let mut rng = XorShift::with_seed(7);
+        let mut cells = [0; 16];
+
+        for index in 0..16 {
+            let (x, y) = pattern.offset(index, 16, &mut rng);
+
+            assert!((0.0..1.0).contains(&x) && (0.0..1.0).contains(&y));
+            cells[(y * 4.) as usize * 4 + (x * 4.) as usize] += 1;
+        }
+
+        assert_eq!(cells.iter().all(|count| *count == 1), spread, "{cells:?}");
+    }
+
+    #[test_case(40, 30, 8 ; "many tiles")]
+    #[test_case(5, 5, 32  ; "single tile")]
+    #[test_case(0, 0, 32  ; "empty image")]
+    fn test_progress_callback(width: u16, height: u16, tile_size: u16) {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            width.max(1),
+            height.max(1),
+        );
+        let scene = edge_scene(&mut camera);
+        let calls = Arc::new(Mutex::new(vec![]));
+        let recorded = Arc::clone(&calls);
+        let renderer = Renderer::new(width, height)
+            .with_tile_size(tile_size)
+            .with_progress(move |fraction| recorded.lock().unwrap().push(fraction));
+
+        renderer.render_to_buffer(&scene).unwrap();
+
+        let calls = calls.lock().unwrap();
+        let tiles = renderer.tiles().count();
+        assert_eq!(calls.len(), tiles.max(1) + 1);
+        assert_eq!(calls.first(), Some(&0.0));
+        assert_eq!(calls.last(), Some(&1.0));
+        assert_eq!(calls.iter().filter(|fraction| **fraction == 1.0).count(), 1);
+        assert!(calls.windows(2).all(|pair| pair[0] <= pair[1]), "{calls:?}");
+    }
+
+    #[test_case(None, 1 ; "one ray per pixel")]
+    #[test_case(Some(AdaptiveSampling { base_samples: 4, max_samples: 4, threshold: 0.0 }), 4 ; "one ray per sample")]
+    fn test_render_stats(sampling: Option<AdaptiveSampling>, rays_per_pixel: u64) {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            12,
+            9,
+        );
+        let mut scene = edge_scene(&mut camera);
+        scene.add_light(Box::new(PointLight::new(
+            Vector3D::new(-5.0, 5.0, -5.0),
+            color::WHITE,
+            100.0,
+        )));
+
+        let mut renderer = Renderer::new(12, 9).with_tile_size(4);
+        if let Some(sampling) = sampling {
+            renderer = renderer.with_adaptive_sampling(sampling);
+        }
+        let (pixels, stats) = renderer.render_with_stats(&scene).unwrap();
+
+        assert_eq!(pixels.len(), 12 * 9);
+        assert_eq!(stats.primary_rays, 12 * 9 * rays_per_pixel);
+        // Every ray that hits the sphere casts a shadow ray towards the light
+        assert!(stats.shadow_rays > 0 && stats.shadow_rays < stats.primary_rays);
+        assert!(stats.intersection_tests > 0);
+
+        // Counting is only on while the stats are gathered
+        assert!(!scene.counters().is_enabled());
+        renderer.render_to_buffer(&scene).unwrap();
+        assert_eq!(scene.counters().primary_rays(), stats.primary_rays);
+    }
+
+    #[test]
+    fn test_cancelled_before_rendering() {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            40,
+            30,
+        );
+        let scene = edge_scene(&mut camera);
+        scene.counters().set_enabled(true);
+
+        let renderer = Renderer::new(40, 30);
+
+        let (pixels, complete) = renderer
+            .render_cancellable(&scene, Arc::new(AtomicBool::new(true)))
+            .unwrap();
+        assert!(!complete);
+        assert_eq!(pixels.len(), 40 * 30);
+        assert!(pixels
+            .iter()
+            .all(|pixel| pixel.rgba() == color::BLACK.rgba()));
+        assert_eq!(scene.counters().primary_rays(), 0);
+
+        let (pixels, complete) = renderer
+            .render_cancellable(&scene, Arc::new(AtomicBool::new(false)))
+            .unwrap();
+        assert!(complete);
+        assert_eq!(pixels.len(), 40 * 30);
+    }
+
+    #[test]
+    fn test_cancelling_leaves_later_tiles_untraced() {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            128,
+            128,
+        );
+        let scene = edge_scene(&mut camera);
+        let renderer = Renderer::new(128, 128).with_tile_size(8);
+        let cancel = AtomicBool::new(false);
+
+        // Every thread finishes the tile it's on, but none start another
+        let (pixels, complete) = renderer
+            .render_tiles(&scene, &cancel, |_, _| {
+                cancel.store(true, Ordering::Relaxed);
+                Ok(1)
+            })
+            .unwrap();
+        let traced = pixels.iter().filter(|pixel| **pixel == 1).count();
+
+        assert!(!complete);
+        assert!(traced > 0 && traced < pixels.len(), "{traced}");
+        assert_eq!(traced % 64, 0);
+    }
+
+    #[test]
+    fn test_cancelling_after_the_last_tile_is_complete() {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            16,
+            16,
+        );
+        let scene = edge_scene(&mut camera);
+        let renderer = Renderer::new(16, 16).with_tile_size(8);
+        let cancel = AtomicBool::new(false);
+        let remaining = AtomicUsize::new(16 * 16);
+
+        // Set by whichever thread traces the last pixel, after every tile was handed out
+        let (pixels, complete) = renderer
+            .render_tiles(&scene, &cancel, |_, _| {
+                if remaining.fetch_sub(1, Ordering::Relaxed) == 1 {
+                    cancel.store(true, Ordering::Relaxed);
+                }
+                Ok(1)
+            })
+            .unwrap();
+
+        assert!(complete);
+        assert!(pixels.iter().all(|pixel| *pixel == 1));
+    }
+
+    fn depth_with_sphere_at(z: f64) -> Vec<f64> {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            30,
+            20,
+        );
+        let scene = Scene::new(
+            &mut camera,
+            color::BLUE,
+            color::BLACK,
+            Box::new([Box::new(Sphere::new(
+                Vector3D::new(0.0, 0.0, z),
+                1.0,
+                color::RED,
+            ))]),
+            Box::new([]),
+        );
+
+        Renderer::new(30, 20)
+            .with_tile_size(7)
+            .render_depth(&scene)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_render_depth() {
+        let near = depth_with_sphere_at(0.0);
+        let far = depth_with_sphere_at(3.0);
+        let center = 10 * 30 + 15;
+
+        assert_eq!(near.len(), 30 * 20);
+        // The pixel is half a pixel off the middle, so a touch past the front of the sphere
+        assert!((near[center] - 4.0).abs() < 0.05, "{}", near[center]);
+        assert!(near[center] < far[center]);
+        assert_eq!((near[0], far[0]), (f64::INFINITY, f64::INFINITY));
+    }
+
+    #[test]
+    fn test_render_normals() {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            40,
+            40,
+        );
+        let scene = edge_scene(&mut camera);
+        let normals = Renderer::new(40, 40).render_normals(&scene).unwrap();
+
+        // The camera's right is -X, so the side of the sphere facing +X is on the left
+        let [r, g, b, _] = normals[20 * 40 + 14].rgba();
+        assert!(r > g && r > b, "{:?}", (r, g, b));
+
+        // Straight at the camera is -Z
+        let [r, g, b, _] = normals[20 * 40 + 20].rgba();
+        assert!(b < 10 && (r as i32 - 128).abs() < 10 && (g as i32 - 128).abs() < 10);
+
+        assert_eq!(normals[0].rgba(), color::BLUE.rgba());
+    }
+
+    #[test_case(2, 2 ; "tiny square image")]
+    fn test_write_ppm(width: u16, height: u16) {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            width,
+            height,
+        );
+        camera.set_fov(120);
+        let scene = Scene::new(
+            &mut camera,
+            color::BLUE,
+            color::BLACK,
+            Box::new([Box::new(Sphere::new(
+                Vector3D::new(-4.3, 4.3, 0.0),
+                1.0,
+                color::RED,
+            ))]),
+            Box::new([]),
+        );
+
+        let path = std::env::temp_dir().join("ray-tracer-write-ppm.ppm");
+        let renderer = Renderer::new(width, height);
+        renderer.write_ppm(&scene, path.to_str().unwrap()).unwrap();
+
+        let contents = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let header = b"P6\n2 2\n255\n";
+        let (red, blue) = ([255, 0, 0], [0, 0, 255]);
+
+        assert_eq!(&contents[..header.len()], header);
+        assert_eq!(
+            &contents[header.len()..],
+            [blue, red, blue, blue].concat().as_slice()
+        );
+    }
+
+    #[test_case(40, 30 ; "landscape image")]
+    fn test_write_png(width: u16, height: u16) {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            width,
+            height,
+        );
+        let scene = Scene::new(
+            &mut camera,
+            color::BLUE,
+            color::BLACK,
+            Box::new([Box::new(Sphere::new(
+                Vector3D::new(0.0, 0.0, 0.0),
+                1.0,
+                color::RED,
+            ))]),
+            Box::new([]),
+        );
+
+        let path = std::env::temp_dir().join("ray-tracer-write-png.png");
+        Renderer::new(width, height)
+            .write_png(&scene, path.to_str().unwrap())
+            .unwrap();
+
+        let contents = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let (decoded_width, decoded_height, rgba) = png::decode(&contents).unwrap();
+        let center = (height as usize / 2 * width as usize + width as usize / 2) * 4;
+
+        assert_eq!(
+            (decoded_width, decoded_height),
+            (width as u32, height as u32)
+        );
+        assert_eq!(&rgba[center..center + 4], &color::RED.rgba());
+        assert_eq!(&rgba[..4], &color::BLUE.rgba());
+    }
+
+    #[test]
+    fn test_write_exr_keeps_bright_light() {
+        let (width, height) = (20, 20);
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            width,
+            height,
+        );
+        let glowing = Material {
+            emission: color::WHITE,
+            ..Material::new(color::WHITE)
+        };
+        let scene = Scene::new(
+            &mut camera,
+            color::BLUE,
+            color::BLACK,
+            Box::new([Box::new(Sphere::new(
+                Vector3D::new(0.0, 0.0, 0.0),
+                1.0,
+                glowing,
+            ))]),
+            Box::new([Box::new(PointLight::new(
+                Vector3D::new(0.0, 0.0, -5.0),
+                color::WHITE,
+                100.0,
+            ))]),
+        );
+
+        let path = std::env::temp_dir().join("ray-tracer-write-exr.exr");
+        Renderer::new(width, height)
+            .write_exr(&scene, path.to_str().unwrap())
+            .unwrap();
+
+        let contents = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let (decoded_width, decoded_height, rgb) = exr::tests::decode(&contents);
+        let center = (height as usize / 2 * width as usize + width as usize / 2) * 3;
+
+        assert_eq!(
+            (decoded_width, decoded_height),
+            (width as u32, height as u32)
+        );
+        assert!(rgb[center..center + 3].iter().all(|&channel| channel > 1.0));
+        assert_eq!(&rgb[..3], &[0.0, 0.0, 1.0]);
+    }
+
+    #[test_case(2, 3 ; "rows need padding")]
+    fn test_write_bmp(width: u16, height: u16) {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            width,
+            height,
+        );
+        camera.set_fov(120);
+        let scene = Scene::new(
+            &mut camera,
+            color::BLUE,
+            color::BLACK,
+            Box::new([Box::new(Sphere::new(
+                Vector3D::new(-2.9, 5.8, 0.0),
+                1.0,
+                color::RED,
+            ))]),
+            Box::new([]),
+        );
+
+        let path = std::env::temp_dir().join("ray-tracer-write-bmp.bmp");
+        let renderer = Renderer::new(width, height);
+        renderer.write_bmp(&scene, path.to_str().unwrap()).unwrap();
+
+        let contents = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let read_u32 =
+            |offset: usize| u32::from_le_bytes(contents[offset..offset + 4].try_into().unwrap());
+        let read_u16 =
+            |offset: usize| u16::from_le_bytes(contents[offset..offset + 2].try_into().unwrap());
+
+        // Two pixels take six bytes, padded to eight
+        assert_eq!(&contents[..2], b"BM");
+        assert_eq!(read_u32(2) as usize, contents.len());
+        assert_eq!(read_u32(2), 54 + 8 * 3);
+        assert_eq!(read_u32(10), 54);
+        assert_eq!(read_u32(14), 40);
+        assert_eq!((read_u32(18), read_u32(22)), (2, 3));
+        assert_eq!((read_u16(26), read_u16(28)), (1, 24));
+        assert_eq!((read_u32(30), read_u32(34)), (0, 8 * 3));
+
+        let (red, blue, padding) = (&[0, 0, 255][..], &[255, 0, 0][..], &[0, 0][..]);
+        let rows: Vec<&[u8]> = contents[54..].chunks(8).collect();
+
+        // The top row comes last
+        assert_eq!(rows[2], [blue, red, padding].concat().as_slice());
+        assert_eq!(rows[1], [blue, blue, padding].concat().as_slice());
+        assert_eq!(rows[0], [blue, blue, padding].concat().as_slice());
+    }
+
+    #[test_case(ToneMap::Clamp, true     ; "clamping burns out the center")]
+    #[test_case(ToneMap::Reinhard, false ; "reinhard keeps the center below white")]
+    fn test_tone_map_bright_render(tone_map: ToneMap, burnt_out: bool) {
+        let (width, height) = (20, 20);
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            width,
+            height,
+        );
+        let glowing = Material {
+            emission: color::WHITE,
+            ..Material::new(color::WHITE)
+        };
+        let scene = Scene::new(
+            &mut camera,
+            color::BLUE,
+            color::BLACK,
+            Box::new([Box::new(Sphere::new(
+                Vector3D::new(0.0, 0.0, 0.0),
+                1.0,
+                glowing,
+            ))]),
+            Box::new([Box::new(PointLight::new(
+                Vector3D::new(0.0, 0.0, -5.0),
+                color::WHITE,
+                100.0,
+            ))]),
+        );
+
+        let pixels = Renderer::new(width, height)
+            .with_tone_map(tone_map)
+            .render_to_buffer(&scene)
+            .unwrap();
+        let [r, _, _, _] = pixels[height as usize / 2 * width as usize + width as usize / 2].rgba();
+
+        assert_eq!(r == 0xff, burnt_out, "{r}");
+    }
+
+    #[test]
+    fn test_render_ao() {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            60,
+            60,
+        );
+        let scene = Scene::new(
+            &mut camera,
+            color::BLACK,
+            color::BLACK,
+            Box::new([
+                Box::new(Sphere::new(
+                    Vector3D::new(-1.0, 0.0, 0.0),
+                    1.0,
+                    color::WHITE,
+                )),
+                Box::new(Sphere::new(Vector3D::new(1.0, 0.0, 0.0), 1.0, color::WHITE)),
+            ]),
+            Box::new([]),
+        );
+
+        let ao = Renderer::new(60, 60)
+            .render_ao(
+                &scene,
+                AmbientOcclusion {
+                    samples: 64,
+                    radius: 1.0,
+                },
+            )
+            .unwrap();
+        let gray = |x: usize, y: usize| ao[y * 60 + x].rgba()[0];
+
+        // Next to where the spheres touch, the middle of one facing the camera and the background
+        assert!(
+            gray(31, 30) < gray(43, 30),
+            "{} {}",
+            gray(31, 30),
+            gray(43, 30)
+        );
+        assert_eq!(gray(43, 30), 0xff);
+        assert_eq!(gray(30, 0), 0xff);
     }
 }
@@ -0,0 +1,104 @@
+// Source of uniformly distributed random bits
+pub trait Rng {
+    fn next_u64(&mut self) -> u64;
+
+    // Uniform in [0, 1), built from the top 53 bits so every value is exactly representable
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+// Marsaglia's xorshift64*, small and fast with good enough quality for sampling
+#[derive(Debug, Clone)]
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    pub fn with_seed(seed: u64) -> Self {
+        // A zero state would only ever produce zeroes, so scramble the seed first
+        XorShift {
+            state: splitmix(seed) | 1,
+        }
+    }
+}
+
+impl Rng for XorShift {
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+}
+
+// Spreads nearby seeds far apart, see https://prng.di.unimi.it/splitmix64.c
+pub fn splitmix(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+
+    z ^ (z >> 31)
+}
+
+// Uniformly distributed point inside the unit disk, by rejection sampling its bounding square
+pub fn in_unit_disk(rng: &mut impl Rng) -> (f64, f64) {
+    loop {
+        let x = 2. * rng.next_f64() - 1.;
+        let y = 2. * rng.next_f64() - 1.;
+
+        if x * x + y * y < 1. {
+            return (x, y);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(0 ; "zero seed")]
+    #[test_case(42 ; "small seed")]
+    #[test_case(u64::MAX ; "largest seed")]
+    fn test_same_seed_same_sequence(seed: u64) {
+        let mut a = XorShift::with_seed(seed);
+        let mut b = XorShift::with_seed(seed);
+
+        let a: Vec<u64> = (0..16).map(|_| a.next_u64()).collect();
+        let b: Vec<u64> = (0..16).map(|_| b.next_u64()).collect();
+
+        assert_eq!(a, b);
+        assert!(a.iter().any(|value| *value != 0));
+    }
+
+    #[test_case(1, 2 ; "adjacent seeds")]
+    #[test_case(0, u64::MAX ; "distant seeds")]
+    fn test_different_seeds_differ(first: u64, second: u64) {
+        let mut a = XorShift::with_seed(first);
+        let mut b = XorShift::with_seed(second);
+
+        assert_ne!(a.next_u64(), b.next_u64());
+    }
+
+    #[test_case(7 ; "floats stay in the unit interval")]
+    fn test_next_f64_range(seed: u64) {
+        let mut rng = XorShift::with_seed(seed);
+        let samples: Vec<f64> = (0..10_000).map(|_| rng.next_f64()).collect();
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+
+        assert!(samples.iter().all(|sample| (0. ..1.).contains(sample)));
+        assert!((mean - 0.5).abs() < 0.02);
+    }
+
+    #[test_case(11 ; "disk samples stay inside the unit circle")]
+    fn test_in_unit_disk(seed: u64) {
+        let mut rng = XorShift::with_seed(seed);
+
+        for _ in 0..1_000 {
+            let (x, y) = in_unit_disk(&mut rng);
+            assert!(x * x + y * y < 1.);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use std::{
+    ops::{Deref, DerefMut},
+    slice,
+};
+
+// A torus crosses a ray at most four times, which covers every primitive
+const INLINE: usize = 4;
+
+// Distances along a ray where it crosses a surface. They're kept on the stack so tracing doesn't
+// allocate for every intersection test, only combined bodies with more crossings than a
+// primitive can have spill onto the heap.
+#[derive(Debug, Clone)]
+pub enum Roots {
+    Inline { values: [f64; INLINE], len: usize },
+    Spilled(Vec<f64>),
+}
+
+impl Roots {
+    pub fn new() -> Self {
+        Roots::Inline {
+            values: [0.; INLINE],
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, value: f64) {
+        match self {
+            Roots::Inline { values, len } if *len < INLINE => {
+                values[*len] = value;
+                *len += 1;
+            }
+            Roots::Inline { values, len } => {
+                let mut spilled = values[..*len].to_vec();
+                spilled.push(value);
+                *self = Roots::Spilled(spilled);
+            }
+            Roots::Spilled(values) => values.push(value),
+        }
+    }
+
+    pub fn is_spilled(&self) -> bool {
+        matches!(self, Roots::Spilled(_))
+    }
+}
+
+impl Default for Roots {
+    fn default() -> Self {
+        Roots::new()
+    }
+}
+
+impl Deref for Roots {
+    type Target = [f64];
+
+    fn deref(&self) -> &[f64] {
+        match self {
+            Roots::Inline { values, len } => &values[..*len],
+            Roots::Spilled(values) => values,
+        }
+    }
+}
+
+impl DerefMut for Roots {
+    fn deref_mut(&mut self) -> &mut [f64] {
+        match self {
+            Roots::Inline { values, len } => &mut values[..*len],
+            Roots::Spilled(values) => values,
+        }
+    }
+}
+
+impl<const N: usize> From<[f64; N]> for Roots {
+    fn from(values: [f64; N]) -> Self {
+        values.into_iter().collect()
+    }
+}
+
+impl FromIterator<f64> for Roots {
+    fn from_iter<I: IntoIterator<Item = f64>>(iter: I) -> Self {
+        iter.into_iter().fold(Roots::new(), |mut roots, value| {
+            roots.push(value);
+            roots
+        })
+    }
+}
+
+pub struct IntoIter {
+    roots: Roots,
+    index: usize,
+}
+
+impl Iterator for IntoIter {
+    type Item = f64;
+
+    fn next(&mut self) -> Option<f64> {
+        let value = self.roots.get(self.index).copied();
+        self.index += 1;
+
+        value
+    }
+}
+
+impl IntoIterator for Roots {
+    type Item = f64;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> IntoIter {
+        IntoIter {
+            roots: self,
+            index: 0,
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Roots {
+    type Item = &'a f64;
+    type IntoIter = slice::Iter<'a, f64>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl PartialEq for Roots {
+    fn eq(&self, other: &Roots) -> bool {
+        **self == **other
+    }
+}
+
+impl PartialEq<Vec<f64>> for Roots {
+    fn eq(&self, other: &Vec<f64>) -> bool {
+        **self == **other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(0 ; "empty")]
+    #[test_case(2 ; "a sphere's worth")]
+    #[test_case(4 ; "a torus's worth stays inline")]
+    #[test_case(9 ; "more spills onto the heap")]
+    fn test_roots_hold_what_was_pushed(count: usize) {
+        let values: Vec<f64> = (0..count).map(|index| index as f64 * 1.5).collect();
+        let roots: Roots = values.iter().copied().collect();
+
+        assert_eq!(roots, values);
+        assert_eq!(roots.is_spilled(), count > INLINE);
+        assert_eq!(roots.into_iter().collect::<Vec<_>>(), values);
+    }
+
+    #[test]
+    fn test_roots_sort_in_place() {
+        let mut roots = Roots::from([3., -1., 2.]);
+        roots.sort_by(f64::total_cmp);
+
+        assert_eq!(roots, vec![-1., 2., 3.]);
+    }
+}
@@ -1,7 +1,112 @@
-use color_eyre::eyre::Result;
+use std::{
+    fs,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use color_eyre::eyre::{Result, WrapErr};
 use derivative::Derivative;
 
-use crate::{body::Renderable, camera::Camera, color::Color, vector::Vector3D};
+use crate::{
+    aabb::Aabb,
+    accelerator::{Acceleration, Accelerator},
+    body::{Renderable, Sphere},
+    camera::Camera,
+    color::{Color, ColorF},
+    description::SceneDescription,
+    environment::Environment,
+    error,
+    light::Light,
+    material::Material,
+    ray::{Ray, SURFACE_BIAS},
+    rng::Rng,
+    vector::Vector3D,
+};
+
+// Dim grey fill light so surfaces facing away from every light aren't pitch black
+pub const DEFAULT_AMBIENT: Color = Color::new(0x40, 0x40, 0x40);
+
+// How many times a ray may bounce off mirrors before we stop following it
+pub const DEFAULT_MAX_DEPTH: u32 = 8;
+
+// What rays that miss every body see
+#[derive(Debug, Clone)]
+pub enum Background {
+    Solid(Color),
+    // Sky-like blend from bottom to top by how far up the ray points
+    Gradient { top: Color, bottom: Color },
+    // An image all around the scene, shared since every render thread samples it
+    Environment(Arc<Environment>),
+}
+
+impl Background {
+    pub fn sample(&self, direction: &Vector3D) -> ColorF {
+        match self {
+            Background::Solid(color) => color.srgb_to_linear(),
+            Background::Gradient { top, bottom } => {
+                let t = (direction.unit().y() + 1.) * 0.5;
+
+                bottom
+                    .srgb_to_linear()
+                    .scale(1. - t)
+                    .add(top.srgb_to_linear().scale(t))
+            }
+            Background::Environment(environment) => environment.sample(direction),
+        }
+    }
+}
+
+impl From<Color> for Background {
+    fn from(color: Color) -> Self {
+        Background::Solid(color)
+    }
+}
+
+impl From<Environment> for Background {
+    fn from(environment: Environment) -> Self {
+        Background::Environment(Arc::new(environment))
+    }
+}
+
+// Tallies of the work done tracing, shared by every render thread. Counting is off until
+// enabled, so renders that don't look at the tallies don't have threads fighting over them.
+#[derive(Debug, Default)]
+pub struct RayCounters {
+    enabled: AtomicBool,
+    primary_rays: AtomicU64,
+    shadow_rays: AtomicU64,
+    intersection_tests: AtomicU64,
+}
+
+impl RayCounters {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    fn add(&self, counter: &AtomicU64, count: u64) {
+        if self.is_enabled() {
+            counter.fetch_add(count, Ordering::Relaxed);
+        }
+    }
+
+    pub fn primary_rays(&self) -> u64 {
+        self.primary_rays.load(Ordering::Relaxed)
+    }
+
+    pub fn shadow_rays(&self) -> u64 {
+        self.shadow_rays.load(Ordering::Relaxed)
+    }
+
+    pub fn intersection_tests(&self) -> u64 {
+        self.intersection_tests.load(Ordering::Relaxed)
+    }
+}
 
 #[derive(Derivative)]
 #[derivative(Debug)]
@@ -9,43 +114,627 @@ use crate::{body::Renderable, camera::Camera, color::Color, vector::Vector3D};
 #[allow(clippy::needless_lifetimes)]
 pub struct Scene<'a> {
     camera: &'a mut Camera,
-    background: Color,
+    background: Background,
+    ambient: Color,
     #[derivative(Debug = "ignore")]
-    pub bodies: Vec<Box<dyn Renderable>>,
+    bodies: Vec<Box<dyn Renderable>>,
+    // Kept in step with bodies, rebuilt whenever they change
+    accelerator: Accelerator,
+    // Boxes around where bodies were and now are since the last clear_dirty, None when the whole
+    // frame needs tracing again
+    dirty: Option<Vec<Aabb>>,
+    lights: Vec<Box<dyn Light>>,
+    max_depth: u32,
+    counters: RayCounters,
 }
 
 impl<'a> Scene<'a> {
     pub fn new(
         camera: &'a mut Camera,
-        background: Color,
+        background: impl Into<Background>,
+        ambient: Color,
         bodies: Box<[Box<dyn Renderable>]>,
+        lights: Box<[Box<dyn Light>]>,
     ) -> Self {
+        let bodies: Vec<_> = bodies.into();
+
         Scene {
+            camera,
+            background: background.into(),
+            ambient,
+            accelerator: Accelerator::new(Acceleration::default(), &bodies),
+            bodies,
+            dirty: None,
+            lights: lights.into(),
+            max_depth: DEFAULT_MAX_DEPTH,
+            counters: RayCounters::default(),
+        }
+    }
+
+    // Loads bodies and colors from a JSON scene description. A camera in the file replaces the
+    // given one, lights still have to be added in code.
+    pub fn from_file(path: &str, camera: &'a mut Camera) -> Result<Self> {
+        let text = fs::read_to_string(path).wrap_err_with(|| format!("Couldn't read {path}"))?;
+        let description =
+            SceneDescription::parse(&text).wrap_err_with(|| format!("Invalid scene in {path}"))?;
+
+        if let Some(camera_description) = &description.camera {
+            *camera = camera_description.to_camera();
+        }
+
+        let (background, ambient) = (description.background, description.ambient);
+
+        Ok(Scene::new(
             camera,
             background,
-            bodies: bodies.into(),
+            ambient,
+            description.bodies(),
+            Box::new([]),
+        ))
+    }
+
+    pub fn bodies(&self) -> &[Box<dyn Renderable>] {
+        &self.bodies
+    }
+
+    // Box around every body, None when there are none
+    pub fn bounds(&self) -> Option<Aabb> {
+        self.bodies
+            .iter()
+            .map(|body| body.bounding_box())
+            .reduce(|total, bounds| total.union(&bounds))
+    }
+
+    pub fn add_body(&mut self, body: Box<dyn Renderable>) {
+        self.mark_dirty(body.bounding_box());
+        self.bodies.push(body);
+        self.rebuild_accelerator();
+    }
+
+    pub fn remove_body(&mut self, index: usize) -> Option<Box<dyn Renderable>> {
+        let removed = (index < self.bodies.len()).then(|| self.bodies.remove(index));
+        if let Some(body) = &removed {
+            self.mark_dirty(body.bounding_box());
         }
+        self.rebuild_accelerator();
+
+        removed
     }
 
-    pub fn background(&self) -> Color {
-        self.background
+    // Swaps the body at index for another one, like a moved copy of it. Returns the old body,
+    // or None and leaves the scene alone if there's no body at index.
+    pub fn replace_body(
+        &mut self,
+        index: usize,
+        body: Box<dyn Renderable>,
+    ) -> Option<Box<dyn Renderable>> {
+        if index >= self.bodies.len() {
+            return None;
+        }
+
+        self.mark_dirty(self.bodies[index].bounding_box());
+        self.mark_dirty(body.bounding_box());
+        let replaced = std::mem::replace(&mut self.bodies[index], body);
+        self.rebuild_accelerator();
+
+        Some(replaced)
+    }
+
+    // Hides or shows the body without taking it out of the scene. Returns false if there's no
+    // body at index.
+    pub fn set_enabled(&mut self, index: usize, enabled: bool) -> bool {
+        let Some(body) = self.bodies.get_mut(index) else {
+            return false;
+        };
+
+        if body.is_enabled() != enabled {
+            body.set_enabled(enabled);
+            self.mark_dirty(self.bodies[index].bounding_box());
+        }
+
+        true
     }
 
-    pub fn trace(&self, x: i32, y: i32) -> Result<Color> {
+    // Where bodies changed since the last clear_dirty, None when everything has to be traced
+    // again, like before the first frame or after the camera moves
+    pub fn dirty_regions(&self) -> Option<&[Aabb]> {
+        self.dirty.as_deref()
+    }
+
+    // Call once a frame has been rendered with the changes so far
+    pub fn clear_dirty(&mut self) {
+        self.dirty = Some(vec![]);
+    }
+
+    fn mark_dirty(&mut self, bounds: Aabb) {
+        match &mut self.dirty {
+            // Lit bodies cast shadows outside their own boxes
+            Some(_) if !self.lights.is_empty() => self.dirty = None,
+            Some(regions) if bounds.is_finite() => regions.push(bounds),
+            // Unbounded bodies could show up anywhere
+            _ => self.dirty = None,
+        }
+    }
+
+    pub fn acceleration(&self) -> Acceleration {
+        self.accelerator.acceleration()
+    }
+
+    pub fn set_acceleration(&mut self, acceleration: Acceleration) {
+        self.accelerator = Accelerator::new(acceleration, &self.bodies);
+    }
+
+    fn rebuild_accelerator(&mut self) {
+        self.set_acceleration(self.acceleration());
+    }
+
+    // Nearest body the ray hits and how far along the ray it is
+    pub fn closest_hit(&self, ray: &Ray) -> Option<(f64, &dyn Renderable)> {
+        let (hit, tests) = self.accelerator.hit_counting(&self.bodies, ray);
+        self.counters.add(&self.counters.intersection_tests, tests);
+
+        hit.map(|(distance, index)| (distance, self.bodies[index].as_ref()))
+    }
+
+    // Index of the nearest body under the pixel, for selecting bodies with the mouse
+    pub fn pick(&self, x: i32, y: i32) -> Option<usize> {
+        self.accelerator
+            .hit(&self.bodies, &self.camera.ray_for_pixel(x, y))
+            .map(|(_, index)| index)
+    }
+
+    // Whether a body sits between the point and the target at the given shutter time, for
+    // shadow rays
+    pub fn is_occluded(&self, point: &Vector3D, target: &Vector3D, time: f64) -> bool {
+        let to_target = Vector3D::from(point).to(target);
+
+        self.any_hit(
+            &Ray::new(point, &to_target).with_time(time),
+            to_target.length(),
+        )
+    }
+
+    // Whether anything is hit closer than max_t. Shadow rays don't care which body is in the
+    // way, so this stops at the first one instead of looking for the nearest.
+    pub fn any_hit(&self, ray: &Ray, max_t: f64) -> bool {
+        let (hit, tests) = self.accelerator.any_hit(&self.bodies, ray, max_t);
+        self.counters.add(&self.counters.shadow_rays, 1);
+        self.counters.add(&self.counters.intersection_tests, tests);
+
+        hit
+    }
+
+    pub fn lights(&self) -> &[Box<dyn Light>] {
+        &self.lights
+    }
+
+    pub fn add_light(&mut self, light: Box<dyn Light>) {
+        self.lights.push(light);
+        self.dirty = None;
+    }
+
+    pub fn max_depth(&self) -> u32 {
+        self.max_depth
+    }
+
+    // At zero surfaces are only shaded, nothing is reflected or refracted
+    pub fn set_max_depth(&mut self, max_depth: u32) {
+        self.max_depth = max_depth;
+        self.dirty = None;
+    }
+
+    pub fn background(&self) -> &Background {
+        &self.background
+    }
+
+    pub fn ambient(&self) -> Color {
+        self.ambient
+    }
+
+    pub fn trace(&self, x: i32, y: i32) -> error::Result<Color> {
+        self.counters.add(&self.counters.primary_rays, 1);
         self.camera.trace(self, x, y)
     }
 
+    // Like trace, but keeps the light in linear space without clamping
+    pub fn trace_linear(&self, x: i32, y: i32) -> error::Result<ColorF> {
+        self.counters.add(&self.counters.primary_rays, 1);
+        self.camera.ray_for_pixel(x, y).trace_linear(self)
+    }
+
+    // One sample of a pixel in linear space, see Camera::ray_for_sample for the offset and seed
+    pub fn trace_sample(
+        &self,
+        x: i32,
+        y: i32,
+        offset: (f64, f64),
+        seed: u64,
+    ) -> error::Result<ColorF> {
+        self.counters.add(&self.counters.primary_rays, 1);
+        self.camera
+            .ray_for_sample(x, y, offset, seed)
+            .trace_linear(self)
+    }
+
+    // Monte Carlo estimate of the light coming back along the ray, counting light that bounced
+    // off other surfaces on the way. Diffuse hits pick one random direction to carry on in, so
+    // it takes many samples per pixel to settle. Lights are ignored, only emissive bodies and
+    // the background give off light.
+    pub fn path_trace(&self, ray: &Ray, depth: u32, rng: &mut impl Rng) -> ColorF {
+        let Some((distance, body)) = self.closest_hit(ray) else {
+            return self.background.sample(&ray.direction);
+        };
+
+        let hit = body.hit_at(ray, distance);
+        let material = body.material_at(&hit.point, ray.time);
+        let emitted = material.emission.srgb_to_linear();
+
+        if depth == 0 {
+            return emitted;
+        }
+
+        let albedo = body.get_color_at(&hit.point, ray.time).srgb_to_linear();
+        let normal = if hit.front_face {
+            hit.normal
+        } else {
+            hit.normal.invert()
+        };
+        let origin = &hit.point + &normal.scale(SURFACE_BIAS);
+
+        // Russian roulette, dark surfaces absorb most of what reaches them so paths off them
+        // are cut short more often. The ones that carry on make up for the others.
+        let survival = albedo.r().max(albedo.g()).max(albedo.b()).clamp(0.05, 1.);
+        if rng.next_f64() >= survival {
+            return emitted;
+        }
+
+        if rng.next_f64() < material.reflectivity {
+            let mirrored = Ray::new(&origin, &ray.direction.reflect(&normal)).with_time(ray.time);
+
+            return emitted.add(
+                self.path_trace(&mirrored, depth - 1, rng)
+                    .scale(1. / survival),
+            );
+        }
+
+        // Uniform over the hemisphere the pdf is 1 / 2π, with Lambert's albedo / π that leaves
+        // twice the cosine
+        let direction = Vector3D::random_in_hemisphere(&normal, rng);
+        let cosine = direction.dot(&normal);
+        let bounced = Ray::new(&origin, &direction).with_time(ray.time);
+        let incoming = self.path_trace(&bounced, depth - 1, rng);
+
+        emitted.add(albedo.multiply(incoming).scale(2. * cosine / survival))
+    }
+
+    pub fn counters(&self) -> &RayCounters {
+        &self.counters
+    }
+
+    pub fn camera(&self) -> &Camera {
+        self.camera
+    }
+
     pub fn move_camera(&mut self, new_position: Vector3D) {
         self.camera.move_to(new_position);
+        self.dirty = None;
+    }
+
+    // Focuses the camera on whatever is in the middle of the picture, keeping its aperture.
+    // Returns the new focus distance, or None when nothing is there and the focus is left alone.
+    pub fn autofocus(&mut self) -> Option<f64> {
+        let (distance, _) = self.closest_hit(&self.camera.center_ray())?;
+
+        self.camera
+            .set_depth_of_field(self.camera.aperture(), distance);
+        self.dirty = None;
+
+        Some(distance)
+    }
+
+    pub fn aim_camera(&mut self, position: Vector3D, target: Vector3D) {
+        self.camera.look_at(position, target);
+        self.dirty = None;
+    }
+
+    pub fn set_resolution(&mut self, width: u16, height: u16) {
+        self.camera.set_resolution(width, height);
+        self.dirty = None;
+    }
+}
+
+// Collects bodies and lights one at a time, instead of boxing them all up front for Scene::new
+pub struct SceneBuilder<'a> {
+    camera: &'a mut Camera,
+    background: Background,
+    ambient: Color,
+    acceleration: Acceleration,
+    bodies: Vec<Box<dyn Renderable>>,
+    lights: Vec<Box<dyn Light>>,
+}
+
+impl<'a> SceneBuilder<'a> {
+    pub fn new(camera: &'a mut Camera, background: impl Into<Background>) -> Self {
+        SceneBuilder {
+            camera,
+            background: background.into(),
+            ambient: DEFAULT_AMBIENT,
+            acceleration: Acceleration::default(),
+            bodies: vec![],
+            lights: vec![],
+        }
+    }
+
+    pub fn ambient(mut self, ambient: Color) -> Self {
+        self.ambient = ambient;
+        self
+    }
+
+    pub fn acceleration(mut self, acceleration: Acceleration) -> Self {
+        self.acceleration = acceleration;
+        self
+    }
+
+    pub fn add_sphere(self, center: Vector3D, radius: f64, material: impl Into<Material>) -> Self {
+        self.add_body(Box::new(Sphere::new(center, radius, material)))
+    }
+
+    pub fn add_body(mut self, body: Box<dyn Renderable>) -> Self {
+        self.bodies.push(body);
+        self
+    }
+
+    pub fn add_light(mut self, light: Box<dyn Light>) -> Self {
+        self.lights.push(light);
+        self
+    }
+
+    pub fn build(self) -> Scene<'a> {
+        let mut scene = Scene::new(
+            self.camera,
+            self.background,
+            self.ambient,
+            self.bodies.into_boxed_slice(),
+            self.lights.into_boxed_slice(),
+        );
+
+        if self.acceleration != Acceleration::default() {
+            scene.set_acceleration(self.acceleration);
+        }
+
+        scene
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Vector3D;
+    use crate::{body::Sphere, color, light::PointLight, utils::approx_eq_eps, Vector3D};
     use test_case::test_case;
 
+    #[test_case(1.0, Some(10.0) ; "focuses on the sphere")]
+    #[test_case(20.0, None      ; "miss keeps the focus")]
+    fn test_autofocus(sphere_x: f64, expected: Option<f64>) {
+        let mut camera = crate::camera::Camera::new(
+            &Vector3D::new(0.0, 0.0, -10.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            60,
+            60,
+        );
+        camera.set_depth_of_field(0.5, 3.0);
+        // The sphere's near side is 10 from the camera when it's straight ahead
+        let mut scene = SceneBuilder::new(&mut camera, color::BLACK)
+            .add_sphere(Vector3D::new(sphere_x - 1.0, 0.0, 1.0), 1.0, color::RED)
+            .build();
+
+        let focused = scene.autofocus();
+
+        assert_eq!(focused.is_some(), expected.is_some());
+        assert!(approx_eq_eps(
+            scene.camera().focus_distance(),
+            expected.unwrap_or(3.0),
+            1e-9
+        ));
+        assert_eq!(scene.camera().aperture(), 0.5);
+    }
+
+    #[test_case(Acceleration::Linear ; "linear")]
+    #[test_case(Acceleration::Bvh    ; "bvh")]
+    #[test_case(Acceleration::Grid   ; "grid")]
+    fn test_disabled_body_is_skipped(acceleration: Acceleration) {
+        let mut camera = crate::camera::Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            60,
+            60,
+        );
+        let mut scene = SceneBuilder::new(&mut camera, color::BLUE)
+            .ambient(color::WHITE)
+            .acceleration(acceleration)
+            .add_sphere(Vector3D::new(0.0, 0.0, 0.0), 1.0, color::RED)
+            .build();
+        let ray = Ray::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 1.0),
+        );
+
+        assert!(scene.set_enabled(0, false));
+        assert_eq!(scene.trace(30, 30).unwrap().rgba(), color::BLUE.rgba());
+        assert!(!scene.any_hit(&ray, f64::INFINITY));
+        assert!(!scene.bodies()[0].is_enabled());
+
+        assert!(scene.set_enabled(0, true));
+        assert_eq!(scene.trace(30, 30).unwrap().rgba(), color::RED.rgba());
+        assert!(scene.any_hit(&ray, f64::INFINITY));
+        assert!(!scene.set_enabled(1, false));
+    }
+
+    #[test]
+    fn test_bounds() {
+        let mut camera = crate::camera::Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            60,
+            60,
+        );
+        let mut scene = SceneBuilder::new(&mut camera, color::BLACK).build();
+        assert!(scene.bounds().is_none());
+
+        scene.add_body(Box::new(Sphere::new(
+            Vector3D::new(-5.0, 0.0, 0.0),
+            1.0,
+            color::RED,
+        )));
+        scene.add_body(Box::new(Sphere::new(
+            Vector3D::new(5.0, 0.0, 0.0),
+            1.0,
+            color::RED,
+        )));
+        let bounds = scene.bounds().unwrap();
+
+        assert_eq!(bounds.min(), &Vector3D::new(-6.0, -1.0, -1.0));
+        assert_eq!(bounds.max(), &Vector3D::new(6.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_scene_builder() {
+        let mut camera = crate::camera::Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            60,
+            60,
+        );
+
+        let scene = SceneBuilder::new(&mut camera, color::BLUE)
+            .ambient(color::BLACK)
+            .add_sphere(Vector3D::new(0.0, 0.0, 0.0), 1.0, color::RED)
+            .add_sphere(Vector3D::new(0.0, 0.0, 10.0), 5.0, color::GREEN)
+            .build();
+
+        assert_eq!(scene.bodies().len(), 2);
+        assert!(scene.lights().is_empty());
+        // The small sphere hides the middle of the big one behind it
+        assert_eq!(scene.trace(30, 30).unwrap().rgba(), color::RED.rgba());
+        assert_eq!(scene.trace(30, 15).unwrap().rgba(), color::GREEN.rgba());
+        assert_eq!(scene.trace(0, 0).unwrap().rgba(), color::BLUE.rgba());
+    }
+
+    #[test_case(Acceleration::Bvh  ; "bvh")]
+    #[test_case(Acceleration::Grid ; "grid")]
+    fn test_acceleration_matches_linear(acceleration: Acceleration) {
+        let mut camera = crate::camera::Camera::new(
+            &Vector3D::new(0.0, 0.0, -20.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            40,
+            40,
+        );
+        let mut builder = SceneBuilder::new(&mut camera, color::BLUE).ambient(color::BLACK);
+        for x in -4i32..=4 {
+            for y in -4..=4 {
+                for z in 0..4 {
+                    let shade = ((x + y + z).rem_euclid(4) * 60) as u8;
+                    builder = builder.add_sphere(
+                        Vector3D::new(x as f64 * 1.5, y as f64 * 1.5, z as f64 * 1.5),
+                        0.6,
+                        Color::new(shade, 255 - shade, 0),
+                    );
+                }
+            }
+        }
+        let mut scene = builder.acceleration(Acceleration::Linear).build();
+
+        let render = |scene: &Scene| -> Vec<[u8; 4]> {
+            (0..40)
+                .flat_map(|y| (0..40).map(move |x| (x, y)))
+                .map(|(x, y)| scene.trace(x, y).unwrap().rgba())
+                .collect()
+        };
+
+        let linear = render(&scene);
+        scene.set_acceleration(acceleration);
+
+        assert_eq!(scene.acceleration(), acceleration);
+        assert_eq!(render(&scene), linear);
+    }
+
+    #[test_case(color::WHITE, true ; "emissive sphere lights the floor")]
+    #[test_case(color::BLACK, false ; "dark sphere leaves the floor black")]
+    fn test_path_trace_indirect_light(emission: Color, lit: bool) {
+        let mut camera = crate::camera::Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            60,
+            60,
+        );
+        let glowing = Material {
+            emission,
+            ..Material::new(color::BLACK)
+        };
+        let scene = SceneBuilder::new(&mut camera, color::BLACK)
+            .ambient(color::BLACK)
+            .add_sphere(Vector3D::new(0.0, 0.0, 0.0), 0.5, glowing)
+            .add_body(Box::new(crate::plane::Plane::new(
+                Vector3D::new(0.0, -1.0, 0.0),
+                Vector3D::new(0.0, 1.0, 0.0),
+                color::WHITE,
+            )))
+            .build();
+
+        // Straight down at the floor beside the sphere, the only light it gets is what the sphere
+        // gives off
+        let ray = Ray::new(
+            &Vector3D::new(1.5, 1.0, 0.0),
+            &Vector3D::new(0.0, -1.0, 0.0),
+        );
+        let mut rng = crate::rng::XorShift::with_seed(3);
+        let samples = 512;
+        let total = (0..samples).fold(ColorF::default(), |total, _| {
+            total.add(scene.path_trace(&ray, 4, &mut rng))
+        });
+        let mean = total.scale(1. / samples as f64);
+
+        assert_eq!(mean.r() > 0.01, lit, "{mean:?}");
+        assert!(mean.r() < 1.);
+    }
+
+    #[test_case(10.0, true  ; "blocker in front of the limit")]
+    #[test_case(3.0, false  ; "only body is past the limit")]
+    fn test_scene_any_hit(max_t: f64, expected: bool) {
+        let mut camera = crate::camera::Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            60,
+            60,
+        );
+        let scene = SceneBuilder::new(&mut camera, color::BLUE)
+            .add_sphere(Vector3D::new(0.0, 0.0, 5.0), 1.0, color::RED)
+            .build();
+        let ray = Ray::new(&Vector3D::new(0.0, 0.0, 0.0), &Vector3D::new(0.0, 0.0, 1.0));
+        scene.counters().set_enabled(true);
+
+        assert_eq!(scene.any_hit(&ray, max_t), expected);
+        assert_eq!(scene.counters().shadow_rays(), 1);
+    }
+
+    #[test_case((30, 30), Some(1) ; "center of the sphere")]
+    #[test_case((0, 0), None      ; "corner misses")]
+    fn test_scene_pick(pixel: (i32, i32), expected: Option<usize>) {
+        let mut camera = crate::camera::Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            60,
+            60,
+        );
+
+        // The first sphere sits behind the second, so only the second can be picked
+        let scene = SceneBuilder::new(&mut camera, color::BLUE)
+            .add_sphere(Vector3D::new(0.0, 0.0, 3.0), 1.0, color::GREEN)
+            .add_sphere(Vector3D::new(0.0, 0.0, 0.0), 1.0, color::RED)
+            .build();
+
+        assert_eq!(scene.pick(pixel.0, pixel.1), expected);
+    }
+
     #[test_case((2, 3, 4) ; "Scene returns correct background color")]
     fn test_scene_background(expected_color: (u8, u8, u8)) {
         let mut dummy_camera = crate::camera::Camera::new(
@@ -58,12 +747,176 @@ mod tests {
         let scene = Scene::new(
             &mut dummy_camera,
             Color::new(expected_color.0, expected_color.1, expected_color.2),
+            color::BLACK,
+            vec![].into_boxed_slice(),
             vec![].into_boxed_slice(),
         );
 
         assert_eq!(
-            scene.background().rgba(),
+            scene
+                .background()
+                .sample(&Vector3D::new(0.0, 1.0, 0.0))
+                .linear_to_srgb()
+                .rgba(),
             Color::new(expected_color.0, expected_color.1, expected_color.2).rgba()
         );
     }
+
+    #[test_case((0.0, 0.0, -10.0) ; "light in front of the sphere")]
+    fn test_scene_lit_sphere_is_brighter_than_flat(light_position: (f64, f64, f64)) {
+        let mut dummy_camera = crate::camera::Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            600,
+            600,
+        );
+        let flat = Color::new(100, 100, 100);
+
+        let mut scene = Scene::new(
+            &mut dummy_camera,
+            Color::new(0, 0, 0),
+            color::BLACK,
+            Box::new([Box::new(Sphere::new(
+                Vector3D::new(0.0, 0.0, 0.0),
+                1.0,
+                flat,
+            ))]),
+            Box::new([]),
+        );
+        let unlit = scene.trace(300, 300).unwrap();
+
+        scene.add_light(Box::new(PointLight::new(
+            Vector3D::new(light_position.0, light_position.1, light_position.2),
+            color::WHITE,
+            200.0,
+        )));
+        let lit = scene.trace(300, 300).unwrap();
+
+        assert_eq!(unlit.rgba(), flat.rgba());
+        assert!(lit.luminance() > unlit.luminance());
+    }
+
+    #[test_case((255, 0, 0) ; "added body shows up in front of the background")]
+    fn test_scene_add_and_remove_body(body_color: (u8, u8, u8)) {
+        let mut dummy_camera = crate::camera::Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            600,
+            600,
+        );
+        let background = Color::new(0, 0, 255);
+
+        let mut scene = Scene::new(
+            &mut dummy_camera,
+            background,
+            color::BLACK,
+            Box::new([]),
+            Box::new([]),
+        );
+        let empty = scene.trace(300, 300).unwrap();
+
+        scene.add_body(Box::new(Sphere::new(
+            Vector3D::new(0.0, 0.0, 0.0),
+            1.0,
+            Color::from(body_color),
+        )));
+        let added = scene.trace(300, 300).unwrap();
+
+        let removed = scene.remove_body(0);
+        let reverted = scene.trace(300, 300).unwrap();
+
+        assert_eq!(empty.rgba(), background.rgba());
+        assert_eq!(added.rgba(), Color::from(body_color).rgba());
+        assert!(removed.is_some());
+        assert!(scene.bodies().is_empty());
+        assert_eq!(reverted.rgba(), background.rgba());
+    }
+
+    #[test_case(0 ; "empty scene")]
+    #[test_case(3 ; "past the end")]
+    fn test_scene_remove_missing_body(index: usize) {
+        let mut dummy_camera = crate::camera::Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            600,
+            600,
+        );
+        let mut scene = Scene::new(
+            &mut dummy_camera,
+            color::BLACK,
+            color::BLACK,
+            Box::new([]),
+            Box::new([]),
+        );
+        if index > 0 {
+            scene.add_body(Box::new(Sphere::new(
+                Vector3D::new(0.0, 0.0, 0.0),
+                1.0,
+                color::RED,
+            )));
+        }
+
+        assert!(scene.remove_body(index).is_none());
+        assert_eq!(scene.bodies().len(), (index > 0) as usize);
+    }
+
+    const SCENE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/scene.json");
+
+    #[test]
+    fn test_scene_from_file() {
+        let mut dummy_camera = crate::camera::Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            600,
+            600,
+        );
+        let scene = Scene::from_file(SCENE, &mut dummy_camera).unwrap();
+
+        assert!(matches!(scene.background(), Background::Solid(_)));
+        assert_eq!(
+            scene
+                .background()
+                .sample(&Vector3D::new(0.0, 0.0, 1.0))
+                .linear_to_srgb()
+                .rgba(),
+            [0x1a, 0x2b, 0x3c, 0xff]
+        );
+        assert_eq!(scene.ambient().rgba(), DEFAULT_AMBIENT.rgba());
+        assert_eq!(scene.bodies().len(), 2);
+
+        let colors: Vec<[u8; 4]> = scene
+            .bodies
+            .iter()
+            .map(|body| body.color().rgba())
+            .collect();
+        assert_eq!(colors, [[255, 0, 0, 0xff], [0, 128, 255, 0xff]]);
+
+        let bounds = scene.bodies()[1].bounding_box();
+        assert_eq!(bounds.min(), &Vector3D::new(2.0, -1.5, 3.5));
+        assert_eq!(bounds.max(), &Vector3D::new(3.0, -0.5, 4.5));
+
+        assert_eq!(scene.trace(300, 300).unwrap().rgba(), color::RED.rgba());
+    }
+
+    #[test_case("missing-background", r##"{"bodies": []}"##, "`background`" ; "missing field")]
+    #[test_case("bad-color", r##"{"background": "#12345", "bodies": []}"##, "#12345" ; "unparseable color")]
+    #[test_case("bad-radius", r##"{"background": "#000", "bodies": [{"type": "sphere", "center": {"x": 0, "y": 0, "z": 0}, "radius": "1", "color": "#fff"}]}"##, "`radius`" ; "wrong type points at the field")]
+    fn test_scene_from_file_errors(name: &str, contents: &str, expected: &str) {
+        let path = std::env::temp_dir().join(format!("ray-tracer-{name}.json"));
+        std::fs::write(&path, contents).unwrap();
+
+        let mut dummy_camera = crate::camera::Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            600,
+            600,
+        );
+        let error = Scene::from_file(path.to_str().unwrap(), &mut dummy_camera)
+            .map(|_| ())
+            .unwrap_err();
+
+        std::fs::remove_file(path).unwrap();
+
+        assert!(format!("{error:#}").contains(expected));
+    }
 }
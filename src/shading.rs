@@ -0,0 +1,476 @@
+use crate::{
+    color::{Color, ColorF},
+    material::{Material, SpecularModel},
+    ray::{Ray, SURFACE_BIAS},
+    rng::Rng,
+    scene::Scene,
+    vector::Vector3D,
+};
+
+// Lambert's cosine law, how much light a surface receives given the direction towards the light
+pub fn diffuse(normal: &Vector3D, to_light: &Vector3D) -> f64 {
+    normal.dot(&to_light.unit()).max(0.)
+}
+
+// Phong highlight, strongest when looking along the light's reflection off the surface
+pub fn specular(normal: &Vector3D, to_light: &Vector3D, to_view: &Vector3D, shininess: f64) -> f64 {
+    let to_light = to_light.unit();
+
+    // Lights behind the surface can't produce a highlight on it
+    if normal.dot(&to_light) <= 0. {
+        return 0.;
+    }
+
+    let reflected = to_light.invert().reflect(normal);
+
+    reflected.dot(&to_view.unit()).max(0.).powf(shininess)
+}
+
+// Blinn-Phong highlight, strongest when the normal points halfway between the light and the view
+pub fn blinn_phong(
+    normal: &Vector3D,
+    to_light: &Vector3D,
+    to_view: &Vector3D,
+    shininess: f64,
+) -> f64 {
+    let to_light = to_light.unit();
+
+    if normal.dot(&to_light) <= 0. {
+        return 0.;
+    }
+
+    let halfway = to_light.append(&to_view.unit());
+    if halfway.length() == 0. {
+        return 0.;
+    }
+
+    normal.dot(&halfway.unit()).max(0.).powf(shininess)
+}
+
+// Share of the hemisphere above the point that's open, out to the radius. Nearby geometry blocks
+// some of the random rays, so creases and contact points come out darker than open surfaces.
+pub fn ambient_occlusion(
+    scene: &Scene,
+    point: &Vector3D,
+    normal: &Vector3D,
+    samples: u32,
+    radius: f64,
+    rng: &mut impl Rng,
+) -> f64 {
+    if samples == 0 {
+        return 1.;
+    }
+
+    let origin = point + &normal.scale(SURFACE_BIAS);
+    let blocked = (0..samples)
+        .filter(|_| {
+            let direction = Vector3D::random_in_hemisphere(normal, rng);
+            scene.any_hit(&Ray::new(&origin, &direction), radius)
+        })
+        .count();
+
+    1. - blocked as f64 / samples as f64
+}
+
+// Lighting is accumulated in linear space, converting back to sRGB is up to the caller. The ray
+// is the one that hit the point, shadow rays are cast at its time and seeded by it.
+pub fn shade(
+    scene: &Scene,
+    material: &Material,
+    albedo: Color,
+    point: &Vector3D,
+    normal: &Vector3D,
+    ray: &Ray,
+) -> ColorF {
+    let albedo = albedo.srgb_to_linear();
+    let ambient = albedo.multiply(scene.ambient().srgb_to_linear());
+    let unlit = ambient.add(material.emission.srgb_to_linear());
+    let to_view = ray.direction.invert();
+
+    // Shadow rays start just off the surface so they don't hit it again
+    let shadow_origin = point + &normal.scale(SURFACE_BIAS);
+
+    scene.lights().iter().fold(unlit, |total, light| {
+        let visibility = 1. - light.occlusion(scene, &shadow_origin, ray.time, ray.seed);
+
+        if visibility <= 0. {
+            return total;
+        }
+
+        let to_light = light.direction_from(point);
+        let radiance = light
+            .radiance_at(point)
+            .scale(light.attenuation_at(point) * visibility);
+
+        let diffuse = albedo.multiply(radiance).scale(diffuse(normal, &to_light));
+        let highlight = match material.specular_model {
+            SpecularModel::Phong => specular(normal, &to_light, &to_view, material.shininess),
+            SpecularModel::BlinnPhong => {
+                blinn_phong(normal, &to_light, &to_view, material.shininess)
+            }
+        };
+        let specular = radiance.scale(highlight);
+
+        total.add(diffuse).add(specular)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{body::Sphere, camera::Camera, color, light::PointLight, rng::XorShift};
+    use test_case::test_case;
+
+    // Ray arriving at the origin from the given direction
+    fn viewed_from(to_view: Vector3D) -> Ray {
+        Ray::new(&to_view, &to_view.invert())
+    }
+
+    #[test_case((0.0, 1.0, 0.0), (0.0, 1.0, 0.0), 1.0  ; "surface facing the light is fully lit")]
+    #[test_case((0.0, 1.0, 0.0), (0.0, -1.0, 0.0), 0.0 ; "surface facing away is unlit")]
+    #[test_case((0.0, 1.0, 0.0), (1.0, 0.0, 0.0), 0.0  ; "surface edge on to the light is unlit")]
+    #[test_case((0.0, 1.0, 0.0), (0.0, 1.0, 1.0), 0.5f64.sqrt() ; "light at 45 degrees")]
+    fn test_diffuse(normal: (f64, f64, f64), to_light: (f64, f64, f64), expected: f64) {
+        let intensity = diffuse(
+            &Vector3D::new(normal.0, normal.1, normal.2),
+            &Vector3D::new(to_light.0, to_light.1, to_light.2),
+        );
+
+        assert!((intensity - expected).abs() < 1e-12);
+    }
+
+    #[test_case((0.0, 1.0, -1.0), 1.0  ; "highlight peaks in the mirror direction")]
+    #[test_case((0.0, 1.0, 1.0), 0.0   ; "no highlight looking back at the light")]
+    #[test_case((0.0, -1.0, -1.0), 0.0 ; "no highlight from below the surface")]
+    fn test_specular(to_view: (f64, f64, f64), expected: f64) {
+        let intensity = specular(
+            &Vector3D::new(0.0, 1.0, 0.0),
+            &Vector3D::new(0.0, 1.0, 1.0),
+            &Vector3D::new(to_view.0, to_view.1, to_view.2),
+            32.,
+        );
+
+        assert!((intensity - expected).abs() < 1e-9);
+    }
+
+    #[test_case((0.0, 1.0, -1.0), 1.0  ; "peaks in the mirror direction too")]
+    #[test_case((0.0, -1.0, -1.0), 0.0 ; "light below the surface")]
+    fn test_blinn_phong(to_view: (f64, f64, f64), expected: f64) {
+        let intensity = blinn_phong(
+            &Vector3D::new(0.0, 1.0, 0.0),
+            &Vector3D::new(0.0, 1.0, 1.0),
+            &Vector3D::new(to_view.0, to_view.1, to_view.2),
+            32.,
+        );
+
+        assert!((intensity - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_specular_models_differ_off_axis() {
+        let normal = Vector3D::new(0.0, 1.0, 0.0);
+        let to_light = Vector3D::new(0.0, 1.0, 1.0);
+        let to_view = Vector3D::new(0.2, 1.0, -1.0);
+
+        let phong = specular(&normal, &to_light, &to_view, 32.);
+        let blinn = blinn_phong(&normal, &to_light, &to_view, 32.);
+
+        assert!(phong > 0. && phong < 1.);
+        assert!(blinn > 0. && blinn < 1.);
+        // The halfway vector moves half as far as the reflection, so the highlight is wider
+        assert!(blinn > phong, "{blinn} vs {phong}");
+    }
+
+    #[test]
+    fn test_specular_fades_off_axis() {
+        let normal = Vector3D::new(0.0, 1.0, 0.0);
+        let to_light = Vector3D::new(0.0, 1.0, 1.0);
+
+        let intensities: Vec<f64> = [0.0, 0.1, 0.3, 1.0]
+            .iter()
+            .map(|offset| {
+                let to_view = Vector3D::new(*offset, 1.0, -1.0);
+                specular(&normal, &to_light, &to_view, 32.)
+            })
+            .collect();
+
+        assert!(intensities.windows(2).all(|pair| pair[0] > pair[1]));
+        assert!(intensities[3] < 0.1);
+    }
+
+    #[test_case((0.0, 10.0, 0.0), (255, 255, 255) ; "surface facing the light is full brightness")]
+    #[test_case((0.0, -10.0, 0.0), (0, 0, 0)      ; "surface facing away from the light is black")]
+    fn test_shade(light_position: (f64, f64, f64), expected: (u8, u8, u8)) {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -10.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            800,
+            600,
+        );
+        let scene = Scene::new(
+            &mut camera,
+            color::BLACK,
+            color::BLACK,
+            Box::new([]),
+            Box::new([Box::new(PointLight::new(
+                Vector3D::new(light_position.0, light_position.1, light_position.2),
+                color::WHITE,
+                100.0,
+            ))]),
+        );
+
+        let color = shade(
+            &scene,
+            &Material::default(),
+            color::WHITE,
+            &Vector3D::new(0.0, 0.0, 0.0),
+            &Vector3D::new(0.0, 1.0, 0.0),
+            &viewed_from(Vector3D::new(0.0, 1.0, -1.0)),
+        );
+
+        assert_eq!(color.linear_to_srgb().rgba(), Color::from(expected).rgba());
+    }
+
+    #[test_case(color::BLACK, color::WHITE, (0, 0, 0)    ; "no ambient leaves the dark side black")]
+    #[test_case(color::GREY, color::WHITE, (127, 127, 127) ; "ambient lights the dark side")]
+    #[test_case(color::GREY, color::RED, (127, 0, 0)       ; "ambient is multiplied into the albedo")]
+    fn test_shade_ambient(ambient: Color, albedo: Color, expected: (u8, u8, u8)) {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -10.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            800,
+            600,
+        );
+        let scene = Scene::new(
+            &mut camera,
+            color::BLACK,
+            ambient,
+            Box::new([]),
+            Box::new([Box::new(PointLight::new(
+                Vector3D::new(0.0, -10.0, 0.0),
+                color::WHITE,
+                1.0,
+            ))]),
+        );
+
+        let color = shade(
+            &scene,
+            &Material::default(),
+            albedo,
+            &Vector3D::new(0.0, 0.0, 0.0),
+            &Vector3D::new(0.0, 1.0, 0.0),
+            &viewed_from(Vector3D::new(0.0, 1.0, -1.0)),
+        );
+
+        assert_eq!(color.linear_to_srgb().rgba(), Color::from(expected).rgba());
+    }
+
+    #[test]
+    fn test_emissive_sphere_glows_without_lights() {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            60,
+            60,
+        );
+        let glowing = Material {
+            emission: color::WHITE,
+            ..Material::new(color::RED)
+        };
+        let scene = Scene::new(
+            &mut camera,
+            color::BLACK,
+            color::BLACK,
+            Box::new([
+                Box::new(Sphere::new(Vector3D::new(0.0, 0.0, 0.0), 1.0, glowing)),
+                Box::new(Sphere::new(Vector3D::new(0.0, 0.0, 10.0), 5.0, color::RED)),
+            ]),
+            Box::new([]),
+        );
+
+        assert_eq!(scene.trace(30, 30).unwrap().rgba(), color::WHITE.rgba());
+        // The other sphere keeps its flat color
+        assert_eq!(scene.trace(30, 15).unwrap().rgba(), color::RED.rgba());
+    }
+
+    #[test]
+    fn test_moving_sphere_does_not_shadow_itself() {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -5.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            60,
+            60,
+        );
+        camera.set_shutter(1.0);
+        // Moving away from the light, where it was at the start of the shutter is in front of
+        // where it's seen later on
+        let sphere = Sphere::new(Vector3D::new(0.0, 0.0, 0.0), 1.0, color::WHITE)
+            .with_velocity(Vector3D::new(0.0, 0.0, 2.0));
+        let scene = Scene::new(
+            &mut camera,
+            color::BLACK,
+            color::BLACK,
+            Box::new([Box::new(sphere)]),
+            Box::new([Box::new(PointLight::new(
+                Vector3D::new(0.0, 0.0, -5.0),
+                color::WHITE,
+                100.0,
+            ))]),
+        );
+
+        for y in 28..33 {
+            assert_ne!(scene.trace(30, y).unwrap().rgba(), color::BLACK.rgba());
+        }
+    }
+
+    #[test_case(color::BLACK, (0, 0, 0)  ; "plain surface stays dark")]
+    #[test_case(color::GREEN, (0, 255, 0) ; "emission is added on the unlit side")]
+    fn test_shade_emission(emission: Color, expected: (u8, u8, u8)) {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -10.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            800,
+            600,
+        );
+        let scene = Scene::new(
+            &mut camera,
+            color::BLACK,
+            color::BLACK,
+            Box::new([]),
+            Box::new([Box::new(PointLight::new(
+                Vector3D::new(0.0, -10.0, 0.0),
+                color::WHITE,
+                100.0,
+            ))]),
+        );
+
+        let color = shade(
+            &scene,
+            &Material {
+                emission,
+                ..Material::default()
+            },
+            color::WHITE,
+            &Vector3D::new(0.0, 0.0, 0.0),
+            &Vector3D::new(0.0, 1.0, 0.0),
+            &viewed_from(Vector3D::new(0.0, 1.0, -1.0)),
+        );
+
+        assert_eq!(color.linear_to_srgb().rgba(), Color::from(expected).rgba());
+    }
+
+    #[test]
+    fn test_shade_adds_highlight_to_diffuse() {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -10.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            800,
+            600,
+        );
+        let scene = Scene::new(
+            &mut camera,
+            color::BLACK,
+            color::BLACK,
+            Box::new([]),
+            Box::new([Box::new(PointLight::new(
+                Vector3D::new(0.0, 10.0, 10.0),
+                color::WHITE,
+                1.0,
+            ))]),
+        );
+
+        let shade_from = |to_view: Vector3D| {
+            shade(
+                &scene,
+                &Material::default(),
+                color::GREY,
+                &Vector3D::new(0.0, 0.0, 0.0),
+                &Vector3D::new(0.0, 1.0, 0.0),
+                &viewed_from(to_view),
+            )
+            .linear_to_srgb()
+        };
+
+        let mirror = shade_from(Vector3D::new(0.0, 1.0, -1.0));
+        let off_axis = shade_from(Vector3D::new(1.0, 1.0, 1.0));
+
+        assert!(mirror.luminance() > off_axis.luminance());
+    }
+
+    #[test_case(2.0, 10.0 ; "nearer light is brighter")]
+    #[test_case(5.0, 6.0  ; "small step back still dims")]
+    fn test_shade_attenuates_with_distance(near: f64, far: f64) {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -10.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            800,
+            600,
+        );
+
+        let mut shade_with_light_at = |height: f64| {
+            let scene = Scene::new(
+                &mut camera,
+                color::BLACK,
+                color::BLACK,
+                Box::new([]),
+                Box::new([Box::new(PointLight::new(
+                    Vector3D::new(0.0, height, 0.0),
+                    color::WHITE,
+                    1.0,
+                ))]),
+            );
+
+            shade(
+                &scene,
+                &Material::default(),
+                color::GREY,
+                &Vector3D::new(0.0, 0.0, 0.0),
+                &Vector3D::new(0.0, 1.0, 0.0),
+                &viewed_from(Vector3D::new(1.0, 1.0, 1.0)),
+            )
+            .linear_to_srgb()
+        };
+
+        let near = shade_with_light_at(near);
+        let far = shade_with_light_at(far);
+
+        assert!(near.luminance() > far.luminance());
+    }
+
+    #[test]
+    fn test_ambient_occlusion_darkens_crevices() {
+        let mut camera = Camera::new(
+            &Vector3D::new(0.0, 0.0, -10.0),
+            &Vector3D::new(0.0, 0.0, 0.0),
+            60,
+            60,
+        );
+        let scene = Scene::new(
+            &mut camera,
+            color::BLACK,
+            color::BLACK,
+            Box::new([
+                Box::new(Sphere::new(
+                    Vector3D::new(-1.0, 0.0, 0.0),
+                    1.0,
+                    color::WHITE,
+                )),
+                Box::new(Sphere::new(Vector3D::new(1.0, 0.0, 0.0), 1.0, color::WHITE)),
+            ]),
+            Box::new([]),
+        );
+        let mut rng = XorShift::with_seed(5);
+
+        // Just beside where the spheres touch, facing the other sphere
+        let crevice_normal = Vector3D::new(0.9, 0.3, 0.0).unit();
+        let crevice = &Vector3D::new(-1.0, 0.0, 0.0) + &crevice_normal;
+        let exposed_normal = Vector3D::new(-1.0, 0.0, 0.0);
+        let exposed = Vector3D::new(-2.0, 0.0, 0.0);
+
+        let crevice = ambient_occlusion(&scene, &crevice, &crevice_normal, 256, 1.0, &mut rng);
+        let exposed = ambient_occlusion(&scene, &exposed, &exposed_normal, 256, 1.0, &mut rng);
+
+        assert_eq!(exposed, 1.0);
+        assert!(crevice < 0.8, "{crevice}");
+    }
+}
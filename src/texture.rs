@@ -0,0 +1,471 @@
+use std::{fmt::Debug, fs};
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+
+use crate::{
+    color::Color,
+    noise, png,
+    vector::{Vector2D, Vector3D},
+};
+
+// Hits on a surface through a cell corner land a rounding error either side of it, nudge them
+// all the same way so a flat floor doesn't speckle
+const CELL_BIAS: f64 = 1e-9;
+
+// Color that varies over a surface, looked up by the surface coordinates of a point or the
+// point itself in world space, whichever the texture goes by
+pub trait Texture: Debug + Send + Sync {
+    fn sample(&self, uv: Vector2D, point: &Vector3D) -> Color;
+}
+
+// The same color everywhere
+#[derive(Debug, Clone, Copy)]
+pub struct SolidColor(pub Color);
+
+impl Texture for SolidColor {
+    fn sample(&self, _uv: Vector2D, _point: &Vector3D) -> Color {
+        self.0
+    }
+}
+
+// Blends from one color at u = 0 to the other at u = 1, in linear light
+#[derive(Debug, Clone, Copy)]
+pub struct Gradient {
+    pub from: Color,
+    pub to: Color,
+}
+
+impl Gradient {
+    pub fn new(from: Color, to: Color) -> Self {
+        Gradient { from, to }
+    }
+}
+
+impl Texture for Gradient {
+    fn sample(&self, uv: Vector2D, _point: &Vector3D) -> Color {
+        let t = uv.x().clamp(0., 1.);
+
+        self.from
+            .srgb_to_linear()
+            .scale(1. - t)
+            .add(self.to.srgb_to_linear().scale(t))
+            .linear_to_srgb()
+    }
+}
+
+// Alternating cubes of two colors in world space, the classic ray tracer floor
+#[derive(Debug, Clone, Copy)]
+pub struct Checkerboard {
+    pub color_a: Color,
+    pub color_b: Color,
+    // Edge length of a cell in world units
+    pub scale: f64,
+}
+
+impl Checkerboard {
+    pub fn new(color_a: Color, color_b: Color, scale: f64) -> Self {
+        Checkerboard {
+            color_a,
+            color_b,
+            scale,
+        }
+    }
+}
+
+impl Texture for Checkerboard {
+    fn sample(&self, _uv: Vector2D, point: &Vector3D) -> Color {
+        let cell = |coordinate: f64| (coordinate / self.scale + CELL_BIAS).floor() as i64;
+
+        if (cell(point.x()) + cell(point.y()) + cell(point.z())).rem_euclid(2) == 0 {
+            self.color_a
+        } else {
+            self.color_b
+        }
+    }
+}
+
+// Blotchy variation in the brightness of a base color, for stone, clouds and the like
+#[derive(Debug, Clone, Copy)]
+pub struct NoiseTexture {
+    pub base: Color,
+    // Size of the blotches in world units
+    pub scale: f64,
+    pub seed: u64,
+}
+
+impl NoiseTexture {
+    pub fn new(base: Color, scale: f64) -> Self {
+        NoiseTexture {
+            base,
+            scale,
+            seed: 0,
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn sample(&self, _uv: Vector2D, point: &Vector3D) -> Color {
+        let value = noise::value_noise(&point.scale(1. / self.scale), self.seed);
+
+        self.base.srgb_to_linear().scale(value).linear_to_srgb()
+    }
+}
+
+// What an image texture does with UVs outside 0 to 1, and with the edge pixels it filters
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WrapMode {
+    // Tiles the image, filtering across the edge into the other side
+    #[default]
+    Repeat,
+    // Stretches the edge pixels out
+    Clamp,
+}
+
+// An image laid over a body's UVs, u across from the left edge and v down from the top.
+// Neighbouring pixels are blended bilinearly in linear light.
+#[derive(Debug, Clone)]
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    // Row-major from the top left
+    pixels: Vec<Color>,
+    wrap: WrapMode,
+}
+
+impl ImageTexture {
+    pub fn new(width: usize, height: usize, pixels: Vec<Color>) -> Result<Self> {
+        if width == 0 || height == 0 || Some(pixels.len()) != width.checked_mul(height) {
+            return Err(eyre!(
+                "A {width}x{height} texture needs that many pixels, got {}",
+                pixels.len()
+            ));
+        }
+
+        Ok(ImageTexture {
+            width,
+            height,
+            pixels,
+            wrap: WrapMode::default(),
+        })
+    }
+
+    // Reads a PNG, or a binary PPM with 8-bit channels like Renderer::write_ppm writes. Other
+    // formats like JPEG aren't supported.
+    pub fn load(path: &str) -> Result<Self> {
+        let contents = fs::read(path)?;
+
+        if contents.starts_with(&png::SIGNATURE) {
+            let (width, height, rgba) =
+                png::decode(&contents).wrap_err_with(|| format!("Couldn't read {path}"))?;
+            // Color has no alpha channel, transparent pixels keep their color
+            let pixels = rgba
+                .chunks_exact(4)
+                .map(|pixel| Color::new(pixel[0], pixel[1], pixel[2]))
+                .collect();
+
+            return ImageTexture::new(width as usize, height as usize, pixels);
+        }
+
+        // Magic number, width, height and maximum value separated by whitespace, with comments
+        // running from # to the end of the line. One whitespace byte before the pixels.
+        let mut fields = vec![];
+        let mut position = 0;
+        while fields.len() < 4 {
+            match contents.get(position) {
+                None => return Err(eyre!("{path} ends in the middle of its header")),
+                Some(b'#') => {
+                    while contents.get(position).is_some_and(|byte| *byte != b'\n') {
+                        position += 1;
+                    }
+                }
+                Some(byte) if byte.is_ascii_whitespace() => position += 1,
+                Some(_) => {
+                    let start = position;
+                    while contents
+                        .get(position)
+                        .is_some_and(|byte| !byte.is_ascii_whitespace())
+                    {
+                        position += 1;
+                    }
+                    fields.push(String::from_utf8_lossy(&contents[start..position]).into_owned());
+                }
+            }
+        }
+
+        if fields[0] != "P6" || fields[3] != "255" {
+            return Err(eyre!("{path} isn't a binary PPM with 8-bit channels"));
+        }
+        let (width, height): (usize, usize) = (fields[1].parse()?, fields[2].parse()?);
+        let pixel_count = width
+            .checked_mul(height)
+            .filter(|count| count.checked_mul(3).is_some())
+            .ok_or_else(|| eyre!("{path} is too large at {width}x{height}"))?;
+
+        let data = contents.get(position + 1..).unwrap_or_default();
+        if data.len() < pixel_count * 3 {
+            return Err(eyre!("{path} is missing pixel data"));
+        }
+
+        let pixels = data
+            .chunks_exact(3)
+            .take(pixel_count)
+            .map(|pixel| Color::new(pixel[0], pixel[1], pixel[2]))
+            .collect();
+
+        ImageTexture::new(width, height, pixels)
+    }
+
+    pub fn with_wrap(mut self, wrap: WrapMode) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    // Brings a pixel coordinate within a pixel of the image, so far off UVs can't overflow once
+    // they're whole pixels
+    fn texel(&self, coordinate: f64, size: usize) -> f64 {
+        match self.wrap {
+            WrapMode::Repeat if coordinate.is_finite() => coordinate.rem_euclid(size as f64),
+            WrapMode::Clamp if !coordinate.is_nan() => coordinate.clamp(-1., size as f64),
+            // Repeating has no place on the image for infinity, and neither mode for NaN
+            _ => 0.,
+        }
+    }
+
+    fn pixel(&self, x: i64, y: i64) -> Color {
+        let (x, y) = match self.wrap {
+            WrapMode::Repeat => (
+                x.rem_euclid(self.width as i64),
+                y.rem_euclid(self.height as i64),
+            ),
+            WrapMode::Clamp => (
+                x.clamp(0, self.width as i64 - 1),
+                y.clamp(0, self.height as i64 - 1),
+            ),
+        };
+
+        self.pixels[y as usize * self.width + x as usize]
+    }
+}
+
+impl Texture for ImageTexture {
+    fn sample(&self, uv: Vector2D, _point: &Vector3D) -> Color {
+        // Pixel centers sit half a pixel in from the edges
+        let x = self.texel(uv.x() * self.width as f64 - 0.5, self.width);
+        let y = self.texel(uv.y() * self.height as f64 - 0.5, self.height);
+        let (left, top) = (x.floor(), y.floor());
+        let (across, down) = (x - left, y - top);
+        let (left, top) = (left as i64, top as i64);
+
+        let blend = |a: Color, b: Color, t: f64| {
+            a.srgb_to_linear()
+                .scale(1. - t)
+                .add(b.srgb_to_linear().scale(t))
+        };
+        let upper = blend(self.pixel(left, top), self.pixel(left + 1, top), across);
+        let lower = blend(
+            self.pixel(left, top + 1),
+            self.pixel(left + 1, top + 1),
+            across,
+        );
+
+        upper
+            .scale(1. - down)
+            .add(lower.scale(down))
+            .linear_to_srgb()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color;
+    use test_case::test_case;
+
+    #[test]
+    fn test_solid_color_is_the_same_everywhere() {
+        let texture = SolidColor(color::RED);
+
+        for (u, v) in [(0.0, 0.0), (0.3, 0.9), (1.0, 0.5)] {
+            let point = Vector3D::new(u * 10., -v, 3.0);
+
+            assert_eq!(
+                texture.sample(Vector2D::new(u, v), &point).rgba(),
+                color::RED.rgba()
+            );
+        }
+    }
+
+    #[test_case(0.0, [0, 0, 0, 255]       ; "from at the start")]
+    #[test_case(0.5, [188, 188, 188, 255] ; "halfway in linear light")]
+    #[test_case(1.0, [255, 255, 255, 255] ; "to at the end")]
+    #[test_case(2.0, [255, 255, 255, 255] ; "clamped past the end")]
+    fn test_gradient_varies_with_u(u: f64, expected: [u8; 4]) {
+        let texture = Gradient::new(color::BLACK, color::WHITE);
+        let point = Vector3D::new(0.0, 0.0, 0.0);
+
+        assert_eq!(
+            texture.sample(Vector2D::new(u, 0.7), &point).rgba(),
+            expected
+        );
+        assert_eq!(
+            texture.sample(Vector2D::new(u, 0.1), &point).rgba(),
+            expected
+        );
+    }
+
+    // Red and green on top, blue and white below, as a PNG or PPM going by the name
+    fn write_checkerboard(name: &str) -> String {
+        let contents = if name.ends_with(".png") {
+            png::encode(
+                2,
+                2,
+                &[
+                    255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 255, 255, 255, 255,
+                ],
+            )
+        } else {
+            let mut contents = b"P6\n# 2x2 test image\n2 2\n255\n".to_vec();
+            contents.extend([255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255]);
+            contents
+        };
+
+        let path = std::env::temp_dir().join(name);
+        fs::write(&path, contents).unwrap();
+
+        path.to_str().unwrap().to_owned()
+    }
+
+    #[test_case("ppm", (0.0, 0.0), color::RED   ; "top left")]
+    #[test_case("ppm", (1.0, 0.0), color::GREEN ; "top right")]
+    #[test_case("ppm", (0.0, 1.0), color::BLUE  ; "bottom left")]
+    #[test_case("ppm", (1.0, 1.0), color::WHITE ; "bottom right")]
+    #[test_case("png", (0.0, 0.0), color::RED   ; "png top left")]
+    #[test_case("png", (1.0, 0.0), color::GREEN ; "png top right")]
+    #[test_case("png", (0.0, 1.0), color::BLUE  ; "png bottom left")]
+    #[test_case("png", (1.0, 1.0), color::WHITE ; "png bottom right")]
+    fn test_image_texture_corners(extension: &str, uv: (f64, f64), expected: Color) {
+        let name = format!("ray-tracer-texture-corners-{}-{}.{extension}", uv.0, uv.1);
+        let texture = ImageTexture::load(&write_checkerboard(&name))
+            .unwrap()
+            .with_wrap(WrapMode::Clamp);
+        let point = Vector3D::new(0.0, 0.0, 0.0);
+
+        assert_eq!(
+            texture.sample(Vector2D::new(uv.0, uv.1), &point).rgba(),
+            expected.rgba()
+        );
+    }
+
+    #[test]
+    fn test_image_texture_filtering() {
+        let texture =
+            ImageTexture::load(&write_checkerboard("ray-tracer-texture-filter.ppm")).unwrap();
+        let point = Vector3D::new(0.0, 0.0, 0.0);
+
+        // Halfway between red and green in linear light
+        assert_eq!(
+            texture.sample(Vector2D::new(0.5, 0.25), &point).rgba(),
+            [188, 188, 0, 255]
+        );
+        // Repeating wraps the left edge round to the right, clamping keeps it red
+        let edge = Vector2D::new(0.0, 0.25);
+        assert_eq!(texture.sample(edge, &point).rgba(), [188, 188, 0, 255]);
+        assert_eq!(
+            texture
+                .clone()
+                .with_wrap(WrapMode::Clamp)
+                .sample(edge, &point)
+                .rgba(),
+            color::RED.rgba()
+        );
+    }
+
+    #[test_case("short", "P6 4 4 255\n\x00\x00"                         ; "missing pixels")]
+    #[test_case("huge", "P6 4294967296 4294967296 255\n\x00\x00"      ; "pixel count overflows")]
+    #[test_case("wide", "P6 6148914691236517206 1 255\n\x00\x00"      ; "byte count overflows")]
+    fn test_image_texture_rejects_bad_files(name: &str, contents: &str) {
+        let path = std::env::temp_dir().join(format!("ray-tracer-texture-{name}.ppm"));
+        fs::write(&path, contents).unwrap();
+
+        assert!(ImageTexture::load(path.to_str().unwrap()).is_err());
+    }
+
+    #[test_case(WrapMode::Repeat, (1e300, -1e300)   ; "repeat far off")]
+    #[test_case(WrapMode::Clamp, (1e300, -1e300)    ; "clamp far off")]
+    #[test_case(WrapMode::Repeat, (f64::MAX, 0.25) ; "repeat at the largest float")]
+    #[test_case(WrapMode::Clamp, (f64::MAX, 0.25)  ; "clamp at the largest float")]
+    #[test_case(WrapMode::Repeat, (f64::NAN, 0.25) ; "repeat not a number")]
+    #[test_case(WrapMode::Clamp, (f64::NAN, 0.25)  ; "clamp not a number")]
+    fn test_image_texture_huge_uvs(wrap: WrapMode, uv: (f64, f64)) {
+        let texture = ImageTexture::new(2, 2, vec![color::RED; 4])
+            .unwrap()
+            .with_wrap(wrap);
+        let point = Vector3D::new(0.0, 0.0, 0.0);
+
+        assert_eq!(
+            texture.sample(Vector2D::new(uv.0, uv.1), &point).rgba(),
+            color::RED.rgba()
+        );
+    }
+
+    #[test]
+    fn test_image_texture_checks_pixel_count() {
+        assert!(ImageTexture::new(2, 2, vec![color::RED; 3]).is_err());
+        assert!(ImageTexture::new(usize::MAX, 2, vec![]).is_err());
+    }
+
+    #[test_case((0.5, 0.0, 0.5), (1.0, 0.0, 0.0), 1.0  ; "neighbour along X")]
+    #[test_case((0.5, 0.0, 0.5), (0.0, 0.0, 1.0), 1.0  ; "neighbour along Z")]
+    #[test_case((-0.5, 0.0, -0.5), (1.0, 0.0, 0.0), 1.0 ; "neighbours across the origin")]
+    #[test_case((1.0, 0.0, 1.0), (0.0, 0.0, 2.5), 2.5  ; "larger cells")]
+    fn test_adjacent_cells_differ(point: (f64, f64, f64), step: (f64, f64, f64), scale: f64) {
+        let checkerboard = Checkerboard::new(color::WHITE, color::BLACK, scale);
+
+        let point = Vector3D::new(point.0, point.1, point.2);
+        let neighbour = &point + &Vector3D::new(step.0, step.1, step.2);
+
+        assert_ne!(
+            checkerboard.sample(Vector2D::default(), &point).rgba(),
+            checkerboard.sample(Vector2D::default(), &neighbour).rgba()
+        );
+    }
+
+    #[test_case(1.0, 0 ; "unit blotches")]
+    #[test_case(4.0, 7 ; "large seeded blotches")]
+    fn test_noise_texture(scale: f64, seed: u64) {
+        let texture = NoiseTexture::new(color::WHITE, scale).with_seed(seed);
+        let point = Vector3D::new(1.3, -0.2, 4.8);
+        let nearby = &point + &Vector3D::new(0.001, 0.001, 0.0);
+
+        let [r, g, b, _] = texture.sample(Vector2D::default(), &point).rgba();
+        let [near_r, _, _, _] = texture.sample(Vector2D::default(), &nearby).rgba();
+
+        assert_eq!(
+            texture.sample(Vector2D::default(), &point).rgba(),
+            [r, g, b, 255]
+        );
+        assert!(r == g && g == b);
+        assert!((r as i32 - near_r as i32).abs() <= 2, "{r} vs {near_r}");
+    }
+
+    #[test_case((0.25, 0.0, 0.25), (0.75, 0.0, 0.75) ; "same cell")]
+    #[test_case((0.5, 0.0, 0.5), (1.5, 0.0, 1.5)     ; "diagonal cells match")]
+    #[test_case((0.5, -1e-12, 0.5), (0.5, 1e-12, 0.5) ; "rounding across the floor")]
+    fn test_matching_cells(a: (f64, f64, f64), b: (f64, f64, f64)) {
+        let checkerboard = Checkerboard::new(color::WHITE, color::BLACK, 1.0);
+
+        assert_eq!(
+            checkerboard
+                .sample(Vector2D::default(), &Vector3D::new(a.0, a.1, a.2))
+                .rgba(),
+            checkerboard
+                .sample(Vector2D::default(), &Vector3D::new(b.0, b.1, b.2))
+                .rgba()
+        );
+    }
+}
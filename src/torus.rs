@@ -0,0 +1,163 @@
+use crate::{
+    aabb::Aabb,
+    body::{Body, Colored, Renderable, Volume},
+    color::Color,
+    material::Material,
+    ray::Ray,
+    roots::Roots,
+    utils::solve_quartic,
+    vector::Vector3D,
+};
+
+// A ring around the axis, with the major radius measured to the middle of the tube
+#[derive(Debug)]
+pub struct Torus {
+    body: Body,
+    center: Vector3D,
+    axis: Vector3D,
+    major_radius: f64,
+    minor_radius: f64,
+}
+
+impl Torus {
+    pub fn new(
+        center: Vector3D,
+        axis: Vector3D,
+        major_radius: f64,
+        minor_radius: f64,
+        material: impl Into<Material>,
+    ) -> Self {
+        Torus {
+            body: Body::new(material),
+            center,
+            axis: axis.unit(),
+            major_radius,
+            minor_radius,
+        }
+    }
+}
+
+impl Colored for Torus {
+    fn material(&self) -> &Material {
+        self.body.material()
+    }
+}
+
+impl Volume for Torus {
+    // Expands (|P|² + R² - r²)² = 4R²(|P|² - (P·A)²) with P = O + tD relative to the center
+    fn intersect(&self, ray: &Ray) -> Roots {
+        let origin = Vector3D::from(&self.center).to(&ray.start);
+        let major_squared = self.major_radius * self.major_radius;
+
+        let origin_along_ray = origin.dot(&ray.direction);
+        let origin_along_axis = origin.dot(&self.axis);
+        let direction_along_axis = ray.direction.dot(&self.axis);
+        let k = origin.squid() + major_squared - self.minor_radius * self.minor_radius;
+
+        let a = 4. * origin_along_ray;
+        let b = 4. * origin_along_ray * origin_along_ray + 2. * k
+            - 4. * major_squared * (1. - direction_along_axis * direction_along_axis);
+        let c = 4. * origin_along_ray * k
+            - 8. * major_squared * (origin_along_ray - origin_along_axis * direction_along_axis);
+        let d = k * k - 4. * major_squared * (origin.squid() - origin_along_axis.powi(2));
+
+        solve_quartic(a, b, c, d)
+    }
+
+    fn get_normal_at(&self, point: &Vector3D, _time: f64) -> Vector3D {
+        let relative = Vector3D::from(&self.center).to(point);
+        let in_plane = &relative - &(&self.axis * relative.dot(&self.axis));
+        let tube_center = in_plane.unit().scale(self.major_radius);
+
+        Vector3D::from(&tube_center).to(&relative).unit()
+    }
+
+    fn get_color_at(&self, _point: &Vector3D, _time: f64) -> Color {
+        self.color()
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        // The ring spreads like a disk perpendicular to the axis, padded by the tube
+        let spread = |component: f64| {
+            self.major_radius * (1. - component * component).max(0.).sqrt() + self.minor_radius
+        };
+        let extent = Vector3D::new(
+            spread(self.axis.x()),
+            spread(self.axis.y()),
+            spread(self.axis.z()),
+        );
+
+        Aabb::new(&(&self.center - &extent), &(&self.center + &extent))
+    }
+}
+
+impl Renderable for Torus {
+    fn is_enabled(&self) -> bool {
+        self.body.is_enabled()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.body.set_enabled(enabled);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn ring() -> Torus {
+        Torus::new(
+            Vector3D::new(0.0, 0.0, 0.0),
+            Vector3D::new(0.0, 1.0, 0.0),
+            2.0,
+            0.5,
+            Color::new(0, 0, 0),
+        )
+    }
+
+    #[test_case((0.0, 5.0, 0.0), (0.0, -1.0, 0.0), None      ; "ray threading the hole misses")]
+    #[test_case((2.0, 5.0, 0.0), (0.0, -1.0, 0.0), Some(4.5) ; "ray from above hits the tube")]
+    #[test_case((-5.0, 0.0, 0.0), (1.0, 0.0, 0.0), Some(2.5) ; "ray through the middle hits the outer wall")]
+    #[test_case((0.0, 0.0, 0.0), (1.0, 0.0, 0.0), Some(1.5)  ; "ray from the center hits the inner wall")]
+    #[test_case((5.0, 5.0, 0.0), (0.0, -1.0, 0.0), None      ; "ray outside the ring misses")]
+    fn test_torus_intersection(
+        start: (f64, f64, f64),
+        direction: (f64, f64, f64),
+        expected_distance: Option<f64>,
+    ) {
+        let torus = ring();
+        let ray = Ray::new(
+            &Vector3D::new(start.0, start.1, start.2),
+            &Vector3D::new(direction.0, direction.1, direction.2),
+        );
+
+        let distance = torus.closest_ray_distance(&ray);
+        match (distance, expected_distance) {
+            (Some(distance), Some(expected)) => assert!((distance - expected).abs() < 1e-9),
+            (distance, expected) => assert_eq!(distance, expected),
+        }
+    }
+
+    #[test_case((-5.0, 0.0, 0.0), (1.0, 0.0, 0.0), 4 ; "ray through the middle crosses the tube four times")]
+    #[test_case((2.0, 5.0, 0.0), (0.0, -1.0, 0.0), 2 ; "ray through the tube crosses it twice")]
+    fn test_torus_root_count(start: (f64, f64, f64), direction: (f64, f64, f64), expected: usize) {
+        let ray = Ray::new(
+            &Vector3D::new(start.0, start.1, start.2),
+            &Vector3D::new(direction.0, direction.1, direction.2),
+        );
+
+        assert_eq!(ring().intersect(&ray).len(), expected);
+    }
+
+    #[test_case((2.5, 0.0, 0.0), (1.0, 0.0, 0.0)  ; "outer equator normal points away from the axis")]
+    #[test_case((2.0, 0.5, 0.0), (0.0, 1.0, 0.0)  ; "top of the tube normal points up")]
+    #[test_case((1.5, 0.0, 0.0), (-1.0, 0.0, 0.0) ; "inner equator normal points at the axis")]
+    fn test_torus_normal(point: (f64, f64, f64), expected: (f64, f64, f64)) {
+        let normal = ring().get_normal_at(&Vector3D::new(point.0, point.1, point.2), 0.);
+
+        assert!((normal.x() - expected.0).abs() < 1e-9);
+        assert!((normal.y() - expected.1).abs() < 1e-9);
+        assert!((normal.z() - expected.2).abs() < 1e-9);
+    }
+}
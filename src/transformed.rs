@@ -0,0 +1,212 @@
+use color_eyre::eyre::{eyre, Result};
+
+use crate::{
+    aabb::Aabb,
+    body::{Colored, Renderable, Volume},
+    color::Color,
+    material::Material,
+    matrix::Matrix4,
+    ray::Ray,
+    roots::Roots,
+    vector::Vector3D,
+};
+
+// Body placed in the scene by a transform, the wrapped body stays in its own object space
+#[derive(Debug)]
+pub struct Transformed<T: Renderable> {
+    inner: T,
+    forward: Matrix4,
+    inverse: Matrix4,
+}
+
+impl<T: Renderable> Transformed<T> {
+    pub fn new(inner: T, transform: Matrix4) -> Result<Self> {
+        let inverse = transform
+            .inverse()
+            .ok_or_else(|| eyre!("The transform {transform:?} can't be inverted"))?;
+
+        Ok(Transformed {
+            inner,
+            forward: transform,
+            inverse,
+        })
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    // The ray in object space, along with how much longer its direction got on the way there.
+    // Distances along the object ray have to be divided by that to be distances in the scene
+    fn object_ray(&self, ray: &Ray) -> (Ray, f64) {
+        let direction = self.inverse.transform_vector(&ray.direction);
+        let stretch = direction.length();
+
+        (
+            Ray::new(&self.inverse.transform_point(&ray.start), &direction)
+                .with_time(ray.time)
+                .with_seed(ray.seed),
+            stretch,
+        )
+    }
+}
+
+impl<T: Renderable> Colored for Transformed<T> {
+    fn material(&self) -> &Material {
+        self.inner.material()
+    }
+}
+
+impl<T: Renderable> Volume for Transformed<T> {
+    fn intersect(&self, ray: &Ray) -> Roots {
+        let (object_ray, stretch) = self.object_ray(ray);
+
+        self.inner
+            .intersect(&object_ray)
+            .into_iter()
+            .map(|distance| distance / stretch)
+            .collect()
+    }
+
+    fn intervals(&self, ray: &Ray) -> Vec<(f64, f64)> {
+        let (object_ray, stretch) = self.object_ray(ray);
+
+        self.inner
+            .intervals(&object_ray)
+            .into_iter()
+            .map(|(entry, exit)| (entry / stretch, exit / stretch))
+            .collect()
+    }
+
+    // Normals are carried back by the inverse transpose, so they stay perpendicular to
+    // surfaces that were scaled unevenly
+    fn get_normal_at(&self, point: &Vector3D, time: f64) -> Vector3D {
+        let normal = self
+            .inner
+            .get_normal_at(&self.inverse.transform_point(point), time);
+
+        self.inverse.transpose().transform_vector(&normal)
+    }
+
+    fn get_color_at(&self, point: &Vector3D, time: f64) -> Color {
+        self.inner
+            .get_color_at(&self.inverse.transform_point(point), time)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let bounds = self.inner.bounding_box();
+
+        if !bounds.is_finite() {
+            return Aabb::infinite();
+        }
+
+        let (min, max) = (bounds.min(), bounds.max());
+        let corners: Vec<_> = (0..8)
+            .map(|corner| {
+                let pick = |bit: usize, low: f64, high: f64| {
+                    if corner & bit == 0 {
+                        low
+                    } else {
+                        high
+                    }
+                };
+
+                self.forward.transform_point(&Vector3D::new(
+                    pick(1, min.x(), max.x()),
+                    pick(2, min.y(), max.y()),
+                    pick(4, min.z(), max.z()),
+                ))
+            })
+            .collect();
+
+        Aabb::from_points(&corners).unwrap_or_else(Aabb::infinite)
+    }
+}
+
+impl<T: Renderable> Renderable for Transformed<T> {
+    fn is_enabled(&self) -> bool {
+        self.inner.is_enabled()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.inner.set_enabled(enabled);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{body::Sphere, color};
+    use test_case::test_case;
+
+    fn unit_sphere() -> Sphere {
+        Sphere::new(Vector3D::new(0., 0., 0.), 1., color::RED)
+    }
+
+    #[test]
+    fn test_translated_sphere_is_hit_where_the_original_is_not() {
+        let moved = Transformed::new(unit_sphere(), Matrix4::translation(5., 0., 0.)).unwrap();
+        let ray = Ray::new(&Vector3D::new(5., 0., -5.), &Vector3D::new(0., 0., 1.));
+
+        assert_eq!(unit_sphere().closest_ray_distance(&ray), None);
+        assert_eq!(moved.closest_ray_distance(&ray), Some(4.));
+
+        let point = moved.closest_ray_point(&ray).unwrap();
+        assert_eq!(point, Vector3D::new(5., 0., -1.));
+        assert_eq!(moved.normal_at(&point, 0.), Vector3D::new(0., 0., -1.));
+    }
+
+    #[test_case(Matrix4::scaling(2., 2., 2.), (0., 0., -5.), 3.     ; "uniform scaling keeps distances in the scene")]
+    #[test_case(Matrix4::scaling(1., 1., 3.), (0., 0., -5.), 2.     ; "stretched along the ray")]
+    #[test_case(Matrix4::scaling(3., 1., 1.), (2.5, 0., -5.), 5. - (2.75f64 / 9.).sqrt() ; "stretched across the ray")]
+    fn test_scaled_sphere_distance(transform: Matrix4, start: (f64, f64, f64), expected: f64) {
+        let scaled = Transformed::new(unit_sphere(), transform).unwrap();
+        let ray = Ray::new(
+            &Vector3D::new(start.0, start.1, start.2),
+            &Vector3D::new(0., 0., 1.),
+        );
+
+        let distance = scaled.closest_ray_distance(&ray).unwrap();
+
+        assert!(
+            (distance - expected).abs() < 1e-9,
+            "{distance} != {expected}"
+        );
+    }
+
+    #[test]
+    fn test_unevenly_scaled_normal_stays_perpendicular() {
+        // An ellipsoid twice as wide as it's tall, its flank at 45° has a steeper normal
+        let squashed = Transformed::new(unit_sphere(), Matrix4::scaling(2., 1., 1.)).unwrap();
+        let point = Vector3D::new(2f64.sqrt(), 0.5f64.sqrt(), 0.);
+
+        let normal = squashed.normal_at(&point, 0.);
+        let expected = Vector3D::new(0.5f64.sqrt() / 2., 0.5f64.sqrt(), 0.).unit();
+
+        assert!(
+            (&normal - &expected).length() < 1e-9,
+            "{normal} != {expected}"
+        );
+    }
+
+    #[test]
+    fn test_transformed_bounding_box() {
+        let moved = Transformed::new(
+            unit_sphere(),
+            Matrix4::translation(5., 0., 0.) * Matrix4::scaling(2., 1., 1.),
+        )
+        .unwrap();
+
+        let bounds = moved.bounding_box();
+
+        assert_eq!(bounds.min(), &Vector3D::new(3., -1., -1.));
+        assert_eq!(bounds.max(), &Vector3D::new(7., 1., 1.));
+    }
+
+    #[test]
+    fn test_singular_transform_is_rejected() {
+        let error = Transformed::new(unit_sphere(), Matrix4::scaling(0., 1., 1.)).unwrap_err();
+
+        assert!(error.to_string().contains("can't be inverted"));
+    }
+}
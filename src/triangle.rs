@@ -0,0 +1,229 @@
+use crate::{
+    aabb::Aabb,
+    body::{Body, Colored, Renderable, Volume, THRESHOLD},
+    color::Color,
+    material::Material,
+    ray::Ray,
+    roots::Roots,
+    vector::Vector3D,
+};
+
+#[derive(Debug)]
+pub struct Triangle {
+    body: Body,
+    a: Vector3D,
+    b: Vector3D,
+    c: Vector3D,
+    normal: Vector3D,
+}
+
+impl Triangle {
+    pub fn new(a: Vector3D, b: Vector3D, c: Vector3D, material: impl Into<Material>) -> Self {
+        let normal = Vector3D::from(&a)
+            .to(&b)
+            .cross(&Vector3D::from(&a).to(&c))
+            .unit();
+
+        Triangle {
+            body: Body::new(material),
+            a,
+            b,
+            c,
+            normal,
+        }
+    }
+
+    pub fn vertices(&self) -> [&Vector3D; 3] {
+        [&self.a, &self.b, &self.c]
+    }
+
+    // Checks whether a point on the triangle's plane lies within its edges
+    pub fn contains(&self, point: &Vector3D) -> bool {
+        [(&self.a, &self.b), (&self.b, &self.c), (&self.c, &self.a)]
+            .iter()
+            .all(|(from, to)| {
+                let edge = Vector3D::from(from).to(to);
+                let to_point = Vector3D::from(from).to(point);
+
+                edge.cross(&to_point).dot(&self.normal) >= -THRESHOLD
+            })
+    }
+
+    // Point on the triangle, edges included, nearest to the given one. Works out which vertex,
+    // edge or the face the point is closest to from its barycentric position.
+    pub fn closest_point(&self, point: &Vector3D) -> Vector3D {
+        let ab = Vector3D::from(&self.a).to(&self.b);
+        let ac = Vector3D::from(&self.a).to(&self.c);
+        let ap = Vector3D::from(&self.a).to(point);
+        let (d1, d2) = (ab.dot(&ap), ac.dot(&ap));
+        if d1 <= 0. && d2 <= 0. {
+            return self.a.clone();
+        }
+
+        let bp = Vector3D::from(&self.b).to(point);
+        let (d3, d4) = (ab.dot(&bp), ac.dot(&bp));
+        if d3 >= 0. && d4 <= d3 {
+            return self.b.clone();
+        }
+
+        let vc = d1 * d4 - d3 * d2;
+        if vc <= 0. && d1 >= 0. && d3 <= 0. {
+            return self.a.append(&ab.scale(d1 / (d1 - d3)));
+        }
+
+        let cp = Vector3D::from(&self.c).to(point);
+        let (d5, d6) = (ab.dot(&cp), ac.dot(&cp));
+        if d6 >= 0. && d5 <= d6 {
+            return self.c.clone();
+        }
+
+        let vb = d5 * d2 - d1 * d6;
+        if vb <= 0. && d2 >= 0. && d6 <= 0. {
+            return self.a.append(&ac.scale(d2 / (d2 - d6)));
+        }
+
+        let va = d3 * d6 - d5 * d4;
+        if va <= 0. && d4 - d3 >= 0. && d5 - d6 >= 0. {
+            let bc = Vector3D::from(&self.b).to(&self.c);
+            return self
+                .b
+                .append(&bc.scale((d4 - d3) / ((d4 - d3) + (d5 - d6))));
+        }
+
+        let denominator = 1. / (va + vb + vc);
+        self.a
+            .append(&ab.scale(vb * denominator))
+            .append(&ac.scale(vc * denominator))
+    }
+}
+
+impl Colored for Triangle {
+    fn material(&self) -> &Material {
+        self.body.material()
+    }
+}
+
+impl Volume for Triangle {
+    // Möller–Trumbore intersection
+    fn intersect(&self, ray: &Ray) -> Roots {
+        let edge_ab = Vector3D::from(&self.a).to(&self.b);
+        let edge_ac = Vector3D::from(&self.a).to(&self.c);
+
+        let p = ray.direction.cross(&edge_ac);
+        let determinant = edge_ab.dot(&p);
+
+        // The ray runs parallel to the triangle
+        if determinant.abs() < THRESHOLD {
+            return Roots::new();
+        }
+
+        let inverse_determinant = 1. / determinant;
+        let a_to_start = Vector3D::from(&self.a).to(&ray.start);
+
+        let u = a_to_start.dot(&p) * inverse_determinant;
+        if !(0. ..=1.).contains(&u) {
+            return Roots::new();
+        }
+
+        let q = a_to_start.cross(&edge_ab);
+        let v = ray.direction.dot(&q) * inverse_determinant;
+        if v < 0. || u + v > 1. {
+            return Roots::new();
+        }
+
+        Roots::from([edge_ac.dot(&q) * inverse_determinant])
+    }
+
+    fn get_normal_at(&self, _point: &Vector3D, _time: f64) -> Vector3D {
+        self.normal.clone()
+    }
+
+    fn get_color_at(&self, _point: &Vector3D, _time: f64) -> Color {
+        self.color()
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::new(&self.a, &self.b).union(&Aabb::new(&self.c, &self.c))
+    }
+}
+
+impl Renderable for Triangle {
+    fn is_enabled(&self) -> bool {
+        self.body.is_enabled()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.body.set_enabled(enabled);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::approx_eq;
+    use test_case::test_case;
+
+    #[test_case((0.25, 0.25, -5.0), (0.0, 0.0, 1.0), vec![5.0] ; "ray hits triangle interior")]
+    #[test_case((2.0, 2.0, -5.0), (0.0, 0.0, 1.0), vec![]      ; "ray misses outside edges")]
+    #[test_case((0.25, 0.25, -5.0), (1.0, 0.0, 0.0), vec![]    ; "ray parallel to triangle misses")]
+    fn test_triangle_intersection(
+        start: (f64, f64, f64),
+        direction: (f64, f64, f64),
+        expected_ts: Vec<f64>,
+    ) {
+        let triangle = Triangle::new(
+            Vector3D::new(0.0, 0.0, 0.0),
+            Vector3D::new(1.0, 0.0, 0.0),
+            Vector3D::new(0.0, 1.0, 0.0),
+            Color::new(0, 0, 0),
+        );
+        let ray = Ray::new(
+            &Vector3D::new(start.0, start.1, start.2),
+            &Vector3D::new(direction.0, direction.1, direction.2),
+        );
+
+        let intersections = triangle.intersect(&ray);
+        assert_eq!(intersections.len(), expected_ts.len());
+        assert!(intersections
+            .iter()
+            .zip(expected_ts.iter())
+            .all(|(t, expected)| approx_eq(*t, *expected)));
+    }
+
+    #[test_case((0.25, 0.25, 0.0), true  ; "point inside triangle")]
+    #[test_case((0.75, 0.75, 0.0), false ; "point past the hypotenuse")]
+    fn test_triangle_contains(point: (f64, f64, f64), expected: bool) {
+        let triangle = Triangle::new(
+            Vector3D::new(0.0, 0.0, 0.0),
+            Vector3D::new(1.0, 0.0, 0.0),
+            Vector3D::new(0.0, 1.0, 0.0),
+            Color::new(0, 0, 0),
+        );
+
+        assert_eq!(
+            triangle.contains(&Vector3D::new(point.0, point.1, point.2)),
+            expected
+        );
+    }
+
+    #[test_case((0.25, 0.25, 2.0), (0.25, 0.25, 0.0) ; "above the face")]
+    #[test_case((-1.0, -1.0, 0.0), (0.0, 0.0, 0.0)   ; "past a vertex")]
+    #[test_case((0.5, -2.0, 1.0), (0.5, 0.0, 0.0)    ; "beside an edge")]
+    #[test_case((1.0, 1.0, 0.0), (0.5, 0.5, 0.0)     ; "past the hypotenuse")]
+    fn test_triangle_closest_point(point: (f64, f64, f64), expected: (f64, f64, f64)) {
+        let triangle = Triangle::new(
+            Vector3D::new(0.0, 0.0, 0.0),
+            Vector3D::new(1.0, 0.0, 0.0),
+            Vector3D::new(0.0, 1.0, 0.0),
+            Color::new(0, 0, 0),
+        );
+        let closest = triangle.closest_point(&Vector3D::new(point.0, point.1, point.2));
+
+        assert!(
+            closest
+                .to(&Vector3D::new(expected.0, expected.1, expected.2))
+                .length()
+                < 1e-9
+        );
+    }
+}
@@ -1,5 +1,216 @@
-use crate::body::THRESHOLD;
+use crate::{body::THRESHOLD, roots::Roots};
 
 pub fn approx_eq(a: f64, b: f64) -> bool {
-    (a - b).abs() < THRESHOLD
+    approx_eq_eps(a, b, THRESHOLD)
+}
+
+// Absolute comparison with a tolerance picked for the size of what's being compared
+pub fn approx_eq_eps(a: f64, b: f64, eps: f64) -> bool {
+    (a - b).abs() < eps
+}
+
+// Tolerance as a share of the larger magnitude, for values far from one. Nothing is relatively
+// close to zero, so comparisons against it use the tolerance as an absolute one instead
+pub fn approx_eq_rel(a: f64, b: f64, rel: f64) -> bool {
+    if a == 0. || b == 0. {
+        return approx_eq_eps(a, b, rel);
+    }
+
+    (a - b).abs() <= rel * a.abs().max(b.abs())
+}
+
+// Real roots of ax² + bx + c = 0 in ascending order, degrading to the linear case when a is ~0
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Roots {
+    if a.abs() < THRESHOLD {
+        if b.abs() < THRESHOLD {
+            return Roots::new();
+        }
+
+        return Roots::from([-c / b]);
+    }
+
+    let discriminant = b * b - 4. * a * c;
+
+    if discriminant < 0. {
+        Roots::new()
+    } else if discriminant == 0. {
+        Roots::from([-b / (2. * a)])
+    } else {
+        // Avoid cancellation between -b and the root, see Numerical Recipes 5.6
+        let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+        let (first, second) = (q / a, c / q);
+
+        if first < second {
+            Roots::from([first, second])
+        } else {
+            Roots::from([second, first])
+        }
+    }
+}
+
+// Real roots of x³ + ax² + bx + c = 0 in ascending order, via Cardano or the trigonometric form
+pub fn solve_cubic(a: f64, b: f64, c: f64) -> Roots {
+    // Substitute x = t - a/3 to get the depressed cubic t³ + pt + q = 0
+    let shift = a / 3.;
+    let p = b - a * a / 3.;
+    let q = 2. * a * a * a / 27. - a * b / 3. + c;
+
+    let discriminant = (q / 2.).powi(2) + (p / 3.).powi(3);
+
+    let mut roots = if p.abs() < THRESHOLD && q.abs() < THRESHOLD {
+        Roots::from([0.])
+    } else if discriminant.abs() < THRESHOLD {
+        // A single and a double root
+        Roots::from([3. * q / p, -3. * q / (2. * p)])
+    } else if discriminant > 0. {
+        let root = discriminant.sqrt();
+        Roots::from([(-q / 2. + root).cbrt() + (-q / 2. - root).cbrt()])
+    } else {
+        let magnitude = 2. * (-p / 3.).sqrt();
+        let angle = (3. * q / (2. * p) * (-3. / p).sqrt()).clamp(-1., 1.).acos() / 3.;
+
+        (0..3)
+            .map(|k| magnitude * (angle - 2. * std::f64::consts::PI * k as f64 / 3.).cos())
+            .collect()
+    };
+
+    roots.iter_mut().for_each(|root| *root -= shift);
+    roots.sort_by(|a, b| a.total_cmp(b));
+    roots
+}
+
+// Real roots of x⁴ + ax³ + bx² + cx + d = 0 in ascending order, using Ferrari's method followed
+// by a few Newton steps against the original polynomial to polish away cancellation error
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64) -> Roots {
+    // Substitute x = y - a/4 to get the depressed quartic y⁴ + py² + qy + r = 0
+    let shift = a / 4.;
+    let a_squared = a * a;
+    let p = b - 3. * a_squared / 8.;
+    let q = c - a * b / 2. + a_squared * a / 8.;
+    let r = d - a * c / 4. + a_squared * b / 16. - 3. * a_squared * a_squared / 256.;
+
+    let mut roots: Roots = if q.abs() < THRESHOLD {
+        // Biquadratic, solve for y² directly
+        solve_quadratic(1., p, r)
+            .into_iter()
+            .filter(|z| *z >= -THRESHOLD)
+            .flat_map(|z| {
+                let y = z.max(0.).sqrt();
+                [-y, y]
+            })
+            .collect()
+    } else {
+        // The resolvent cubic always has a positive root when q isn't zero
+        let m = solve_cubic(p, p * p / 4. - r, -q * q / 8.)
+            .into_iter()
+            .fold(f64::NEG_INFINITY, f64::max);
+
+        if m <= 0. {
+            return Roots::new();
+        }
+
+        let root = (2. * m).sqrt();
+        let offset = q / (2. * root);
+
+        solve_quadratic(1., -root, p / 2. + m + offset)
+            .into_iter()
+            .chain(solve_quadratic(1., root, p / 2. + m - offset))
+            .collect()
+    };
+
+    let evaluate = |x: f64| (((x + a) * x + b) * x + c) * x + d;
+    let derive = |x: f64| ((4. * x + 3. * a) * x + 2. * b) * x + c;
+
+    roots.iter_mut().for_each(|root| {
+        *root -= shift;
+
+        for _ in 0..4 {
+            let slope = derive(*root);
+            if slope.abs() < THRESHOLD {
+                break;
+            }
+            *root -= evaluate(*root) / slope;
+        }
+    });
+
+    roots.sort_by(|a, b| a.total_cmp(b));
+
+    // Drop repeated roots that the polishing pulled together
+    roots.into_iter().fold(Roots::new(), |mut distinct, root| {
+        if distinct
+            .last()
+            .is_none_or(|last| (root - last).abs() >= THRESHOLD.sqrt())
+        {
+            distinct.push(root);
+        }
+        distinct
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(1.0, 1.0 + 1e-9, 1e-6, true  ; "rounding error is fine at a loose tolerance")]
+    #[test_case(100.0, 100.001, 1e-2, true   ; "rendered distances within a hundredth")]
+    #[test_case(1.0, 1.1, 1e-2, false        ; "too far apart even when loose")]
+    fn test_approx_eq_eps(a: f64, b: f64, eps: f64, expected: bool) {
+        // Anything this far apart is beyond the default tolerance
+        assert!(!approx_eq(a, b));
+        assert_eq!(approx_eq_eps(a, b, eps), expected);
+    }
+
+    #[test_case(1_000_000.0, 1_000_000.1, 1e-6, true ; "large distances")]
+    #[test_case(1e-6, 1.1e-6, 1e-6, false            ; "small values aren't all equal")]
+    #[test_case(0.0, 1e-7, 1e-6, true                ; "zero falls back to absolute")]
+    #[test_case(0.0, 1e-5, 1e-6, false               ; "zero still has a tolerance")]
+    #[test_case(-2.0, 2.0, 1e-6, false               ; "opposite signs")]
+    fn test_approx_eq_rel(a: f64, b: f64, rel: f64, expected: bool) {
+        assert_eq!(approx_eq_rel(a, b, rel), expected);
+        assert_eq!(approx_eq_rel(b, a, rel), expected);
+    }
+
+    #[test]
+    fn test_relative_passes_where_absolute_fails() {
+        let (a, b, tolerance) = (1_000_000.0, 1_000_000.1, 1e-6);
+
+        assert!(approx_eq_rel(a, b, tolerance));
+        assert!(!approx_eq_eps(a, b, tolerance));
+    }
+
+    #[test_case(1.0, -3.0, 2.0, vec![1.0, 2.0] ; "two real roots")]
+    #[test_case(1.0, -2.0, 1.0, vec![1.0]      ; "repeated root")]
+    #[test_case(1.0, 0.0, 1.0, vec![]          ; "no real roots")]
+    #[test_case(0.0, 2.0, -4.0, vec![2.0]      ; "linear equation")]
+    #[test_case(0.0, 0.0, 1.0, vec![]          ; "degenerate equation")]
+    #[test_case(-1.0, 0.0, 4.0, vec![-2.0, 2.0] ; "negative leading coefficient")]
+    fn test_solve_quadratic(a: f64, b: f64, c: f64, expected: Vec<f64>) {
+        assert_eq!(solve_quadratic(a, b, c), expected);
+    }
+
+    fn assert_roots(roots: Roots, expected: Vec<f64>) {
+        assert_eq!(roots.len(), expected.len(), "{roots:?} != {expected:?}");
+        assert!(roots
+            .iter()
+            .zip(expected.iter())
+            .all(|(root, expected)| (root - expected).abs() < 1e-9));
+    }
+
+    #[test_case(-6.0, 11.0, -6.0, vec![1.0, 2.0, 3.0] ; "three distinct roots")]
+    #[test_case(0.0, 0.0, -8.0, vec![2.0]            ; "single real root")]
+    #[test_case(-3.0, 3.0, -1.0, vec![1.0]           ; "triple root")]
+    #[test_case(-4.0, 5.0, -2.0, vec![1.0, 2.0]      ; "single and double root")]
+    fn test_solve_cubic(a: f64, b: f64, c: f64, expected: Vec<f64>) {
+        assert_roots(solve_cubic(a, b, c), expected);
+    }
+
+    #[test_case(-10.0, 35.0, -50.0, 24.0, vec![1.0, 2.0, 3.0, 4.0] ; "four distinct roots")]
+    #[test_case(0.0, -5.0, 0.0, 4.0, vec![-2.0, -1.0, 1.0, 2.0]    ; "biquadratic roots")]
+    #[test_case(0.0, 0.0, 0.0, 1.0, vec![]                         ; "no real roots")]
+    #[test_case(-2.0, 2.0, -2.0, 1.0, vec![1.0]                    ; "double root with complex pair")]
+    #[test_case(-3.0, 3.0, -3.0, 2.0, vec![1.0, 2.0]               ; "two real roots with complex pair")]
+    fn test_solve_quartic(a: f64, b: f64, c: f64, d: f64, expected: Vec<f64>) {
+        assert_roots(solve_quartic(a, b, c, d), expected);
+    }
 }
@@ -1,11 +1,19 @@
 use std::{
-    cell::OnceCell,
     ops::{Add, Mul, Sub},
+    sync::OnceLock,
 };
 
-use color_eyre::eyre::{eyre, Result};
+use color_eyre::eyre::Result;
 
-use crate::lazy::Lazy;
+use crate::{
+    error::{self, RayTracerError},
+    json::{FromJson, Json, ToJson},
+    lazy::Lazy,
+    rng::Rng,
+};
+
+// Below this the sine of the angle between two directions is too small to divide by in slerp
+const SLERP_EPSILON: f64 = 1e-9;
 
 pub struct FromToVector3D {
     from: Vector3D,
@@ -16,15 +24,13 @@ impl FromToVector3D {
         destination - &self.from
     }
 
-    pub fn for_distance(self, distance: f64) -> Result<Vector3D> {
+    pub fn for_distance(self, distance: f64) -> error::Result<Vector3D> {
         let factor = distance + self.from.length();
         let total = &self.from * factor;
         let result = total - self.from;
 
         if result.length() == 0. {
-            return Err(eyre!(
-                "A vector of length 0 has no direction and can't be extended for a distance"
-            ));
+            return Err(RayTracerError::ZeroLengthVector);
         }
 
         Ok(result)
@@ -34,29 +40,33 @@ impl FromToVector3D {
         self,
         distance: f64,
         direction: &Vector3D,
-    ) -> Result<Vector3D> {
+    ) -> error::Result<Vector3D> {
+        if !distance.is_finite() {
+            return Err(RayTracerError::NonFiniteDistance(distance));
+        }
+
         let direction = direction.unit();
         let total = &direction * distance;
         let result = &self.from + &total;
 
         if result.length() == 0. {
-            return Err(eyre!(
-                "A vector of length 0 has no direction and can't be extended for a distance"
-            ));
+            return Err(RayTracerError::ZeroLengthVector);
         }
 
         Ok(result)
     }
 
-    pub fn to_distance_in_direction(self, distance: f64, direction: &Vector3D) -> Result<Vector3D> {
+    pub fn to_distance_in_direction(
+        self,
+        distance: f64,
+        direction: &Vector3D,
+    ) -> error::Result<Vector3D> {
         let direction = direction.unit();
         let total = &direction * distance;
         let result = &self.from + &total;
 
         if result.length() == 0. {
-            return Err(eyre!(
-                "A vector of length 0 has no direction and can't be extended for a distance"
-            ));
+            return Err(RayTracerError::ZeroLengthVector);
         }
 
         Ok(self.to(&result))
@@ -72,6 +82,27 @@ pub struct Vector3D {
     squid: Lazy<f64>,
 }
 
+// Only the components are stored, the cached lengths are recomputed on demand
+impl ToJson for Vector3D {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("x", self.x.to_json()),
+            ("y", self.y.to_json()),
+            ("z", self.z.to_json()),
+        ])
+    }
+}
+
+impl FromJson for Vector3D {
+    fn from_json(json: &Json) -> Result<Self> {
+        Ok(Vector3D::new(
+            json.get("x")?,
+            json.get("y")?,
+            json.get("z")?,
+        ))
+    }
+}
+
 impl PartialEq for Vector3D {
     fn eq(&self, other: &Self) -> bool {
         self.x == other.x && self.y == other.y && self.z == other.z
@@ -150,8 +181,8 @@ impl Vector3D {
             x,
             y,
             z,
-            len: Lazy::Lazy(OnceCell::new()),
-            squid: Lazy::Lazy(OnceCell::new()),
+            len: Lazy::Lazy(OnceLock::new()),
+            squid: Lazy::Lazy(OnceLock::new()),
         }
     }
 
@@ -167,14 +198,18 @@ impl Vector3D {
     }
 
     pub fn length(&self) -> f64 {
-        self.len
-            .get_or_init(self.len.get_or_init(self.squid().sqrt()))
+        self.len.get_or_init(|| self.squid().sqrt())
     }
 
     // "Squid" is a funny name for "Squared Euclidean distance"
     pub fn squid(&self) -> f64 {
-        self.squid
-            .get_or_init((self.x.abs()).powi(2) + (self.y.abs()).powi(2) + (self.z.abs()).powi(2))
+        self.squid.get_or_init(|| {
+            (self.x.abs()).powi(2) + (self.y.abs()).powi(2) + (self.z.abs()).powi(2)
+        })
+    }
+
+    pub fn distance_squared_to(&self, other: &Vector3D) -> f64 {
+        self.subtract(other).squid()
     }
 
     pub fn dot(&self, operand: &Vector3D) -> f64 {
@@ -186,19 +221,25 @@ impl Vector3D {
             x: self.y * operand.z - self.z * operand.y,
             y: self.z * operand.x - self.x * operand.z,
             z: self.x * operand.y - self.y * operand.x,
-            len: Lazy::Lazy(OnceCell::new()),
-            squid: Lazy::Lazy(OnceCell::new()),
+            len: Lazy::Lazy(OnceLock::new()),
+            squid: Lazy::Lazy(OnceLock::new()),
         }
     }
 
+    // Signed volume of the parallelepiped spanned by the three vectors, positive when they're
+    // right handed and zero when they lie in one plane
+    pub fn scalar_triple(&self, b: &Vector3D, c: &Vector3D) -> f64 {
+        self.dot(&b.cross(c))
+    }
+
     pub fn divide(&self, divisor: f64) -> Vector3D {
         if divisor == 0. {
             return Vector3D {
                 x: 0.,
                 y: 0.,
                 z: 0.,
-                len: Lazy::Lazy(OnceCell::new()),
-                squid: Lazy::Lazy(OnceCell::new()),
+                len: Lazy::Lazy(OnceLock::new()),
+                squid: Lazy::Lazy(OnceLock::new()),
             };
         }
 
@@ -206,22 +247,35 @@ impl Vector3D {
             x: self.x / divisor,
             y: self.y / divisor,
             z: self.z / divisor,
-            len: Lazy::Lazy(OnceCell::new()),
-            squid: Lazy::Lazy(OnceCell::new()),
+            len: Lazy::Lazy(OnceLock::new()),
+            squid: Lazy::Lazy(OnceLock::new()),
         }
     }
 
+    // The result's length is taken to be exactly 1 rather than worked out again, it's asked for
+    // a lot on ray directions
     pub fn unit(&self) -> Vector3D {
-        self.divide(self.length())
+        let length = self.length();
+
+        if length == 0. {
+            return self.divide(length);
+        }
+
+        Vector3D {
+            len: Lazy::Eager(1.),
+            squid: Lazy::Eager(1.),
+            ..self.divide(length)
+        }
     }
 
+    // Flipping a vector doesn't change its length, so it carries over
     pub fn invert(&self) -> Vector3D {
         Vector3D {
             x: -self.x,
             y: -self.y,
             z: -self.z,
-            len: Lazy::Lazy(OnceCell::new()),
-            squid: Lazy::Lazy(OnceCell::new()),
+            len: self.len.clone(),
+            squid: self.squid.clone(),
         }
     }
 
@@ -230,8 +284,8 @@ impl Vector3D {
             x: self.x + addend.x,
             y: self.y + addend.y,
             z: self.z + addend.z,
-            len: Lazy::Lazy(OnceCell::new()),
-            squid: Lazy::Lazy(OnceCell::new()),
+            len: Lazy::Lazy(OnceLock::new()),
+            squid: Lazy::Lazy(OnceLock::new()),
         }
     }
 
@@ -240,8 +294,8 @@ impl Vector3D {
             x: self.x - subtrahend.x,
             y: self.y - subtrahend.y,
             z: self.z - subtrahend.z,
-            len: Lazy::Lazy(OnceCell::new()),
-            squid: Lazy::Lazy(OnceCell::new()),
+            len: Lazy::Lazy(OnceLock::new()),
+            squid: Lazy::Lazy(OnceLock::new()),
         }
     }
 
@@ -250,9 +304,125 @@ impl Vector3D {
             x: self.x * factor,
             y: self.y * factor,
             z: self.z * factor,
-            len: Lazy::Lazy(OnceCell::new()),
-            squid: Lazy::Lazy(OnceCell::new()),
+            len: Lazy::Lazy(OnceLock::new()),
+            squid: Lazy::Lazy(OnceLock::new()),
+        }
+    }
+
+    // Uniformly distributed direction, by rejection sampling the unit cube for points inside the
+    // unit ball. Points too close to the center are thrown out too, they don't normalize well.
+    pub fn random_unit(rng: &mut impl Rng) -> Self {
+        loop {
+            let candidate = Vector3D::new(
+                2. * rng.next_f64() - 1.,
+                2. * rng.next_f64() - 1.,
+                2. * rng.next_f64() - 1.,
+            );
+            let squid = candidate.squid();
+
+            if squid <= 1. && squid > 1e-12 {
+                return candidate.unit();
+            }
+        }
+    }
+
+    // Uniformly distributed direction on the side of the surface the normal points to
+    pub fn random_in_hemisphere(normal: &Vector3D, rng: &mut impl Rng) -> Self {
+        let direction = Vector3D::random_unit(rng);
+
+        if direction.dot(normal) < 0. {
+            direction.invert()
+        } else {
+            direction
+        }
+    }
+
+    // Rotations about a single axis, counterclockwise looking down the axis towards the origin
+    // like Matrix4::rotation_x and friends. Angles are in radians.
+    pub fn rotate_x(&self, angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+
+        Vector3D::new(
+            self.x,
+            self.y * cos - self.z * sin,
+            self.y * sin + self.z * cos,
+        )
+    }
+
+    pub fn rotate_y(&self, angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+
+        Vector3D::new(
+            self.x * cos + self.z * sin,
+            self.y,
+            -self.x * sin + self.z * cos,
+        )
+    }
+
+    pub fn rotate_z(&self, angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+
+        Vector3D::new(
+            self.x * cos - self.y * sin,
+            self.x * sin + self.y * cos,
+            self.z,
+        )
+    }
+
+    // This vector if it's no longer than max, otherwise shortened to max in the same direction.
+    // A negative or NaN max counts as zero rather than flipping or poisoning the vector.
+    pub fn clamp_length(&self, max: f64) -> Self {
+        let max = max.max(0.);
+
+        if self.length() <= max {
+            return self.clone();
+        }
+
+        self.unit().scale(max)
+    }
+
+    // Straight line from this vector at t = 0 to the other one at t = 1
+    pub fn lerp(&self, other: &Vector3D, t: f64) -> Self {
+        self.append(&other.subtract(self).scale(t))
+    }
+
+    // Turns this unit vector towards the other one along the shortest arc at a steady rate. Nearly
+    // parallel vectors are lerped instead, and so are opposite ones, which have no single
+    // shortest arc.
+    pub fn slerp(&self, other: &Vector3D, t: f64) -> Self {
+        let angle = self.dot(other).clamp(-1., 1.).acos();
+        let sin = angle.sin();
+
+        if sin.abs() < SLERP_EPSILON {
+            return self.lerp(other, t);
+        }
+
+        self.scale(((1. - t) * angle).sin() / sin)
+            .append(&other.scale((t * angle).sin() / sin))
+    }
+
+    // Mirror this vector about a unit normal, as a ray bouncing off a surface would
+    pub fn reflect(&self, normal: &Vector3D) -> Self {
+        self.subtract(&normal.scale(2. * self.dot(normal)))
+    }
+
+    // Bend this unit vector through a surface following Snell's law, where the normal faces the
+    // incoming side and eta_ratio is the incoming over the outgoing refractive index. Returns
+    // None on total internal reflection.
+    pub fn refract(&self, normal: &Vector3D, eta_ratio: f64) -> Option<Self> {
+        let cos_incident = -self.dot(normal);
+        let sin_squared_transmitted = eta_ratio * eta_ratio * (1. - cos_incident * cos_incident);
+
+        if sin_squared_transmitted > 1. {
+            return None;
         }
+
+        let cos_transmitted = (1. - sin_squared_transmitted).sqrt();
+
+        Some(
+            self.scale(eta_ratio)
+                .append(&normal.scale(eta_ratio * cos_incident - cos_transmitted)),
+        )
     }
 
     pub fn to(&self, destination: &Vector3D) -> Self {
@@ -272,8 +442,8 @@ impl From<&Vector3D> for Vector3D {
             x: value.x,
             y: value.y,
             z: value.z,
-            len: Lazy::Lazy(OnceCell::new()),
-            squid: Lazy::Lazy(OnceCell::new()),
+            len: Lazy::Lazy(OnceLock::new()),
+            squid: Lazy::Lazy(OnceLock::new()),
         }
     }
 }
@@ -310,11 +480,63 @@ pub const O: Vector3D = Vector3D {
     squid: Lazy::Eager(0.),
 };
 
+// Point or direction in a flat space like a surface's UV coordinates. Unlike Vector3D there's no
+// length cache, two components are cheap enough to recompute.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Vector2D {
+    x: f64,
+    y: f64,
+}
+
+impl Vector2D {
+    pub const fn new(x: f64, y: f64) -> Self {
+        Vector2D { x, y }
+    }
+
+    pub fn x(&self) -> f64 {
+        self.x
+    }
+
+    pub fn y(&self) -> f64 {
+        self.y
+    }
+
+    pub fn length(&self) -> f64 {
+        self.squid().sqrt()
+    }
+
+    pub fn squid(&self) -> f64 {
+        self.dot(self)
+    }
+
+    pub fn dot(&self, operand: &Vector2D) -> f64 {
+        self.x * operand.x + self.y * operand.y
+    }
+
+    pub fn add(&self, addend: &Vector2D) -> Self {
+        Vector2D::new(self.x + addend.x, self.y + addend.y)
+    }
+
+    pub fn subtract(&self, subtrahend: &Vector2D) -> Self {
+        Vector2D::new(self.x - subtrahend.x, self.y - subtrahend.y)
+    }
+
+    pub fn scale(&self, factor: f64) -> Self {
+        Vector2D::new(self.x * factor, self.y * factor)
+    }
+}
+
+impl From<(f64, f64)> for Vector2D {
+    fn from((x, y): (f64, f64)) -> Self {
+        Vector2D::new(x, y)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::f64::consts::PI;
 
-    use crate::utils::approx_eq;
+    use crate::{matrix::Matrix4, rng::XorShift, utils::approx_eq};
 
     use super::*;
 
@@ -331,6 +553,26 @@ mod tests {
         assert!(approx_eq(v.z(), z));
     }
 
+    #[test_case(3.0, 4.0, 12.0, 13.0 ; "threads race to cache the length")]
+    fn test_length_shared_between_threads(x: f64, y: f64, z: f64, expected: f64) {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Vector3D>();
+
+        let vector = Vector3D::new(x, y, z);
+
+        let lengths: Vec<f64> = std::thread::scope(|scope| {
+            let readers: Vec<_> = (0..8).map(|_| scope.spawn(|| vector.length())).collect();
+
+            readers
+                .into_iter()
+                .map(|reader| reader.join().unwrap())
+                .collect()
+        });
+
+        assert!(lengths.iter().all(|length| approx_eq(*length, expected)));
+        assert!(approx_eq(vector.length(), expected));
+    }
+
     #[test_case(2.0, -3.0, 6.0, 49.0         ; "squared length = 49")]
     #[test_case(0.0, 0.0, 0.0, 0.0           ; "squared length of zero = 0")]
     #[test_case(1.0, 1.0, 1.0, 3.0           ; "squared length of (1,1,1) = 3")]
@@ -369,6 +611,184 @@ mod tests {
         assert!(approx_eq(c.z(), cz));
     }
 
+    #[test_case((1.0, 0.0, 0.0), (0.0, 1.0, 0.0), (0.0, 0.0, 1.0), 1.0  ; "unit axes span a unit cube")]
+    #[test_case((0.0, 1.0, 0.0), (1.0, 0.0, 0.0), (0.0, 0.0, 1.0), -1.0 ; "swapping two axes flips the sign")]
+    #[test_case((1.0, 2.0, 0.0), (-3.0, 1.0, 0.0), (5.0, 5.0, 0.0), 0.0 ; "vectors in the xy plane")]
+    #[test_case((1.0, 2.0, 3.0), (2.0, 4.0, 6.0), (-1.0, 0.5, 2.0), 0.0 ; "parallel pair is coplanar")]
+    fn test_scalar_triple(
+        a: (f64, f64, f64),
+        b: (f64, f64, f64),
+        c: (f64, f64, f64),
+        expected: f64,
+    ) {
+        let a = Vector3D::new(a.0, a.1, a.2);
+        let b = Vector3D::new(b.0, b.1, b.2);
+        let c = Vector3D::new(c.0, c.1, c.2);
+
+        assert!(approx_eq(a.scalar_triple(&b, &c), expected));
+    }
+
+    #[test_case((1.0, 0.0, 0.0), (0.0, 1.0, 0.0)  ; "x axis turns onto y")]
+    #[test_case((0.0, 1.0, 0.0), (-1.0, 0.0, 0.0) ; "y axis turns onto negative x")]
+    #[test_case((1.0, 1.0, 7.0), (-1.0, 1.0, 7.0) ; "z component is left alone")]
+    fn test_rotate_z_quarter_turn(vector: (f64, f64, f64), expected: (f64, f64, f64)) {
+        let rotated = Vector3D::new(vector.0, vector.1, vector.2).rotate_z(PI / 2.);
+
+        assert!(approx_eq(rotated.x(), expected.0));
+        assert!(approx_eq(rotated.y(), expected.1));
+        assert_eq!(rotated.z(), expected.2);
+    }
+
+    #[test_case((3.0, 4.0, 12.0) ; "off axis")]
+    #[test_case((0.0, 0.0, -2.0) ; "along an axis")]
+    fn test_ray_direction_length_is_cached(direction: (f64, f64, f64)) {
+        let ray = crate::ray::Ray::new(
+            &Vector3D::new(1.0, 2.0, 3.0),
+            &Vector3D::new(direction.0, direction.1, direction.2),
+        );
+
+        assert!(ray.direction.len.is_initialized());
+        assert_eq!(ray.direction.length(), 1.0);
+        assert!(approx_eq(
+            ray.direction.dot(&ray.direction).sqrt(),
+            ray.direction.length()
+        ));
+        assert!(ray.direction.invert().len.is_initialized());
+    }
+
+    #[test]
+    fn test_length_is_worked_out_once() {
+        let vector = Vector3D::new(3.0, 4.0, 0.0);
+
+        assert!(!vector.len.is_initialized());
+        assert_eq!(vector.length(), 5.0);
+        assert!(vector.len.is_initialized() && vector.squid.is_initialized());
+    }
+
+    #[test_case((1.0, 2.0, 2.0), 5.0 ; "shorter than the cap")]
+    #[test_case((0.0, 3.0, 4.0), 5.0 ; "exactly at the cap")]
+    #[test_case((0.0, 0.0, 0.0), 1.0 ; "zero vector")]
+    fn test_clamp_length_leaves_short_vectors(vector: (f64, f64, f64), max: f64) {
+        let vector = Vector3D::new(vector.0, vector.1, vector.2);
+
+        assert_eq!(vector.clamp_length(max), vector);
+    }
+
+    #[test_case((0.0, 30.0, 40.0), 5.0 ; "along an axis plane")]
+    #[test_case((-7.0, 2.0, 11.0), 0.5 ; "off axis")]
+    fn test_clamp_length_shortens_long_vectors(vector: (f64, f64, f64), max: f64) {
+        let vector = Vector3D::new(vector.0, vector.1, vector.2);
+
+        let clamped = vector.clamp_length(max);
+
+        assert!(approx_eq(clamped.length(), max));
+        assert!(approx_eq(clamped.unit().dot(&vector.unit()), 1.0));
+    }
+
+    #[test_case(-2.0     ; "negative")]
+    #[test_case(f64::NAN ; "not a number")]
+    fn test_clamp_length_invalid_max_gives_zero(max: f64) {
+        let clamped = Vector3D::new(1.0, -2.0, 3.0).clamp_length(max);
+
+        assert_eq!(clamped, Vector3D::new(0.0, 0.0, 0.0));
+    }
+
+    #[test_case(0.0, (1.0, 2.0, 3.0)  ; "start")]
+    #[test_case(0.5, (2.0, 1.0, 3.5)  ; "midway")]
+    #[test_case(1.0, (3.0, 0.0, 4.0)  ; "end")]
+    fn test_lerp(t: f64, expected: (f64, f64, f64)) {
+        let a = Vector3D::new(1.0, 2.0, 3.0);
+        let b = Vector3D::new(3.0, 0.0, 4.0);
+
+        assert_eq!(
+            a.lerp(&b, t),
+            Vector3D::new(expected.0, expected.1, expected.2)
+        );
+    }
+
+    #[test_case(0.5, (0.5, 0.5) ; "midway bisects the angle")]
+    #[test_case(1. / 3., (0.75, 0.25) ; "a third of the way turns a third of the angle")]
+    fn test_slerp_stays_on_the_unit_circle(t: f64, expected_squares: (f64, f64)) {
+        let x = Vector3D::new(1.0, 0.0, 0.0);
+        let y = Vector3D::new(0.0, 1.0, 0.0);
+
+        let turned = x.slerp(&y, t);
+
+        assert!(approx_eq(turned.length(), 1.0));
+        assert!(approx_eq(turned.x() * turned.x(), expected_squares.0));
+        assert!(approx_eq(turned.y() * turned.y(), expected_squares.1));
+        assert_eq!(x.slerp(&y, 0.0), x);
+    }
+
+    #[test_case(0.3 ; "small angle")]
+    #[test_case(-2.1 ; "negative angle")]
+    fn test_axis_rotations_match_matrices(angle: f64) {
+        let vector = Vector3D::new(1.5, -2.0, 0.5);
+        let rotations = [
+            (vector.rotate_x(angle), Matrix4::rotation_x(angle)),
+            (vector.rotate_y(angle), Matrix4::rotation_y(angle)),
+            (vector.rotate_z(angle), Matrix4::rotation_z(angle)),
+        ];
+
+        for (rotated, matrix) in rotations {
+            let expected = matrix.transform_vector(&vector);
+
+            assert!(approx_eq(rotated.x(), expected.x()));
+            assert!(approx_eq(rotated.y(), expected.y()));
+            assert!(approx_eq(rotated.z(), expected.z()));
+            assert!(approx_eq(rotated.length(), vector.length()));
+        }
+    }
+
+    #[test_case(1 ; "first seed")]
+    #[test_case(42 ; "another seed")]
+    fn test_random_unit_is_uniform(seed: u64) {
+        let mut rng = XorShift::with_seed(seed);
+        let samples: Vec<Vector3D> = (0..10_000)
+            .map(|_| Vector3D::random_unit(&mut rng))
+            .collect();
+
+        let sum = samples.iter().fold(O, |sum, sample| &sum + sample);
+        let mean = sum.divide(samples.len() as f64);
+
+        assert!(samples.iter().all(|sample| approx_eq(sample.length(), 1.0)));
+        // Each component has a variance of a third, so the mean of 10k is within 0.02 or so
+        assert!(mean.length() < 0.03, "{mean}");
+    }
+
+    #[test_case((0.0, 1.0, 0.0) ; "up")]
+    #[test_case((1.0, -1.0, 2.0) ; "tilted")]
+    fn test_random_in_hemisphere(normal: (f64, f64, f64)) {
+        let normal = Vector3D::new(normal.0, normal.1, normal.2).unit();
+        let mut rng = XorShift::with_seed(7);
+
+        let cosines: Vec<f64> = (0..10_000)
+            .map(|_| Vector3D::random_in_hemisphere(&normal, &mut rng).dot(&normal))
+            .collect();
+        let mean = cosines.iter().sum::<f64>() / cosines.len() as f64;
+
+        assert!(cosines.iter().all(|cosine| *cosine >= 0.));
+        // Uniform over the hemisphere the cosine averages one half
+        assert!((mean - 0.5).abs() < 0.02, "{mean}");
+    }
+
+    #[test_case((3.0, 0.0, 0.0), (0.0, 4.0, 0.0), 5.0 ; "lengths are recomputed after appending")]
+    fn test_append_resets_cached_lengths(a: (f64, f64, f64), b: (f64, f64, f64), expected: f64) {
+        let a = Vector3D::new(a.0, a.1, a.2);
+        let b = Vector3D::new(b.0, b.1, b.2);
+
+        // Fill the caches before appending
+        assert!(approx_eq(a.length(), 3.0));
+        assert!(approx_eq(a.squid(), 9.0));
+
+        let appended = a.append(&b);
+        let added = &a + &b;
+
+        assert_eq!(appended, added);
+        assert!(approx_eq(appended.length(), expected));
+        assert!(approx_eq(appended.squid(), expected * expected));
+    }
+
     #[allow(clippy::too_many_arguments)]
     #[test_case(1.0, 2.0, 3.0, -1.0, 4.0, 0.5, 0.0, 6.0, 3.5 ; "add vectors")]
     #[test_case(0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0 ; "add zero vector")]
@@ -390,7 +810,6 @@ mod tests {
         assert!(approx_eq(sum.z(), sz));
     }
 
-    #[allow(clippy::too_many_arguments)]
     #[allow(clippy::too_many_arguments)]
     #[test_case(1.0, 2.0, 3.0, -1.0, 4.0, 0.5, 2.0, -2.0, 2.5 ; "subtract vectors")]
     #[test_case(1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0 ; "subtract same")]
@@ -471,6 +890,51 @@ mod tests {
         assert!(approx_eq(inv.z(), iz));
     }
 
+    #[test_case((1.0, -1.0, 0.0), (0.0, 1.0, 0.0), (1.0, 1.0, 0.0)  ; "reflect off floor flips y")]
+    #[test_case((0.0, 0.0, 1.0), (0.0, 0.0, -1.0), (0.0, 0.0, -1.0) ; "head on reflection reverses")]
+    #[test_case((1.0, 0.0, 0.0), (0.0, 1.0, 0.0), (1.0, 0.0, 0.0)   ; "grazing vector is unchanged")]
+    fn test_reflect(v: (f64, f64, f64), normal: (f64, f64, f64), expected: (f64, f64, f64)) {
+        let reflected =
+            Vector3D::new(v.0, v.1, v.2).reflect(&Vector3D::new(normal.0, normal.1, normal.2));
+        assert!(approx_eq(reflected.x(), expected.0));
+        assert!(approx_eq(reflected.y(), expected.1));
+        assert!(approx_eq(reflected.z(), expected.2));
+    }
+
+    #[test_case((0.0, -1.0, 0.0), 1.5, Some((0.0, -1.0, 0.0)) ; "head on ray passes straight through")]
+    #[test_case((1.0, -1.0, 0.0), 1.0, Some((1.0, -1.0, 0.0))  ; "matching indices don't bend")]
+    #[test_case((1.0, -1.0, 0.0), 1.5, None                    ; "steep exit is totally internally reflected")]
+    fn test_refract(v: (f64, f64, f64), eta_ratio: f64, expected: Option<(f64, f64, f64)>) {
+        let refracted = Vector3D::new(v.0, v.1, v.2)
+            .unit()
+            .refract(&Vector3D::new(0.0, 1.0, 0.0), eta_ratio);
+
+        match (refracted, expected) {
+            (Some(refracted), Some(expected)) => {
+                let expected = Vector3D::new(expected.0, expected.1, expected.2).unit();
+                assert!((refracted.x() - expected.x()).abs() < 1e-12);
+                assert!((refracted.y() - expected.y()).abs() < 1e-12);
+                assert!((refracted.z() - expected.z()).abs() < 1e-12);
+            }
+            (refracted, expected) => assert_eq!(refracted.is_some(), expected.is_some()),
+        }
+    }
+
+    #[test_case(1.0 / 1.5 ; "entering glass bends towards the normal")]
+    fn test_refract_obeys_snells_law(eta_ratio: f64) {
+        let incoming = Vector3D::new(1.0, -1.0, 0.0).unit();
+        let refracted = incoming
+            .refract(&Vector3D::new(0.0, 1.0, 0.0), eta_ratio)
+            .unwrap();
+
+        let sin_incident = incoming.x();
+        let sin_transmitted = refracted.x();
+
+        assert!(approx_eq(refracted.length(), 1.0));
+        assert!((sin_incident * eta_ratio - sin_transmitted).abs() < 1e-12);
+        assert!(refracted.y() < 0.0);
+    }
+
     #[test_case(1.0, 2.0, 3.0, 4.0, -1.0, 5.0 ; "to() yields origin - dest")]
     fn test_to_method(ox: f64, oy: f64, oz: f64, dx: f64, dy: f64, dz: f64) {
         let origin = Vector3D::new(ox, oy, oz);
@@ -508,6 +972,35 @@ mod tests {
         assert!(approx_eq(v.z(), dz - oz));
     }
 
+    #[test_case((0.0, 0.0, 0.0), (1.0, 0.0, 0.0), 5.0, (5.0, 0.0, 0.0)  ; "five units along +X from the origin")]
+    #[test_case((0.0, 0.0, 0.0), (7.0, 0.0, 0.0), 5.0, (5.0, 0.0, 0.0)  ; "direction is normalized first")]
+    #[test_case((1.0, 2.0, 3.0), (0.0, -2.0, 0.0), 1.5, (1.0, 0.5, 3.0) ; "walk from an offset start")]
+    fn test_for_distance_in_direction(
+        start: (f64, f64, f64),
+        direction: (f64, f64, f64),
+        distance: f64,
+        expected: (f64, f64, f64),
+    ) {
+        let v = Vector3D::from(&Vector3D::new(start.0, start.1, start.2))
+            .for_distance_in_direction(
+                distance,
+                &Vector3D::new(direction.0, direction.1, direction.2),
+            )
+            .unwrap();
+
+        assert_eq!(v, Vector3D::new(expected.0, expected.1, expected.2));
+    }
+
+    #[test_case(f64::NAN          ; "NaN distance")]
+    #[test_case(f64::INFINITY     ; "infinite distance")]
+    #[test_case(f64::NEG_INFINITY ; "negative infinite distance")]
+    fn test_for_distance_in_direction_rejects_non_finite(distance: f64) {
+        let result = Vector3D::from(&Vector3D::new(1.0, 0.0, 0.0))
+            .for_distance_in_direction(distance, &Vector3D::new(1.0, 0.0, 0.0));
+
+        assert!(matches!(result, Err(RayTracerError::NonFiniteDistance(_))));
+    }
+
     #[test]
     fn test_constants_and_display() {
         // X, Y, Z, O
@@ -530,4 +1023,31 @@ mod tests {
         assert!(s.contains("y: -4.56"));
         assert!(s.contains("z: 7.89"));
     }
+
+    #[test_case(3.0, 4.0, 5.0              ; "length = 5")]
+    #[test_case(0.0, 0.0, 0.0              ; "length of zero vector = 0")]
+    #[test_case(1.0, 1.0, 2f64.sqrt()      ; "length of (1,1) = sqrt(2)")]
+    fn test_length_2d(x: f64, y: f64, expected: f64) {
+        let v = Vector2D::new(x, y);
+        assert!(approx_eq(v.length(), expected));
+        assert!(approx_eq(v.squid(), expected * expected));
+    }
+
+    #[test_case(1.0, 2.0, -2.0, 0.5, -1.0 ; "dot product = -1")]
+    #[test_case(1.0, 0.0, 0.0, 1.0, 0.0   ; "orthogonal vectors dot = 0")]
+    #[test_case(3.0, 4.0, 3.0, 4.0, 25.0  ; "self dot = squared length")]
+    fn test_dot_2d(ax: f64, ay: f64, bx: f64, by: f64, expected: f64) {
+        let a = Vector2D::new(ax, ay);
+        let b = Vector2D::new(bx, by);
+        assert!(approx_eq(a.dot(&b), expected));
+    }
+
+    #[test_case((1.0, 2.0), (-1.0, 4.0) ; "add and subtract round trip")]
+    fn test_add_subtract_scale_2d(a: (f64, f64), b: (f64, f64)) {
+        let (a, b) = (Vector2D::from(a), Vector2D::from(b));
+
+        assert_eq!(a.add(&b), Vector2D::new(0.0, 6.0));
+        assert_eq!(a.add(&b).subtract(&b), a);
+        assert_eq!(a.scale(2.0), a.add(&a));
+    }
 }